@@ -1,7 +1,10 @@
-use super::plugin::{Plugin, PLUGIN_ARGS};
+use super::plugin::{plugin_args_env, plugin_env, Plugin};
 use crate::CrateFilter;
 use cargo_metadata::camino::Utf8Path;
+use cargo_metadata::PackageId;
 use cargo_util_schemas::manifest::PackageName;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::Hash;
 use std::ops::Index;
 use std::{
     env, fs,
@@ -14,6 +17,22 @@ pub const RUN_ON_ALL_CRATES: &str = "RUSTC_PLUGIN_ALL_TARGETS";
 pub const SPECIFIC_CRATE: &str = "SPECIFIC_CRATE";
 pub const SPECIFIC_TARGET: &str = "SPECIFIC_TARGET";
 pub const CARGO_VERBOSE: &str = "CARGO_VERBOSE";
+// Names of the variables that pass the selection of `RustcPluginArgs` to the drivers,
+// turned into e.g. `CG4RS_PLUGIN_CRATE_TYPES` by `plugin_env` so that plugins with different
+// driver names can run in the same build
+pub const INCLUDE_BUILD_SCRIPTS: &str = "BUILD_SCRIPTS";
+/// Set to run the plugin on proc-macro crates as well
+pub const ANALYZE_PROC_MACROS: &str = "PROC_MACROS";
+/// Comma-separated crate types to run the plugin on, `test` stands for test harnesses
+pub const CRATE_TYPES: &str = "CRATE_TYPES";
+/// Comma-separated features of the combination being analyzed, set for each feature-combination run
+pub const FEATURE_COMBO: &str = "FEATURE_COMBO";
+/// File that drivers append their exit codes to, see `report_exit_code`
+pub const EXIT_CODE_FILE: &str = "EXIT_CODE_FILE";
+/// Comma-separated `crate_name:depth` of every crate in the dependency graph, see `crate_depths`
+pub const CRATE_DEPTHS: &str = "CRATE_DEPTHS";
+/// Maximum dependency depth of the crates to run the plugin on
+pub const DEPS_DEPTH: &str = "DEPS_DEPTH";
 
 /// Main entry point for the cargo-side CLI tool
 pub fn cargo_main<T: Plugin>(plugin: T) {
@@ -33,6 +52,7 @@ pub fn cargo_main<T: Plugin>(plugin: T) {
 
     // Collect plugin arguments
     let args = plugin.args(&target_dir);
+    let driver_name = plugin.driver_name();
 
     // Create the `cargo` command
     let mut cmd = Command::new("cargo");
@@ -42,7 +62,7 @@ pub fn cargo_main<T: Plugin>(plugin: T) {
     // i.e. dir_path/cg4rs
     let mut path = env::current_exe()
         .expect("current executable path invalid")
-        .with_file_name(plugin.driver_name().as_ref());
+        .with_file_name(driver_name.as_ref());
     path.set_extension(
         env::current_exe()
             .unwrap()
@@ -77,26 +97,74 @@ pub fn cargo_main<T: Plugin>(plugin: T) {
         .collect::<Vec<_>>();
 
     // Decide how to run the plugin based on the filter type
-    match args.filter {
+    match &args.filter {
         CrateFilter::CrateContainingFile(file_path) => {
-            only_run_on_file(&mut cmd, file_path, &workspace_members, &target_dir);
+            only_run_on_file(&mut cmd, file_path.clone(), &workspace_members, &target_dir);
         }
-        CrateFilter::AllCrates | CrateFilter::OnlyWorkspace => {
+        CrateFilter::PackagesContainingFiles(files) => {
+            let packages = packages_containing_files(files, &workspace_members);
+            if packages.is_empty() {
+                tracing::info!("No workspace package contains a changed file, nothing to analyze");
+                exit(0);
+            }
+            for pkg in packages {
+                tracing::debug!("Selecting changed package {}", pkg.name);
+                cmd.arg("-p").arg(format!("{}:{}", pkg.name, pkg.version));
+            }
+        }
+        CrateFilter::OnlyWorkspace => {
             cmd.arg("--all");
-            match args.filter {
-                CrateFilter::AllCrates => {
-                    cmd.env(RUN_ON_ALL_CRATES, "");
-                }
-                CrateFilter::OnlyWorkspace => {}
-                CrateFilter::CrateContainingFile(_) => unreachable!(),
+        }
+        CrateFilter::AllCrates | CrateFilter::WithFeatures(_) | CrateFilter::ExcludeFeatures(_) => {
+            cmd.arg("--all").env(RUN_ON_ALL_CRATES, "");
+        }
+    }
+    add_feature_args(&mut cmd, &args.filter, &default_features(&workspace_members));
+
+    // Build scripts are compiled by `cargo check` anyway; let the driver know to analyze them
+    if args.include_build_scripts {
+        cmd.env(plugin_env(&driver_name, INCLUDE_BUILD_SCRIPTS), "");
+    }
+
+    // Run on every crate so that the proc macros of dependencies are analyzed too
+    if args.analyze_proc_macros {
+        cmd.env(RUN_ON_ALL_CRATES, "")
+            .env(plugin_env(&driver_name, ANALYZE_PROC_MACROS), "");
+    }
+
+    // Restrict the plugin to the requested crate types
+    if !args.crate_types.is_empty() {
+        cmd.env(plugin_env(&driver_name, CRATE_TYPES), args.crate_types.join(","));
+    }
+
+    // Only run on the dependencies close to the workspace members
+    if let Some(deps_depth) = args.deps_depth {
+        match dependency_metadata() {
+            Ok(metadata) => {
+                let depths = crate_depths(&metadata);
+                let encoded: Vec<String> = depths.iter().map(|(name, depth)| format!("{name}:{depth}")).collect();
+                cmd.env(plugin_env(&driver_name, CRATE_DEPTHS), encoded.join(","))
+                    .env(plugin_env(&driver_name, DEPS_DEPTH), deps_depth.to_string());
             }
+            Err(e) => tracing::error!("Failed to resolve the dependency graph, ignoring --deps-depth: {e}"),
         }
     }
 
+    // Cross-compile for a specific target triple
+    if let Some(target) = &args.target {
+        cmd.arg("--target").arg(target);
+    }
+
+    // Collect the exit codes reported by the drivers of this run
+    let exit_code_file = target_dir.join("plugin-exit-codes");
+    let _ = fs::remove_file(&exit_code_file);
+    cmd.env(plugin_env(&driver_name, EXIT_CODE_FILE), &exit_code_file);
+
     // Serialize plugin arguments to JSON and pass them via the environment
     let args_str = serde_json::to_string(&args.plugin_args).unwrap();
-    tracing::debug!("{PLUGIN_ARGS}={args_str}");
-    cmd.env(PLUGIN_ARGS, args_str);
+    let plugin_args_var = plugin_args_env(&driver_name);
+    tracing::debug!("{plugin_args_var}={args_str}");
+    cmd.env(plugin_args_var, args_str);
 
     // Special handling for rustc workspace builds
     if workspace_members
@@ -109,11 +177,216 @@ pub fn cargo_main<T: Plugin>(plugin: T) {
     // Allow the plugin to modify the cargo command
     plugin.modify_cargo(&mut cmd, &args.cargo_args);
 
-    tracing::info!("Start to Exec: {:?}", cmd);
-    // Execute the cargo command and exit with its status
-    let exit_status = cmd.status().expect("failed to wait for cargo?");
-    tracing::info!("Finish to Exec {:?}", cmd);
-    exit(exit_status.code().unwrap_or(-1));
+    let feature_combos = if args.all_feature_combos {
+        all_feature_combos(&workspace_members)
+    } else {
+        args.feature_combos
+    };
+
+    if feature_combos.is_empty() {
+        tracing::info!("Start to Exec: {:?}", cmd);
+        // Execute the cargo command and exit with its status
+        let exit_status = cmd.status().expect("failed to wait for cargo?");
+        tracing::info!("Finish to Exec {:?}", cmd);
+        if !exit_status.success() {
+            exit(exit_status.code().unwrap_or(-1));
+        }
+        exit(reported_exit_code(&exit_code_file));
+    }
+
+    // Run `cargo check` once per feature combination, with exactly the given features enabled
+    for combo in &feature_combos {
+        let mut combo_cmd = clone_command(&cmd);
+        combo_cmd
+            .arg("--no-default-features")
+            .arg("--features")
+            .arg(combo.join(","))
+            .env(plugin_env(&driver_name, FEATURE_COMBO), combo.join(","));
+
+        tracing::info!("Start to Exec for features [{}]: {:?}", combo.join(","), combo_cmd);
+        let exit_status = combo_cmd.status().expect("failed to wait for cargo?");
+        tracing::info!("Finish to Exec {:?}", combo_cmd);
+        if !exit_status.success() {
+            exit(exit_status.code().unwrap_or(-1));
+        }
+    }
+    exit(reported_exit_code(&exit_code_file));
+}
+
+/// Exit code to report for the codes written by the drivers, or 0 if none was reported
+///
+/// Failures (codes of 100 and above, e.g. 101 for a panic) take precedence over the condition
+/// codes 1-5, so a crash in one crate is not hidden by a finding in another. Among codes of the
+/// same class, the lowest one wins.
+fn reported_exit_code(exit_code_file: &Utf8Path) -> i32 {
+    fs::read_to_string(exit_code_file)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.trim().parse::<i32>().ok())
+        .filter(|&code| code != 0)
+        .min_by_key(|&code| (code < 100, code))
+        .unwrap_or(0)
+}
+
+/// Add the cargo arguments selecting the features requested by `filter`
+fn add_feature_args(cmd: &mut Command, filter: &CrateFilter, default_features: &[String]) {
+    match filter {
+        CrateFilter::WithFeatures(features) if !features.is_empty() => {
+            cmd.arg("--features").arg(features.join(","));
+        }
+        CrateFilter::ExcludeFeatures(excluded) => {
+            cmd.arg("--no-default-features");
+            let remaining: Vec<&str> = default_features
+                .iter()
+                .filter(|feature| !excluded.contains(*feature))
+                .map(String::as_str)
+                .collect();
+            if !remaining.is_empty() {
+                cmd.arg("--features").arg(remaining.join(","));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Default features of the workspace members, without optional dependencies (`dep:name`)
+fn default_features(workspace_members: &[&cargo_metadata::Package]) -> Vec<String> {
+    let mut features: Vec<String> = workspace_members
+        .iter()
+        .filter_map(|pkg| pkg.features.get("default"))
+        .flatten()
+        .filter(|feature| !feature.starts_with("dep:"))
+        .cloned()
+        .collect();
+    features.sort();
+    features.dedup();
+    features
+}
+
+/// Most features `--all-feature-combos` enumerates the subsets of, i.e. at most 4096 runs
+const MAX_ALL_COMBOS_FEATURES: usize = 12;
+
+/// Enumerate every subset of the features declared by the workspace members
+///
+/// The number of runs is exponential in the number of features, so this exits with an error
+/// when there are more than [`MAX_ALL_COMBOS_FEATURES`].
+fn all_feature_combos(workspace_members: &[&cargo_metadata::Package]) -> Vec<Vec<String>> {
+    let mut features: Vec<String> = workspace_members
+        .iter()
+        .flat_map(|pkg| pkg.features.keys())
+        .filter(|feature| feature.as_str() != "default")
+        .cloned()
+        .collect();
+    features.sort();
+    features.dedup();
+
+    let Some(combos) = feature_subsets(&features) else {
+        tracing::error!(
+            "Refusing to enumerate all combinations of {} features (at most {MAX_ALL_COMBOS_FEATURES}), \
+             pass the combinations to analyze with --feature-combo instead",
+            features.len()
+        );
+        exit(1);
+    };
+    if features.len() > 10 {
+        tracing::warn!(
+            "Enumerating all {} combinations of {} features, this may take a long time",
+            combos.len(),
+            features.len()
+        );
+    }
+    combos
+}
+
+/// Every subset of `features`, or `None` if there are more than [`MAX_ALL_COMBOS_FEATURES`]
+fn feature_subsets(features: &[String]) -> Option<Vec<Vec<String>>> {
+    if features.len() > MAX_ALL_COMBOS_FEATURES {
+        return None;
+    }
+    let combos = (0..1u32 << features.len())
+        .map(|mask| {
+            features
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, feature)| feature.clone())
+                .collect()
+        })
+        .collect();
+    Some(combos)
+}
+
+/// Dependency depth of every crate in the resolved dependency graph, by crate name
+///
+/// Workspace members have depth 0, their direct dependencies 1, and so on. Crate names are the
+/// target names with `-` replaced by `_`, as passed to rustc with `--crate-name`. A crate
+/// reachable on several paths, or in several versions, keeps its smallest depth.
+fn crate_depths(metadata: &cargo_metadata::Metadata) -> BTreeMap<String, usize> {
+    let Some(resolve) = &metadata.resolve else {
+        return BTreeMap::new();
+    };
+    let dependencies: HashMap<&PackageId, &[PackageId]> = resolve
+        .nodes
+        .iter()
+        .map(|node| (&node.id, node.dependencies.as_slice()))
+        .collect();
+    let package_depths = bfs_depths(&metadata.workspace_members, |id| {
+        dependencies.get(id).copied().unwrap_or_default()
+    });
+
+    let mut depths = BTreeMap::new();
+    for (id, depth) in package_depths {
+        for target in &metadata.index(id).targets {
+            let entry = depths.entry(target.name.replace('-', "_")).or_insert(depth);
+            *entry = (*entry).min(depth);
+        }
+    }
+    depths
+}
+
+/// Breadth-first distance from `roots` of every node reachable from them
+fn bfs_depths<'a, T: Eq + Hash>(roots: &'a [T], neighbors: impl Fn(&T) -> &'a [T]) -> HashMap<&'a T, usize> {
+    let mut depths: HashMap<&T, usize> = roots.iter().map(|root| (root, 0)).collect();
+    let mut queue: VecDeque<&T> = roots.iter().collect();
+    while let Some(node) = queue.pop_front() {
+        let depth = depths[node] + 1;
+        for next in neighbors(node) {
+            if !depths.contains_key(next) {
+                depths.insert(next, depth);
+                queue.push_back(next);
+            }
+        }
+    }
+    depths
+}
+
+/// Metadata with the resolved dependency graph, which `build_metadata_command` leaves out
+///
+/// Uses the same options as `build_metadata_command` apart from `--no-deps`.
+fn dependency_metadata() -> cargo_metadata::Result<cargo_metadata::Metadata> {
+    let mut cmd = cargo_metadata::MetadataCommand::new();
+    cmd.other_options(["--all-features".to_string(), "--offline".to_string()]);
+    if let Some(manifest_path) = find_manifest_path() {
+        cmd.manifest_path(manifest_path);
+    }
+    cmd.exec()
+}
+
+/// Copy program, arguments, environment and working directory of a command
+fn clone_command(cmd: &Command) -> Command {
+    let mut cloned = Command::new(cmd.get_program());
+    cloned.args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => cloned.env(key, value),
+            None => cloned.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        cloned.current_dir(dir);
+    }
+    cloned.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    cloned
 }
 
 /// Build the metadata command
@@ -151,6 +424,38 @@ fn find_manifest_path() -> Option<String> {
     None
 }
 
+/// Workspace packages containing at least one of `files`, in workspace order
+///
+/// A file belongs to the package with the innermost manifest directory above it, so files of
+/// a nested package are not attributed to the package around it.
+fn packages_containing_files<'a>(
+    files: &[PathBuf],
+    workspace_members: &[&'a cargo_metadata::Package],
+) -> Vec<&'a cargo_metadata::Package> {
+    let package_dirs: Vec<(PathBuf, &cargo_metadata::Package)> = workspace_members
+        .iter()
+        .filter_map(|pkg| {
+            let dir = pkg.manifest_path.parent()?.as_std_path();
+            Some((dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()), *pkg))
+        })
+        .collect();
+    let owners: Vec<&cargo_metadata::PackageId> = files
+        .iter()
+        .filter_map(|file| {
+            package_dirs
+                .iter()
+                .filter(|(dir, _)| file.starts_with(dir))
+                .max_by_key(|(dir, _)| dir.components().count())
+                .map(|(_, pkg)| &pkg.id)
+        })
+        .collect();
+    workspace_members
+        .iter()
+        .filter(|pkg| owners.contains(&&pkg.id))
+        .copied()
+        .collect()
+}
+
 /// Run the plugin only for the crate containing the target file
 fn only_run_on_file(
     cmd: &mut Command,
@@ -262,3 +567,90 @@ fn only_run_on_file(
         target.name
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature_args(filter: CrateFilter, default_features: &[&str]) -> Vec<String> {
+        let default_features: Vec<String> = default_features.iter().map(|f| f.to_string()).collect();
+        let mut cmd = Command::new("cargo");
+        add_feature_args(&mut cmd, &filter, &default_features);
+        cmd.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn with_features_enables_them() {
+        let filter = CrateFilter::WithFeatures(vec!["fast".to_string(), "verbose".to_string()]);
+        assert_eq!(feature_args(filter, &["std"]), ["--features", "fast,verbose"]);
+        assert!(feature_args(CrateFilter::WithFeatures(Vec::new()), &["std"]).is_empty());
+    }
+
+    #[test]
+    fn exclude_features_enables_remaining_defaults() {
+        let filter = CrateFilter::ExcludeFeatures(vec!["std".to_string()]);
+        assert_eq!(
+            feature_args(filter, &["alloc", "std"]),
+            ["--no-default-features", "--features", "alloc"]
+        );
+
+        let filter = CrateFilter::ExcludeFeatures(vec!["std".to_string()]);
+        assert_eq!(feature_args(filter, &["std"]), ["--no-default-features"]);
+    }
+
+    #[test]
+    fn bfs_depths_keep_the_shortest_distance() {
+        // a -> b -> c -> d, and a -> c directly
+        let graph: HashMap<&str, Vec<&str>> =
+            HashMap::from([("a", vec!["b", "c"]), ("b", vec!["c"]), ("c", vec!["d"]), ("d", vec![])]);
+        let roots = ["a"];
+        let depths = bfs_depths(&roots, |node| graph[node].as_slice());
+        assert_eq!(depths[&"a"], 0);
+        assert_eq!(depths[&"b"], 1);
+        assert_eq!(depths[&"c"], 1);
+        assert_eq!(depths[&"d"], 2);
+    }
+
+    #[test]
+    fn feature_subsets_are_capped() {
+        let features = |count: usize| -> Vec<String> { (0..count).map(|i| format!("f{i}")).collect() };
+        assert_eq!(
+            feature_subsets(&features(2)),
+            Some(vec![
+                vec![],
+                vec!["f0".to_string()],
+                vec!["f1".to_string()],
+                vec!["f0".to_string(), "f1".to_string()]
+            ])
+        );
+        assert_eq!(
+            feature_subsets(&features(MAX_ALL_COMBOS_FEATURES)).map(|combos| combos.len()),
+            Some(1 << MAX_ALL_COMBOS_FEATURES)
+        );
+        assert_eq!(feature_subsets(&features(64)), None);
+    }
+
+    #[test]
+    fn failures_take_precedence_over_condition_codes() {
+        let exit_code_file = cargo_metadata::camino::Utf8PathBuf::from_path_buf(
+            env::temp_dir().join(format!("cg4rs-exit-codes-{}", std::process::id())),
+        )
+        .expect("temp dir should be valid utf-8");
+        let reported = |codes: &str| {
+            fs::write(&exit_code_file, codes).expect("failed to write exit code file");
+            reported_exit_code(&exit_code_file)
+        };
+        assert_eq!(reported("0\n0\n"), 0);
+        assert_eq!(reported("0\n3\n1\n"), 1);
+        assert_eq!(reported("1\n101\n2\n"), 101);
+        let _ = fs::remove_file(&exit_code_file);
+        assert_eq!(reported_exit_code(&exit_code_file), 0);
+    }
+
+    #[test]
+    fn other_filters_keep_cargo_features() {
+        for filter in [CrateFilter::AllCrates, CrateFilter::OnlyWorkspace] {
+            assert!(feature_args(filter, &["std"]).is_empty());
+        }
+    }
+}
@@ -11,9 +11,9 @@ extern crate rustc_session;
 
 #[doc(hidden)]
 pub use cargo_metadata::camino::Utf8Path;
-pub use cargo_plugin::cargo_main;
-pub use plugin::{CrateFilter, Plugin, RustcPluginArgs};
-pub use rustc_plugin::rustc_main;
+pub use cargo_plugin::{cargo_main, FEATURE_COMBO};
+pub use plugin::{plugin_args_env, plugin_env, CompilationUnit, CrateFilter, Plugin, RustcPluginArgs};
+pub use rustc_plugin::{crate_depths, report_exit_code, rustc_main};
 
 mod cargo_plugin;
 mod plugin;
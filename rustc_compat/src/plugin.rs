@@ -13,6 +13,20 @@ pub enum CrateFilter {
 
     /// Only the crate containing a specific file.
     CrateContainingFile(PathBuf),
+
+    /// Only the workspace packages containing at least one of these files.
+    ///
+    /// Files outside every workspace package are ignored; if none is left, nothing is checked.
+    PackagesContainingFiles(Vec<PathBuf>),
+
+    /// Every crate, compiled with these features enabled in addition to the default ones.
+    WithFeatures(Vec<String>),
+
+    /// Every crate, compiled with the default features of the workspace members except these.
+    ///
+    /// Cargo cannot disable single features, so the default features are turned off and the
+    /// remaining ones enabled explicitly. Features the remaining ones enable stay enabled.
+    ExcludeFeatures(Vec<String>),
 }
 
 /// Arguments from your plugin to the rustc_plugin framework.
@@ -23,6 +37,45 @@ pub struct RustcPluginArgs<CargoArgs, PluginArgs> {
 
     /// Which crates you want to run the plugin on.
     pub filter: CrateFilter,
+
+    /// Whether to also run the plugin on build scripts (`build.rs`).
+    pub include_build_scripts: bool,
+
+    /// Whether to also run the plugin on the proc-macro crates of dependencies.
+    /// Proc-macro crates of the primary packages are analyzed like other crates.
+    pub analyze_proc_macros: bool,
+
+    /// Crate types to run the plugin on, e.g. `lib`, `bin`, `proc-macro` or `test`.
+    /// Empty means every crate type except test harnesses.
+    pub crate_types: Vec<String>,
+
+    /// Target triple to compile for, forwarded to cargo as `--target`.
+    pub target: Option<String>,
+
+    /// Only run the plugin on crates within this many levels of the workspace members in the
+    /// cargo dependency graph. The depths are available to the plugin via [`crate::crate_depths`].
+    /// `None` runs it on every crate selected by `filter`.
+    pub deps_depth: Option<usize>,
+
+    /// Feature combinations to run the plugin with, one `cargo check` per combination.
+    /// Empty means a single run with cargo's default feature selection.
+    pub feature_combos: Vec<Vec<String>>,
+
+    /// Run the plugin for every subset of the workspace members' features.
+    pub all_feature_combos: bool,
+}
+
+/// The crate compilation the plugin is run on.
+#[derive(Clone, Debug, Default)]
+pub struct CompilationUnit {
+    /// Whether the crate is a build script (`build.rs`), compiled as `build_script_build`.
+    pub is_build_script: bool,
+
+    /// Target triple cargo passed as `--target`, `None` for host crates like build scripts.
+    pub target: Option<String>,
+
+    /// Cargo package the crate belongs to, as set by cargo in `CARGO_PKG_NAME`.
+    pub package_name: Option<String>,
 }
 
 /// Interface between your plugin and the rustc_plugin framework.
@@ -46,15 +99,55 @@ pub trait Plugin: Sized {
     fn driver_name(&self) -> Cow<'static, str>;
 
     /// Parses and returns the CLI arguments for the plugin.
+    ///
+    /// The returned [`CrateFilter`] selects the crates to run on, and for
+    /// [`CrateFilter::WithFeatures`] and [`CrateFilter::ExcludeFeatures`] the features to compile them with.
     fn args(&self, target_dir: &Utf8Path) -> RustcPluginArgs<Self::CargoArgs, Self::PluginArgs>;
 
     /// Optionally modify the `cargo` command that launches rustc.
     /// For example, you could pass a `--feature` flag here.
     fn modify_cargo(&self, cargo: &mut Command, cargo_args: &Self::CargoArgs);
-    /// Executes the plugin with a set of compiler and plugin args.
-    fn run(self, compiler_args: Vec<String>, plugin_args: Self::PluginArgs);
+    /// Executes the plugin with a set of compiler and plugin args, for the crate described by `unit`.
+    fn run(self, compiler_args: Vec<String>, plugin_args: Self::PluginArgs, unit: CompilationUnit);
 }
 
 /// The name of the environment variable shared between the CLI and the driver.
 /// Must not conflict with any other env var used by Cargo.
-pub const PLUGIN_ARGS: &str = "PLUGIN_ARGS";
+///
+/// Derived from [`Plugin::driver_name`], e.g. `CG4RS_PLUGIN_ARGS` for `cg4rs`, so that
+/// plugins with different driver names can run in the same build without reading each
+/// other's arguments.
+pub fn plugin_args_env(driver_name: &str) -> String {
+    plugin_env(driver_name, "ARGS")
+}
+
+/// The name of the environment variable `name` is passed to the driver in, e.g.
+/// `CG4RS_PLUGIN_CRATE_TYPES` for `cg4rs`, derived like [`plugin_args_env`].
+pub fn plugin_env(driver_name: &str, name: &str) -> String {
+    let prefix: String = driver_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{prefix}_PLUGIN_{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plugin_args_env_is_derived_from_driver_name() {
+        assert_eq!(plugin_args_env("cg4rs"), "CG4RS_PLUGIN_ARGS");
+        assert_eq!(plugin_args_env("cg4rs-nightly"), "CG4RS_NIGHTLY_PLUGIN_ARGS");
+        assert_eq!(
+            plugin_env("cg4rs", crate::cargo_plugin::EXIT_CODE_FILE),
+            "CG4RS_PLUGIN_EXIT_CODE_FILE"
+        );
+    }
+}
@@ -1,12 +1,17 @@
 use std::{
-    env,
+    collections::HashMap,
+    env, fs,
+    io::Write,
     ops::Deref,
     path::{Path, PathBuf},
     process::{exit, Command},
 };
 
-use super::plugin::{Plugin, PLUGIN_ARGS};
-use crate::cargo_plugin::{RUN_ON_ALL_CRATES, SPECIFIC_CRATE, SPECIFIC_TARGET};
+use super::plugin::{plugin_args_env, plugin_env, CompilationUnit, Plugin};
+use crate::cargo_plugin::{
+    ANALYZE_PROC_MACROS, CRATE_DEPTHS, CRATE_TYPES, DEPS_DEPTH, EXIT_CODE_FILE, INCLUDE_BUILD_SCRIPTS,
+    RUN_ON_ALL_CRATES, SPECIFIC_CRATE, SPECIFIC_TARGET,
+};
 use rustc_session::{config::ErrorOutputType, EarlyDiagCtxt};
 
 /// Adapted from clippy.
@@ -33,6 +38,20 @@ fn arg_value<'a, T: Deref<Target = str>>(
     None
 }
 
+/// Every value of a flag that may be passed several times, e.g. `--crate-type`
+fn arg_values<'a, T: Deref<Target = str>>(args: &'a [T], find_arg: &str) -> Vec<&'a str> {
+    let mut values = Vec::new();
+    let mut args = args.iter().map(Deref::deref);
+    while let Some(arg) = args.next() {
+        let mut arg = arg.splitn(2, '=');
+        if arg.next() != Some(find_arg) {
+            continue;
+        }
+        values.extend(arg.next().or_else(|| args.next()));
+    }
+    values
+}
+
 fn toolchain_path(home: Option<String>, toolchain: Option<String>) -> Option<PathBuf> {
     home.and_then(|home| {
         toolchain.map(|toolchain| {
@@ -84,6 +103,56 @@ fn get_sysroot(orig_args: &[String]) -> (bool, String) {
     (have_sys_root_arg, sys_root)
 }
 
+/// Warn when the sysroot has no standard library for `target`.
+///
+/// Target-specific libraries live in `<sysroot>/lib/rustlib/<target>/lib`; without them
+/// the analysis fails with an unhelpful "can't find crate for `core`" error.
+fn check_target_libs(sys_root: &str, target: &str) {
+    let target_libs = Path::new(sys_root).join("lib").join("rustlib").join(target).join("lib");
+    if !target_libs.is_dir() {
+        eprintln!(
+            "warning: no libraries for target `{target}` in sysroot {sys_root}, try `rustup target add {target}`"
+        );
+    }
+}
+
+/// Report an exit code for `cargo_main` to exit with once the cargo run finishes
+///
+/// Failing the rustc invocation itself would make cargo abort the build and exit with
+/// its own code, so plugins report their result here instead. When several crates
+/// report a code, the lowest nonzero one wins. Does nothing outside of `cargo_main`.
+pub fn report_exit_code(driver_name: &str, code: i32) {
+    let Ok(path) = env::var(plugin_env(driver_name, EXIT_CODE_FILE)) else {
+        return;
+    };
+    let written = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{code}"));
+    if let Err(e) = written {
+        tracing::warn!("Failed to report exit code {code} to {path}: {e}");
+    }
+}
+
+/// Dependency depth of every crate in the cargo dependency graph, by crate name
+///
+/// Workspace members have depth 0, their direct dependencies 1, and so on. Only set when
+/// [`crate::RustcPluginArgs::deps_depth`] is; crates outside the cargo dependency graph,
+/// such as the standard library, are not listed.
+pub fn crate_depths(driver_name: &str) -> Option<HashMap<String, usize>> {
+    let depths = env::var(plugin_env(driver_name, CRATE_DEPTHS)).ok()?;
+    Some(
+        depths
+            .split(',')
+            .filter_map(|entry| {
+                let (name, depth) = entry.split_once(':')?;
+                Some((name.to_string(), depth.parse().ok()?))
+            })
+            .collect(),
+    )
+}
+
 struct DefaultCallbacks;
 impl rustc_driver::Callbacks for DefaultCallbacks {}
 
@@ -95,6 +164,7 @@ pub fn rustc_main<T: Plugin>(plugin: T) {
 
     exit(rustc_driver::catch_with_exit_code(move || {
         let mut orig_args: Vec<String> = env::args().collect();
+        let driver_name = plugin.driver_name();
 
         let (have_sys_root_arg, sys_root) = get_sysroot(&orig_args);
 
@@ -133,13 +203,86 @@ pub fn rustc_main<T: Plugin>(plugin: T) {
             }
             _ => true,
         };
-        let run_plugin = !normal_rustc && (run_on_all_crates || primary_package) && is_target_crate;
+        // Build scripts are compiled as separate `build_script_*` crates and are only
+        // analyzed when explicitly requested.
+        let is_build_script = arg_value(&args, "--crate-name", |name| name.starts_with("build_script_")).is_some();
+        let include_build_scripts = env::var(plugin_env(&driver_name, INCLUDE_BUILD_SCRIPTS)).is_ok();
+        // Test harnesses are compiled with `--test` instead of a `--crate-type`.
+        // Build scripts are selected by INCLUDE_BUILD_SCRIPTS alone. Proc-macro crates of the
+        // primary packages are selected like other crates, ANALYZE_PROC_MACROS adds them to CRATE_TYPES.
+        // A crate may be built as several types at once, e.g. `crate-type = ["cdylib", "rlib"]`,
+        // and is selected if any of them is.
+        let crate_types = if args.iter().any(|arg| arg == "--test") {
+            vec!["test"]
+        } else {
+            arg_values(&args, "--crate-type")
+        };
+        let is_proc_macro = crate_types.contains(&"proc-macro");
+        let analyze_proc_macros = env::var(plugin_env(&driver_name, ANALYZE_PROC_MACROS)).is_ok();
+        let is_selected_crate_type = is_build_script
+            || (is_proc_macro && analyze_proc_macros)
+            || match env::var(plugin_env(&driver_name, CRATE_TYPES)) {
+                Ok(selected) => crate_types
+                    .iter()
+                    .any(|crate_type| selected.split(',').any(|ty| ty.trim() == *crate_type)),
+                Err(_) => !crate_types.contains(&"test"),
+            };
+        // Dependencies deeper than `deps_depth` are compiled without the plugin
+        let deps_depth = env::var(plugin_env(&driver_name, DEPS_DEPTH)).ok();
+        let within_deps_depth = match (crate_depths(&driver_name), deps_depth) {
+            (Some(depths), Some(max_depth)) => {
+                let max_depth: usize = max_depth.parse().unwrap_or(usize::MAX);
+                arg_value(&args, "--crate-name", |name| {
+                    depths.get(name).is_none_or(|depth| *depth <= max_depth)
+                })
+                .is_some()
+            }
+            _ => true,
+        };
+        let run_plugin = !normal_rustc
+            && (run_on_all_crates || primary_package)
+            && is_target_crate
+            && is_selected_crate_type
+            && within_deps_depth
+            && (!is_build_script || include_build_scripts);
 
         if run_plugin {
-            let plugin_args: T::PluginArgs = serde_json::from_str(&env::var(PLUGIN_ARGS).unwrap()).unwrap();
-            plugin.run(args, plugin_args);
+            // Host crates (build scripts, proc macros) are compiled without `--target`
+            let target = arg_value(&args, "--target", |_| true);
+            if let Some(target) = target {
+                let sys_root = arg_value(&args, "--sysroot", |_| true).unwrap_or_default();
+                check_target_libs(sys_root, target);
+            }
+            let unit = CompilationUnit {
+                is_build_script,
+                target: target.map(String::from),
+                package_name: env::var("CARGO_PKG_NAME").ok(),
+            };
+            let plugin_args_var = plugin_args_env(&driver_name);
+            let plugin_args: T::PluginArgs = serde_json::from_str(&env::var(plugin_args_var).unwrap()).unwrap();
+            plugin.run(args, plugin_args, unit);
         } else {
             rustc_driver::run_compiler(&args, &mut DefaultCallbacks);
         }
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arg_values_collect_repeated_flags() {
+        let args = [
+            "rustc",
+            "--crate-type",
+            "cdylib",
+            "--crate-name",
+            "ffi",
+            "--crate-type=rlib",
+        ];
+        assert_eq!(arg_values(&args, "--crate-type"), ["cdylib", "rlib"]);
+        assert_eq!(arg_values(&args, "--crate-name"), ["ffi"]);
+        assert!(arg_values(&args, "--test").is_empty());
+    }
+}
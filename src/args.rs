@@ -1,8 +1,19 @@
-use clap::Parser;
+use anyhow::Context;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, Command, CommandFactory, FromArgMatches, Parser};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::callgraph::{CallSortOrder, CallerSortOrder, DeduplicationStrategy, MirLevel, OutputFormat};
+
+/// Name of the optional configuration file, discovered next to the manifest
+pub const CONFIG_FILE_NAME: &str = "cg4rs.toml";
+
+/// Prefix of the environment variables that set options, e.g. `CG4RS_JSON_OUTPUT=true`
+pub const ENV_PREFIX: &str = "CG4RS_";
 
 /// CG args
 #[derive(Parser, Clone, Debug, Serialize, Deserialize)]
@@ -20,6 +31,7 @@ pub struct CGArgs {
     pub emit_mir: bool,
 
     /// Entry point of the program
+    /// Annotates each function with its minimum call depth from it as `depth_from_entry`
     #[clap(long = "entry-point")]
     pub entry_point: Option<String>,
 
@@ -27,26 +39,375 @@ pub struct CGArgs {
     #[arg(short, long)]
     pub output_dir: Option<PathBuf>,
 
+    /// Prefix prepended to the names of all generated files, including `--timer-output`
+    /// Keeps the outputs of runs with different options apart in the same output directory
+    #[arg(long)]
+    pub output_prefix: Option<String>,
+
     /// No deduplication for call sites
     /// When enabled, keeps all call sites for the same caller-callee pair
     #[arg(long, default_value_t = false)]
     pub no_dedup: bool,
 
+    /// Strategy used to deduplicate call sites
+    /// `constraint` keeps one call site per caller-callee pair, `location` one per caller source line (implies --with-locations)
+    #[arg(long, value_enum, default_value_t = DeduplicationStrategy::ByConstraint)]
+    pub dedup_strategy: DeduplicationStrategy,
+
+    /// Which MIR to collect call sites from
+    /// `built` keeps all source-level calls but resolves fewer generics, `optimized` loses calls removed by inlining
+    #[arg(long, value_enum, default_value_t = MirLevel::Optimized)]
+    pub mir_level: MirLevel,
+
+    /// Expand each generic function only once, for its first monomorphization
+    /// Edges to every monomorphization are still recorded; trades precision for speed on generic-heavy crates
+    #[arg(long, default_value_t = false)]
+    pub collapse_monomorphizations: bool,
+
+    /// Merge all instances of a generic function into one node in text output
+    /// Their calls are unioned, keeping the least constrained call per callee; unlike --without-args, nodes are merged, not only relabeled
+    #[arg(long, default_value_t = false)]
+    pub collapse_generics: bool,
+
+    /// Record the source location of each call site
+    /// Locations are emitted as `call_location` (`file:line:col`) in JSON output
+    #[arg(long, default_value_t = false)]
+    pub with_locations: bool,
+
     /// Find all callers of the specified function path
     /// When specified, will output all functions that directly or indirectly call this function
     #[arg(long, value_delimiter = ',')]
     pub find_callers: Vec<String>,
 
+    /// Only report `--find-callers` callers within this many calls of the target
+    /// 1 keeps the direct callers, 0 reports the matched targets themselves
+    #[arg(long)]
+    pub find_callers_depth: Option<usize>,
+
+    /// Only report `--find-callers` callers whose path crosses at least this many package boundaries
+    /// 1 keeps the callers reaching the target from another crate
+    #[arg(long = "min-pkg-edges")]
+    pub min_path_package_num: Option<usize>,
+
+    /// Only report `--find-callers` callers whose path crosses at most this many package boundaries
+    /// 0 keeps the callers in the same crate as the target
+    #[arg(long = "max-pkg-edges")]
+    pub max_path_package_num: Option<usize>,
+
+    /// Write direct callers of the `--find-callers` targets to callers-stream-<crate>.jsonl as they are found
+    /// One JSON object per line, flushed during the analysis, before the callers reports are written
+    #[arg(long)]
+    pub stream_callers: bool,
+
+    /// Exit with code 1 if `--find-callers` finds any callers
+    /// For CI checks that a function stays unreachable from the crate
+    #[arg(long, default_value_t = false)]
+    pub exit_on_callers: bool,
+
+    /// Exit with code 2 if functions of the crate call each other in a cycle
+    /// Direct recursion counts as a cycle, recursion inside other crates does not
+    #[arg(long, default_value_t = false)]
+    pub exit_on_cycles: bool,
+
+    /// Exit with code 3 if functions of the crate call an `unsafe fn`
+    /// Each offending call is logged as an error
+    #[arg(long, default_value_t = false)]
+    pub exit_on_unsafe_calls: bool,
+
+    /// Exit with code 4 if any of these checks is triggered (comma-separated)
+    /// `cycle`, `unresolved-threshold:N` or `reachable:<path>`; results are written to `<crate>-fail-on.json`
+    #[arg(long, value_delimiter = ',', value_parser = parse_fail_check)]
+    pub fail_on: Vec<String>,
+
+    /// Exit with code 5 if the analysis recorded errors, e.g. failed monomorphizations
+    /// The errors are listed as `analysis_errors` in the statistics JSON
+    #[arg(long, default_value_t = false)]
+    pub strict_errors: bool,
+
+    /// Only keep calls into a crate whose version matches, as `name:requirement` (repeatable)
+    /// Requirements use Cargo's semver syntax, e.g. `serde:1.0.130` or `serde:>=1.0,<2.0`
+    #[arg(long = "crate-version-filter", value_parser = parse_crate_version_filter)]
+    pub crate_version_filters: Vec<(String, String)>,
+
+    /// Only analyze dependencies within this many levels of the workspace in the cargo dependency graph
+    /// Deeper crates are compiled without analysis and calls into them are left out; the standard library is kept
+    #[arg(long)]
+    pub deps_depth: Option<usize>,
+
+    /// Only output the subgraph reachable from these functions (comma-separated paths)
+    /// Keeps the matched functions, their transitive callees and the edges among them
+    #[arg(long, value_delimiter = ',')]
+    pub reachable_from: Vec<String>,
+
+    /// Match `--find-callers` targets as plain substrings of the full function path
+    /// Disables stripping of `::<...>` segments, for paths the generic-aware matching mangles
+    #[arg(long, default_value_t = false)]
+    pub no_generic_stripping: bool,
+
+    /// Only analyze functions whose path (without generic arguments) starts with this prefix
+    /// Calls leaving the prefix are recorded, but the callees outside of it are not analyzed
+    #[arg(long)]
+    pub only_prefix: Option<String>,
+
+    /// List all impls of the given trait and their methods instead of building the call graph
+    /// Also available as `cargo cg4rs impls <TRAIT_PATH>`
+    #[arg(long)]
+    pub list_impls: Option<String>,
+
+    /// Only output call sites whose caller belongs to the given crate
+    /// When passed without a value, defaults to the local crate. Callees from any crate are kept
+    #[arg(long, num_args = 0..=1, default_missing_value = "")]
+    pub callers_in_crate: Option<String>,
+
+    /// Hide rustc-generated helper functions (closures, coroutines, constants) in outputs
+    /// Call sites whose caller or callee path matches `GENERATED_FN_PATTERNS` are dropped
+    #[arg(long, default_value_t = false)]
+    pub hide_generated: bool,
+
+    /// Only output calls to functions with a non-Rust ABI, e.g. `extern "C"`
+    /// Lists every foreign call the crate makes; the ABI of each callee is always emitted as `abi` in JSON
+    #[arg(long, default_value_t = false)]
+    pub ffi_only: bool,
+
+    /// Only output calls from one crate into another, e.g. into dependencies
+    /// Also writes `<crate>-cross-crate-calls.txt` with the calls grouped by callee crate and version
+    #[arg(long, default_value_t = false)]
+    pub cross_crate_only: bool,
+
+    /// Only output call sites guarded by at least this many constraints
+    /// Selects the deeply guarded edges; the number of filtered edges is logged and added to the statistics
+    #[arg(long)]
+    pub min_constraints: Option<usize>,
+
+    /// Only output call sites guarded by at most this many constraints
+    /// `--max-constraints 0` lists only the calls reached unconditionally from their caller's entry
+    #[arg(long)]
+    pub max_constraints: Option<usize>,
+
+    /// Sort callees by loop nesting depth of their call site
+    /// Calls inside (nested) loops are estimated to be more frequent and are listed first
+    #[arg(long, default_value_t = false)]
+    pub sort_by_loop_depth: bool,
+
+    /// Order of the calls listed under each caller
+    /// `name` sorts by callee, `bb-order` keeps the MIR basic block order, roughly the execution order (best with --no-dedup)
+    #[arg(long, value_enum, default_value_t = CallSortOrder::Name)]
+    pub sort: CallSortOrder,
+
+    /// Order of the callers in `--find-callers` reports
+    /// `name` sorts by caller, `constraints` by path constraints, `distance` by hop count from the target
+    #[arg(long, value_enum, default_value_t = CallerSortOrder::Name)]
+    pub sort_callers_by: CallerSortOrder,
+
+    /// Annotate each callee edge with whether the callee may panic
+    /// Emits `may_panic` in JSON output when the callee's MIR has an `Assert` or calls a panic lang item
+    #[arg(long, default_value_t = false)]
+    pub annotate_panics: bool,
+
+    /// Annotate each function with its shortest call distance from an entry point
+    /// Entry points are public functions and `main`; emits `[distance: N]` in text and `distance_from_entry` in JSON
+    #[arg(long, default_value_t = false)]
+    pub entry_distances: bool,
+
+    /// Report the maximum call depth from the entry points and the deepest call chain
+    /// Adds `max_call_depth` and `deepest_chain` to the JSON statistics; each function on the chain counts as one frame
+    #[arg(long, default_value_t = false)]
+    pub max_depth_stat: bool,
+
+    /// Warn when the maximum call depth from the entry points exceeds N frames
+    /// For stack size analysis, e.g. in `no_std` contexts
+    #[arg(long)]
+    pub stack_usage_limit: Option<usize>,
+
+    /// Summarize calls by callee visibility (pub, pub(crate), private) and call scope
+    /// Writes `<crate>-visibility-report.txt` and `.json`, e.g. with `cross_module_private_calls`
+    #[arg(long, default_value_t = false)]
+    pub summarize_by_visibility: bool,
+
+    /// Compare the call graph with an LLVM call graph written by `opt -passes=dot-callgraph`
+    /// Writes `<crate>-llvm-comparison.txt` and `.json` with the edges found by only one of the two
+    #[arg(long)]
+    pub compare_llvm: Option<PathBuf>,
+
+    /// Trace all call paths from analyzed functions to panic sites
+    /// Writes callers of `core::panicking`/`std::panicking` functions to `<crate>-panic-paths.txt` (or `.json`)
+    #[arg(long = "panic-paths", default_value_t = false)]
+    pub find_panic_paths: bool,
+
+    /// Enumerate every simple call path from the entry points to the functions matching this path
+    /// Entry points are public functions and `main`; paths are written to `<crate>-all-paths-<path>.txt`
+    #[arg(long)]
+    pub all_paths_to: Option<String>,
+
+    /// Write every call chain from the entry points as folded stacks for flamegraph tools
+    /// Roots are `--entry-point` or public functions and `main`; writes `<crate>-callgraph.folded`
+    #[arg(long, default_value_t = false)]
+    pub folded_output: bool,
+
+    /// Maximum number of paths reported by `--all-paths-to` and `--folded-output`
+    /// The search stops once this many paths were found
+    #[arg(long, default_value_t = 1000)]
+    pub max_paths: usize,
+
+    /// Maximum number of calls in a path reported by `--all-paths-to` and `--folded-output`
+    /// Longer paths are not explored
+    #[arg(long, default_value_t = 16)]
+    pub max_path_len: usize,
+
+    /// Output formats of the call graph, repeatable (text, json, dot, csv, mermaid, cytoscape)
+    /// All formats are written to the output directory; defaults to text
+    #[arg(long = "format", value_enum)]
+    pub output_formats: Vec<OutputFormat>,
+
     /// Output the call graph as JSON format
-    /// This provides machine-readable data for further processing
+    /// Same as `--format json`; callers are also written as JSON
     #[arg(long, default_value_t = false)]
     pub json_output: bool,
 
+    /// Write the JSON call graph as a `nodes` table with edges between numeric node ids
+    /// Nodes carry `id`, `name`, `path`, `crate`, `version` and `path_hash`; edges `from`, `to` and `constraint_count`
+    #[arg(long, default_value_t = false)]
+    pub numeric_ids: bool,
+
+    /// Write output files gzip-compressed
+    /// A `.gz` suffix is appended to each output file name, e.g. `callgraph.json.gz`
+    #[arg(long, default_value_t = false)]
+    pub compress: bool,
+
+    /// Write the call graph to stdout instead of a file
+    /// Only the selected formats go to stdout, without statistics; other reports are still written to files
+    #[arg(long, default_value_t = false)]
+    pub stdout: bool,
+
+    /// Additionally write the call graph in Cytoscape.js elements JSON format
+    /// Same as adding `--format cytoscape`
+    #[arg(long, default_value_t = false)]
+    pub cytoscape_output: bool,
+
+    /// Write text outputs with one `caller -> callee [constraints]` line per call site
+    /// Applies to the call graph and `--find-callers` reports, for piping into grep, sort or awk
+    #[arg(long, default_value_t = false)]
+    pub compact_text: bool,
+
+    /// Group nodes of the DOT output into one cluster per crate
+    /// Calls between crates are drawn dashed, calls within a crate solid
+    #[arg(long, default_value_t = false)]
+    pub dot_cluster: bool,
+
+    /// Reverse the edges of the call graph outputs, pointing from callee to caller
+    /// Each function then lists its callers, e.g. for "who calls me" trees in DOT
+    #[arg(long, default_value_t = false)]
+    pub transpose: bool,
+
+    /// Additionally write the call graph in a compact binary format
+    /// Written to `<crate>-callgraph.bin`, load it with `cargo cg4rs load <file>`
+    #[arg(long, default_value_t = false)]
+    pub binary_output: bool,
+
+    /// Additionally write one JSON call graph per callee crate
+    /// Each file is named `<crate>-<dep_crate>-callgraph.json` and only contains call sites into that crate
+    #[arg(long, default_value_t = false)]
+    pub per_crate_output: bool,
+
+    /// Write the crate-level call graph instead of the function-level one
+    /// Writes `crate-graph.{txt,json,dot}` with one edge per pair of crates with calls between them
+    #[arg(long, default_value_t = false)]
+    pub crate_graph: bool,
+
+    /// Additionally write the number of distinct monomorphizations per generic function
+    /// The report is written to `<crate>-mono-report.txt`, sorted by instantiation count
+    #[arg(long, default_value_t = false)]
+    pub mono_report: bool,
+
+    /// Report the N generic functions with the most distinct monomorphizations
+    /// Writes `<crate>-top-instantiations.txt` and adds `top_instantiations` to the JSON statistics
+    #[arg(long)]
+    pub top_instantiations: Option<usize>,
+
     /// Do not include generic type arguments in function paths
     /// When enabled, function paths will not include generic type parameters
     #[arg(long, default_value_t = false)]
     pub without_args: bool,
 
+    /// Target triple to analyze for, forwarded to `cargo check --target`
+    /// JSON output is tagged with `compile_target` when set
+    #[arg(long)]
+    pub target: Option<String>,
+
+    /// Analyze the crate with exactly these features enabled (comma-separated), repeatable
+    /// Runs `cargo check` once per combination and writes `<crate>-features-<hash>.json` for each
+    #[arg(long = "feature-combo")]
+    pub feature_combos: Vec<String>,
+
+    /// Analyze every subset of the workspace members' features
+    /// The number of runs is 2^n for n features, use with care
+    #[arg(long, default_value_t = false)]
+    pub all_feature_combos: bool,
+
+    /// Also analyze build scripts (`build.rs`)
+    /// Call sites from build scripts are tagged with `is_build_script` in JSON output
+    #[arg(long, default_value_t = false)]
+    pub include_build_scripts: bool,
+
+    /// Also analyze the proc-macro crates of dependencies, not only those of the analyzed packages
+    /// Call sites from proc-macro crates are tagged with `is_proc_macro` in JSON output
+    #[arg(long, default_value_t = false)]
+    pub analyze_proc_macros: bool,
+
+    /// Only analyze crates of these types (comma-separated), e.g. `lib,bin`
+    /// `test` selects test harnesses (`cargo check --tests`), which are skipped by default
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = ["lib", "rlib", "dylib", "cdylib", "staticlib", "proc-macro", "bin", "test"]
+    )]
+    pub crate_types: Vec<String>,
+
+    /// Do not seed the analysis with compiler-generated functions
+    /// Closures, coroutines, constructors and shims are still analyzed when they are called
+    #[arg(long, default_value_t = false)]
+    pub skip_synthetic: bool,
+
+    /// Leave out functions expanded from macros or defined in build script outputs (`OUT_DIR`)
+    /// E.g. code generated by `prost` or `bindgen`; the number excluded is reported as `excluded_generated`
+    #[arg(long, default_value_t = false)]
+    pub exclude_generated: bool,
+
+    /// Leave out methods of local impls of foreign traits or for foreign types, e.g. `impl Display for Local`
+    /// They are neither analyzed nor listed as callees; the number skipped is reported as `skipped_extern_impls`
+    #[arg(long, default_value_t = false)]
+    pub no_extern_impls: bool,
+
+    /// Collapse calls through `#[inline(always)]` functions into direct edges
+    /// `A -> B -> C` becomes `A -> C` with the constraint counts of both calls added up
+    #[arg(long, default_value_t = false)]
+    pub merge_inline: bool,
+
+    /// Maximum MIR size (in basic blocks) of a function to analyze
+    /// Functions with more basic blocks are skipped with a warning
+    #[arg(long)]
+    pub max_mir_size: Option<usize>,
+
+    /// Maximum number of call edges to collect
+    /// Expansion stops once reached, leaving a partial graph marked as truncated in the statistics
+    #[arg(long)]
+    pub max_edges: Option<usize>,
+
+    /// Maximum number of functions to discover
+    /// Calls to functions beyond the limit are dropped, leaving a partial graph marked as truncated
+    #[arg(long)]
+    pub max_nodes: Option<usize>,
+
+    /// Only analyze functions in files changed since this git ref, plus their callers
+    /// Uses `git diff --name-only <ref>` in the manifest directory; falls back to a full analysis without git
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only analyze the crate target containing this source file
+    /// Speeds up iteration on one module; the file must belong to a workspace member
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+
     /// Output file for timing information
     /// When specified, will write detailed timing information to this file
     #[arg(long)]
@@ -57,6 +418,16 @@ pub struct CGArgs {
     #[arg(long, default_value_t = false)]
     pub cg_debug: bool,
 
+    /// Keep intermediate per-function analysis data for debugging
+    /// Writes `<output_dir>/debug/<function>.json` with the raw call site count, constraint map and MIR size
+    #[arg(long, default_value_t = false)]
+    pub no_cleanup: bool,
+
+    /// Write the constraint map of every analyzed function
+    /// Writes `<output_dir>/constraints/<function>.json` with the shortest path to each basic block
+    #[arg(long, default_value_t = false)]
+    pub emit_constraint_maps: bool,
+
     /// Path to the manifest (Cargo.toml)
     /// When specified, will use this manifest path instead of auto-detecting
     #[arg(long)]
@@ -66,6 +437,11 @@ pub struct CGArgs {
     /// When specified, will use this as the base directory for manifest path
     #[arg(long)]
     pub root_path: Option<PathBuf>,
+
+    /// Re-run the analysis whenever a `.rs` file under the root path changes
+    /// Handled by `call-cg4rs`; stop watching with Ctrl+C
+    #[arg(long, default_value_t = false)]
+    pub watch: bool,
 }
 
 impl CGArgs {
@@ -91,10 +467,53 @@ impl CGArgs {
 
         map
     }
+
+    /// Formats to write, combining `--format` with the `--json-output` and `--cytoscape-output` flags
+    ///
+    /// Without `--format`, text is written unless `--json-output` is set.
+    pub fn effective_output_formats(&self) -> Vec<OutputFormat> {
+        let mut formats = self.output_formats.clone();
+        if self.json_output {
+            formats.push(OutputFormat::Json);
+        }
+        if formats.is_empty() {
+            formats.push(OutputFormat::Text);
+        }
+        if self.cytoscape_output {
+            formats.push(OutputFormat::Cytoscape);
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        formats.retain(|format| seen.insert(*format));
+        formats
+    }
+
+    /// `name` of a generated file with the `--output-prefix` prepended
+    pub fn output_file_name(&self, name: &str) -> String {
+        format!("{}{name}", self.output_prefix.as_deref().unwrap_or_default())
+    }
+
+    /// Split each `--feature-combo` value into its list of features
+    pub fn feature_combo_lists(&self) -> Vec<Vec<String>> {
+        self.feature_combos
+            .iter()
+            .map(|combo| {
+                combo
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|feature| !feature.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .collect()
+    }
 }
 
 #[derive(Parser, Clone, Debug, Serialize, Deserialize)]
-#[clap(about = "This is a bug detector for Rust.")]
+#[clap(
+    about = "This is a bug detector for Rust.",
+    after_help = "Exit codes:\n  0  Analysis finished, no --exit-on-* or --fail-on condition was met\n  1  --exit-on-callers: callers were found\n  2  --exit-on-cycles: a call cycle was found\n  3  --exit-on-unsafe-calls: an unsafe function is called\n  4  --fail-on: a check was triggered\n  5  --strict-errors: the analysis recorded errors\n  101  Compilation or analysis failed"
+)]
 pub struct AllCliArgs {
     /// Arguments passed to cargo rust-analyzer
     #[arg(trailing_var_arg = true)]
@@ -103,3 +522,310 @@ pub struct AllCliArgs {
     #[command(flatten)]
     pub cg_args: CGArgs,
 }
+
+impl AllCliArgs {
+    /// Rewrite subcommands into their equivalent flags
+    ///
+    /// `cg4rs impls <trait_path>` becomes `cg4rs --list-impls <trait_path>`. The first
+    /// argument is the binary name, as for `parse_with_config`.
+    pub fn expand_subcommands(args: impl IntoIterator<Item = String>) -> Vec<String> {
+        let mut args: Vec<String> = args.into_iter().collect();
+        if args.get(1).is_some_and(|arg| arg == "impls") {
+            args[1] = "--list-impls".to_string();
+        }
+        args
+    }
+
+    /// Parse command line arguments and merge them with `CG4RS_*` environment variables
+    /// and `cg4rs.toml`, if present
+    ///
+    /// Precedence is CLI > environment > config file > defaults: a value from the file or
+    /// the environment is only used when the corresponding flag was not given on the command line.
+    /// Values from the file and the environment are turned into flags and parsed together with
+    /// the command line, so they are validated exactly like flags.
+    pub fn parse_with_config<I, T>(itr: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let cli_args: Vec<std::ffi::OsString> = itr.into_iter().map(Into::into).collect();
+        let command = Self::command();
+        let matches = command.clone().get_matches_from(cli_args.clone());
+        let args = Self::from_arg_matches(&matches)?;
+
+        // Environment variables are added last, so they replace options from the file
+        let mut options = BTreeMap::new();
+        let config_path = args.cg_args.config_file_path();
+        if config_path.is_file() {
+            tracing::info!("Loading configuration from {}", config_path.display());
+            options.extend(config_file_args(&command, &config_path)?);
+        }
+        options.extend(env_var_args(&command, |var| std::env::var(var).ok())?);
+        options.retain(|field, _| {
+            let on_command_line = matches.value_source(field) == Some(ValueSource::CommandLine);
+            if on_command_line {
+                tracing::debug!("Option `{}` is overridden by the command line", field);
+            }
+            !on_command_line
+        });
+        if options.is_empty() {
+            return Ok(args);
+        }
+
+        Self::parse_with_options(command, cli_args, options)
+            .context("invalid option in cg4rs.toml or CG4RS_* environment variables")
+    }
+
+    /// Parse `cli_args` with the flags of `options` inserted after the binary name
+    fn parse_with_options(
+        command: Command,
+        mut cli_args: Vec<std::ffi::OsString>,
+        options: BTreeMap<String, Vec<String>>,
+    ) -> anyhow::Result<Self> {
+        let insert_at = cli_args.len().min(1);
+        cli_args.splice(insert_at..insert_at, options.into_values().flatten().map(Into::into));
+        let matches = command.try_get_matches_from(cli_args)?;
+        Ok(Self::from_arg_matches(&matches)?)
+    }
+}
+
+impl CGArgs {
+    /// Location of `cg4rs.toml`: next to the manifest, in the root path, or in the current directory
+    fn config_file_path(&self) -> PathBuf {
+        let dir = if let Some(manifest_path) = &self.manifest_path {
+            manifest_path.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else if let Some(root_path) = &self.root_path {
+            root_path.clone()
+        } else {
+            PathBuf::new()
+        };
+        dir.join(CONFIG_FILE_NAME)
+    }
+}
+
+/// Arguments of `cargo cg4rs query`, answered from a saved call graph without recompiling
+#[derive(Parser, Clone, Debug)]
+#[clap(
+    name = "cargo cg4rs query",
+    about = "Answer caller/callee questions on a saved call graph."
+)]
+pub struct QueryArgs {
+    /// Call graph to load: a `--json-output` callgraph.json or a `--binary-output` file
+    /// `--compress`ed `.gz` files are decompressed
+    pub graph: PathBuf,
+
+    /// Find all direct and indirect callers of these functions (comma-separated)
+    /// Paths are matched as for the analysis' `--find-callers`
+    #[arg(long, value_delimiter = ',')]
+    pub find_callers: Vec<String>,
+
+    /// Find all functions directly or indirectly called by these functions (comma-separated)
+    /// Paths are matched as for `--find-callers`
+    #[arg(long, value_delimiter = ',')]
+    pub find_callees: Vec<String>,
+
+    /// Print a shortest call path starting at this function
+    /// Requires `--path-to`
+    #[arg(long, requires = "path_to")]
+    pub path_from: Option<String>,
+
+    /// Print a shortest call path ending at this function
+    /// Requires `--path-from`
+    #[arg(long, requires = "path_from")]
+    pub path_to: Option<String>,
+
+    /// List the call cycles of the graph
+    /// Each cycle is printed as the sorted functions of a strongly connected component
+    #[arg(long)]
+    pub cycles: bool,
+
+    /// Check whether a function matching `A` calls one matching `B`: `A -> B` (directly) or `A ->* B`
+    /// Prints yes or no with a witness path, and exits with status 2 when the answer is no
+    #[arg(long)]
+    pub query: Option<String>,
+
+    /// Print the answers as JSON
+    /// The default is a human-readable text report
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Flags setting the options of a TOML config file, keyed by option
+///
+/// Keys are the long option names, with either `-` or `_` as separator,
+/// e.g. `json-output = true` or `find_callers = ["foo", "bar"]`.
+fn config_file_args(command: &Command, path: &Path) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let mut options = BTreeMap::new();
+    for (key, value) in table {
+        let field = key.replace('-', "_");
+        let Some(arg) = option_arg(command, &field) else {
+            tracing::warn!("Unknown option `{}` in {}, ignoring", key, path.display());
+            continue;
+        };
+        let values = match value {
+            toml::Value::Array(items) => items.into_iter().map(toml_value_string).collect::<Option<Vec<_>>>(),
+            toml::Value::Boolean(enabled) if matches!(arg.get_action(), ArgAction::SetTrue) => {
+                options.insert(field, option_flags(arg, enabled));
+                continue;
+            }
+            value => toml_value_string(value).map(|value| vec![value]),
+        };
+        let values = values.with_context(|| format!("unsupported value of `{}` in {}", key, path.display()))?;
+        options.insert(field, option_values(arg, values));
+    }
+    Ok(options)
+}
+
+/// Flags setting the options of `CG4RS_<OPTION>` environment variables, keyed by option
+///
+/// E.g. `CG4RS_JSON_OUTPUT=true`, `CG4RS_MAX_MIR_SIZE=2000` or `CG4RS_FIND_CALLERS=foo,bar`.
+/// Lists are comma-separated. Crate version filters contain commas themselves
+/// (`serde:>=1.0,<2.0`), so there an item without `:` continues the filter before it.
+fn env_var_args(
+    command: &Command,
+    var: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+    let mut options = BTreeMap::new();
+    for arg in command.get_arguments().filter(|arg| arg.get_long().is_some()) {
+        let field = arg.get_id().to_string();
+        let name = format!("{ENV_PREFIX}{}", field.to_uppercase());
+        let Some(raw) = var(&name) else {
+            continue;
+        };
+        let flags = match arg.get_action() {
+            ArgAction::SetTrue => match raw.trim() {
+                "true" | "1" => option_flags(arg, true),
+                "false" | "0" => option_flags(arg, false),
+                _ => anyhow::bail!("invalid value `{raw}` of {name}, expected `true` or `false`"),
+            },
+            ArgAction::Append if arg.get_value_delimiter().is_none() => {
+                let mut items: Vec<String> = Vec::new();
+                for item in raw.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+                    match items.last_mut() {
+                        Some(previous) if field == "crate_version_filters" && !item.contains(':') => {
+                            previous.push(',');
+                            previous.push_str(item);
+                        }
+                        _ => items.push(item.to_string()),
+                    }
+                }
+                option_values(arg, items)
+            }
+            _ => option_values(arg, vec![raw]),
+        };
+        options.insert(field, flags);
+    }
+    Ok(options)
+}
+
+/// The argument of the option `field`, `None` for unknown options and positional arguments
+fn option_arg<'a>(command: &'a Command, field: &str) -> Option<&'a Arg> {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_id() == field && arg.get_long().is_some())
+}
+
+/// The flag enabling a boolean option, nothing when it stays disabled
+fn option_flags(arg: &Arg, enabled: bool) -> Vec<String> {
+    match arg.get_long() {
+        Some(long) if enabled => vec![format!("--{long}")],
+        _ => Vec::new(),
+    }
+}
+
+/// One `--<option>=<value>` flag per value
+fn option_values(arg: &Arg, values: Vec<String>) -> Vec<String> {
+    let long = arg.get_long().unwrap_or_default();
+    values.into_iter().map(|value| format!("--{long}={value}")).collect()
+}
+
+/// A scalar TOML value as it is written on the command line
+fn toml_value_string(value: toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Float(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Split a `--crate-version-filter` into the crate name and a valid version requirement
+fn parse_crate_version_filter(raw: &str) -> Result<(String, String), String> {
+    let (name, requirement) = raw
+        .split_once(':')
+        .ok_or_else(|| format!("expected `name:version`, got `{raw}`"))?;
+    semver::VersionReq::parse(requirement).map_err(|e| format!("invalid version requirement `{requirement}`: {e}"))?;
+    Ok((name.replace('-', "_"), requirement.to_string()))
+}
+
+/// Validate a `--fail-on` check, keeping its original spelling
+fn parse_fail_check(raw: &str) -> Result<String, String> {
+    raw.parse::<crate::callgraph::FailCheck>().map(|_| raw.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(options: BTreeMap<String, Vec<String>>) -> anyhow::Result<CGArgs> {
+        let cli_args = vec!["cg4rs".into(), "--json-output".into()];
+        AllCliArgs::parse_with_options(AllCliArgs::command(), cli_args, options).map(|args| args.cg_args)
+    }
+
+    fn env_options(vars: &[(&str, &str)]) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+        env_var_args(&AllCliArgs::command(), |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    fn config_options(content: &str) -> anyhow::Result<BTreeMap<String, Vec<String>>> {
+        let path = std::env::temp_dir().join(format!("cg4rs-config-{}.toml", std::process::id()));
+        std::fs::write(&path, content).expect("failed to write config file");
+        let options = config_file_args(&AllCliArgs::command(), &path);
+        let _ = std::fs::remove_file(&path);
+        options
+    }
+
+    #[test]
+    fn env_vars_are_parsed_as_flags() {
+        let options = env_options(&[
+            ("CG4RS_FIND_CALLERS", "foo,bar"),
+            ("CG4RS_MAX_MIR_SIZE", "2000"),
+            ("CG4RS_NO_DEDUP", "true"),
+            ("CG4RS_CRATE_VERSION_FILTERS", "serde:>=1.0,<2.0,rand:0.8"),
+        ])
+        .unwrap();
+        let args = parse(options).unwrap();
+        assert_eq!(args.find_callers, ["foo", "bar"]);
+        assert_eq!(args.max_mir_size, Some(2000));
+        assert!(args.no_dedup && args.json_output);
+        assert_eq!(
+            args.crate_version_filters,
+            [
+                ("serde".to_string(), ">=1.0,<2.0".to_string()),
+                ("rand".to_string(), "0.8".to_string())
+            ]
+        );
+
+        assert!(env_options(&[("CG4RS_NO_DEDUP", "yes")]).is_err());
+        assert!(parse(env_options(&[("CG4RS_CRATE_TYPES", "foo")]).unwrap()).is_err());
+    }
+
+    #[test]
+    fn config_values_are_validated_like_flags() {
+        let options = config_options("json-output = false\ncrate_types = [\"lib\"]\nfail-on = [\"cycle\"]\n").unwrap();
+        let args = parse(options).unwrap();
+        assert_eq!(args.crate_types, ["lib"]);
+        assert_eq!(args.fail_on, ["cycle"]);
+
+        assert!(parse(config_options("fail_on = [\"cycles\"]\n").unwrap()).is_err());
+        assert!(parse(config_options("crate-types = [\"foo\"]\n").unwrap()).is_err());
+        assert!(config_options("max-mir-size = { blocks = 2 }\n").is_err());
+    }
+}
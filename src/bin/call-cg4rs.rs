@@ -1,11 +1,18 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::env;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::fs as tokio_fs;
 use tokio::process::Command;
+use tokio::sync::{mpsc, watch};
 use toml::Value as TomlValue;
 
+/// Delay after a file change before re-running, so that bursts of saves trigger one run
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
 struct Args {
     skip_clean: bool,
+    watch: bool,
     project_root_dir: PathBuf,
     manifest_path: Option<PathBuf>,
     args: Vec<String>,
@@ -15,6 +22,7 @@ struct Args {
 async fn main() -> anyhow::Result<()> {
     let Args {
         skip_clean,
+        watch,
         project_root_dir,
         manifest_path,
         args,
@@ -22,7 +30,14 @@ async fn main() -> anyhow::Result<()> {
 
     cargo_clean(skip_clean, &project_root_dir, manifest_path.as_deref()).await?;
 
-    cargo_cg4rs(args).await?;
+    let exit_code = cargo_cg4rs(args.clone()).await?;
+
+    if watch {
+        watch_and_rerun(&project_root_dir, manifest_path.as_deref(), args).await?;
+    } else if exit_code != 0 {
+        // Forward `--exit-on-*` codes to CI
+        std::process::exit(exit_code);
+    }
     Ok(())
 }
 
@@ -85,11 +100,13 @@ async fn args() -> Args {
     }
 
     let skip_clean = final_args.iter().any(|arg| arg == "--no-clean");
+    let watch = final_args.iter().any(|arg| arg == "--watch");
 
     let args: Vec<String> = final_args.iter().filter(|&arg| arg != "--no-clean").cloned().collect();
 
     Args {
         skip_clean,
+        watch,
         project_root_dir,
         manifest_path,
         args,
@@ -153,7 +170,7 @@ async fn cargo_clean(skip_clean: bool, project_root_dir: &Path, manifest_path: O
         clean_args.push(format!("--manifest-path={}", path.display()));
     }
 
-    println!("Executing: cargo {}", clean_args.join(" "));
+    eprintln!("Executing: cargo {}", clean_args.join(" "));
 
     let mut child = Command::new("cargo")
         .args(clean_args)
@@ -170,7 +187,8 @@ async fn cargo_clean(skip_clean: bool, project_root_dir: &Path, manifest_path: O
     Ok(())
 }
 
-async fn cargo_cg4rs(args: Vec<String>) -> anyhow::Result<()> {
+/// Run `cargo cg4rs` and return its exit code
+async fn cargo_cg4rs(args: Vec<String>) -> anyhow::Result<i32> {
     let mut cg_args: Vec<String> = Vec::new();
     if let Some(tc) = toolchain_channel_from_embedded() {
         cg_args.push(format!("+{}", tc));
@@ -178,7 +196,7 @@ async fn cargo_cg4rs(args: Vec<String>) -> anyhow::Result<()> {
     cg_args.push("cg4rs".to_string());
     cg_args.extend(args.clone());
 
-    println!("Executing: cargo {}", cg_args.join(" "));
+    eprintln!("Executing: cargo {}", cg_args.join(" "));
 
     unsafe {
         std::env::set_var("RUSTFLAGS", "-Zalways-encode-mir --cap-lints allow");
@@ -191,9 +209,172 @@ async fn cargo_cg4rs(args: Vec<String>) -> anyhow::Result<()> {
     let status = child.wait().await.expect("Failed to wait for cargo cg4rs");
 
     if !status.success() {
-        eprintln!("cargo cg4rs failed");
+        eprintln!("cargo cg4rs failed with {status}");
     }
 
     tracing::debug!("Finish to exec: cargo cg4rs");
+    Ok(status.code().unwrap_or(-1))
+}
+
+/// Whether a file system event is a change to a `.rs` source file outside of `target_dir`
+fn is_source_change(event: &notify::Event, target_dir: &Path) -> bool {
+    matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_))
+        && event
+            .paths
+            .iter()
+            .any(|path| path.extension().is_some_and(|ext| ext == "rs") && !path.starts_with(target_dir))
+}
+
+/// Watch `root` recursively and forward changes to `.rs` files
+///
+/// The watcher stops when the returned `RecommendedWatcher` is dropped.
+fn spawn_watcher(root: &Path) -> anyhow::Result<(RecommendedWatcher, mpsc::UnboundedReceiver<notify::Event>)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let target_dir = root.join("target");
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+        Ok(event) if is_source_change(&event, &target_dir) => {
+            let _ = tx.send(event);
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("File watcher error: {}", e),
+    })?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok((watcher, rx))
+}
+
+/// Re-run the analysis on every (debounced) source change until Ctrl+C
+async fn watch_and_rerun(
+    project_root_dir: &Path,
+    manifest_path: Option<&Path>,
+    args: Vec<String>,
+) -> anyhow::Result<()> {
+    let (_watcher, mut events) = spawn_watcher(project_root_dir)?;
+
+    // Debounce on a separate task: wait for the burst of events to settle, then request one run
+    let (rerun_tx, mut rerun_rx) = mpsc::channel::<Vec<PathBuf>>(1);
+    let debouncer = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            tokio::time::sleep(WATCH_DEBOUNCE).await;
+            let mut changed = event.paths;
+            while let Ok(event) = events.try_recv() {
+                changed.extend(event.paths);
+            }
+            changed.sort();
+            changed.dedup();
+            if rerun_tx.send(changed).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Listen for Ctrl+C on its own task, so it is noticed while an analysis run is in progress
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = shutdown_tx.send(true);
+        }
+    });
+
+    println!("Watching {} for changes (Ctrl+C to stop)", project_root_dir.display());
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => break,
+            changed = rerun_rx.recv() => {
+                let Some(changed) = changed else { break };
+                println!("\n{}", "=".repeat(60));
+                for path in &changed {
+                    println!("Changed: {}", path.display());
+                }
+                println!("{}\n", "=".repeat(60));
+
+                cargo_clean_local_package(manifest_path).await?;
+                cargo_cg4rs(args.clone()).await?;
+                if *shutdown_rx.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+
+    println!("Stopped watching");
+    debouncer.abort();
     Ok(())
 }
+
+/// Package name from the `[package]` table of a manifest
+fn local_package_name(manifest_path: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(manifest_path).ok()?;
+    let parsed: TomlValue = toml::from_str(&content).ok()?;
+    parsed.get("package")?.get("name")?.as_str().map(String::from)
+}
+
+/// Run `cargo clean -p <local crate>` so the next run re-analyzes the local crate
+async fn cargo_clean_local_package(manifest_path: Option<&Path>) -> anyhow::Result<()> {
+    let Some(package) = manifest_path.and_then(local_package_name) else {
+        tracing::warn!("Local package name not found, re-running without cargo clean");
+        return Ok(());
+    };
+
+    let mut clean_args: Vec<String> = Vec::new();
+    if let Some(tc) = toolchain_channel_from_embedded() {
+        clean_args.push(format!("+{}", tc));
+    }
+    clean_args.extend(["clean".to_string(), "-p".to_string(), package]);
+    if let Some(path) = manifest_path {
+        clean_args.push(format!("--manifest-path={}", path.display()));
+    }
+
+    eprintln!("Executing: cargo {}", clean_args.join(" "));
+    let status = Command::new("cargo").args(clean_args).status().await?;
+    if !status.success() {
+        eprintln!("cargo clean failed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{DataChange, ModifyKind};
+
+    #[test]
+    fn source_change_filter() {
+        let root = PathBuf::from("/project");
+        let target_dir = root.join("target");
+        let modify = |path: &str| {
+            notify::Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))).add_path(root.join(path))
+        };
+
+        assert!(is_source_change(&modify("src/main.rs"), &target_dir));
+        assert!(!is_source_change(&modify("Cargo.toml"), &target_dir));
+        assert!(!is_source_change(&modify("target/debug/build/out.rs"), &target_dir));
+        assert!(!is_source_change(
+            &notify::Event::new(EventKind::Access(notify::event::AccessKind::Any)).add_path(root.join("src/lib.rs")),
+            &target_dir
+        ));
+    }
+
+    #[tokio::test]
+    async fn watcher_detects_file_modification() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos();
+        let root = env::temp_dir().join(format!("cg4rs-watch-{nanos}"));
+        std::fs::create_dir_all(root.join("src")).expect("failed to create watch dir");
+        let source = root.join("src").join("main.rs");
+        std::fs::write(&source, "fn main() {}\n").expect("failed to write source");
+
+        let (_watcher, mut events) = spawn_watcher(&root).expect("failed to start watcher");
+        std::fs::write(&source, "fn main() { println!(); }\n").expect("failed to modify source");
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .expect("no file change event within 5s")
+            .expect("watcher channel closed");
+        assert!(matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)));
+        assert!(event.paths.iter().any(|path| path.ends_with("src/main.rs")));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,11 +1,65 @@
 #![feature(rustc_private)]
 
-use cg4rs::CGDriver;
+use cg4rs::{CGDriver, GraphModel, QueryArgs};
+use clap::Parser;
 use rustc_compat::cargo_main;
+use std::path::Path;
 
 fn main() {
     // dotenvy::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, so that `--stdout` output can be piped
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    // `cargo cg4rs load <file>` re-emits a `--binary-output` file without running the analysis
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = args.strip_prefix(&["cg4rs".to_string()]).unwrap_or(&args);
+    if args.first().is_some_and(|arg| arg == "load") {
+        if let Err(e) = load(&args[1..]) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // `cargo cg4rs query <file> ...` answers caller/callee questions on a saved call graph
+    if args.first().is_some_and(|arg| arg == "query") {
+        let query_args = QueryArgs::parse_from(args);
+        match cg4rs::run_query(&query_args) {
+            Ok(report) => {
+                print!("{}", report.output);
+                // A `--query` that does not hold fails, so that it can be asserted in CI
+                if report.query_holds == Some(false) {
+                    std::process::exit(2);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     tracing::debug!("run cargo cg4rs");
-    cargo_main(CGDriver);
+    cargo_main(CGDriver::default());
+}
+
+/// `load <file> [--format text|json]`, printing the call graph to stdout
+fn load(args: &[String]) -> anyhow::Result<()> {
+    let Some(path) = args.first() else {
+        anyhow::bail!("usage: cargo cg4rs load <file.bin> [--format text|json]");
+    };
+    let format = match args.get(1..) {
+        Some([flag, format]) if flag == "--format" => format.as_str(),
+        Some([]) | None => "text",
+        _ => anyhow::bail!("usage: cargo cg4rs load <file.bin> [--format text|json]"),
+    };
+
+    let model = GraphModel::load(Path::new(path))?;
+    match format {
+        "text" => print!("{}", model.to_text()),
+        "json" => println!("{}", model.to_json()),
+        other => anyhow::bail!("unsupported load format `{other}`, expected text or json"),
+    }
+    Ok(())
 }
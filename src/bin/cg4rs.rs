@@ -1,25 +1,45 @@
 #![feature(rustc_private)]
 
-use cg4rs::CGDriver;
+use cg4rs::{CGDriver, DRIVER_TIMEOUT, Timer, start_driver_timeout, terminate_child_processes};
 use rustc_compat::rustc_main;
+use std::env;
 use std::process;
 use std::time::Duration;
 use tokio::time::timeout;
 
+/// Set to `1` to disable the analysis timeout entirely.
+const NO_TIMEOUT_ENV: &str = "CG4RS_NO_TIMEOUT";
+
+/// How long child processes get to exit after `SIGTERM` before they are killed
+const CHILD_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, so that `--stdout` output can be piped
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
     tracing::trace!("run cg4rs");
 
-    let timeout_duration = Duration::from_secs(5 * 60);
+    let no_timeout = env::var(NO_TIMEOUT_ENV).is_ok_and(|v| v == "1");
+    if !no_timeout {
+        start_driver_timeout();
+    }
+    let task = tokio::task::spawn_blocking(|| {
+        rustc_main(CGDriver::default());
+    });
+
+    if no_timeout {
+        tracing::info!("{NO_TIMEOUT_ENV}=1 is set, running cg4rs without timeout");
+        match task.await {
+            Ok(()) => tracing::info!("cg4rs completed successfully"),
+            Err(e) => {
+                tracing::error!("cg4rs task failed: {:?}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-    let result = timeout(timeout_duration, async {
-        tokio::task::spawn_blocking(|| {
-            rustc_main(CGDriver);
-        })
-        .await
-    })
-    .await;
+    let result = timeout(DRIVER_TIMEOUT, task).await;
 
     match result {
         Ok(Ok(())) => {
@@ -30,8 +50,18 @@ async fn main() {
             process::exit(1);
         }
         Err(_) => {
-            tracing::error!("cg4rs timed out after 5 minutes, terminating...");
-            eprintln!("Error: cg4rs execution timed out after 5 minutes");
+            let phase = Timer::current_phase().unwrap_or_else(|| "<unknown>".to_string());
+            tracing::error!("cg4rs timed out after 5 minutes during phase '{phase}', terminating...");
+            if let Err(e) = Timer::write_to_file() {
+                tracing::error!("Failed to write timer results to file: {:?}", e);
+            }
+            eprintln!("Error: cg4rs execution timed out after 5 minutes (last phase: {phase})");
+            eprintln!("Hint: set {NO_TIMEOUT_ENV}=1 to disable the timeout");
+            // The rustc task cannot be cancelled, but the processes it spawned must not outlive us
+            let terminated = terminate_child_processes(CHILD_GRACE_PERIOD);
+            if terminated > 0 {
+                tracing::info!("Terminated {terminated} child processes");
+            }
             process::exit(124);
         }
     }
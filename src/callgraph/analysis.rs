@@ -3,33 +3,114 @@
 //! 2. Dynamic dispatch: the function to call is determined at runtime.
 
 use super::{
+    collector::{MirEdgeCollector, MirPass},
     controlflow::{BlockPath, compute_shortest_paths},
+    fmt::{CallerStream, write_output},
     function::FunctionInstance,
     origin::OriginTraceContext,
+    path_utils::sanitize_path_for_filename,
     resolution::{
         build_fn_sig_index, candidates_for_dyn_fn_trait, candidates_for_dyn_normal_trait, candidates_for_fnptr_sig,
         collect_address_taken_functions, extract_dyn_fn_signature, extract_dyn_trait_info,
-        fallback_callable_def_id_from_ty, monomorphize, operand_fn_def, peel_dyn_from_receiver, trivial_resolve,
+        fallback_callable_def_id_from_ty, monomorphize, operand_fn_def, peel_dyn_from_receiver, resolve_fn_trait_shim,
+        reveal_opaque_types, trivial_resolve,
+    },
+    types::{
+        AnalysisError, CallGraph, CallKind, CallLocation, CallSite, DeduplicationStrategy, MirLevel, MonoStats,
+        ResolutionSource,
+    },
+    utils::{
+        collect_built_mir, matches_path_prefix, mir_failure_def_ids, mir_failures, optimized_mir_if_available,
+        stable_hash,
     },
-    types::{CallGraph, CallKind, CallSite},
 };
 use crate::timer;
 
-use rustc_hir::{def, def_id::DefId};
+use rustc_hir::{
+    def,
+    def_id::{CrateNum, DefId},
+};
 use rustc_middle::{
-    mir::{self, Terminator, TerminatorKind, visit::Visitor},
+    mir::{self, Terminator, TerminatorKind},
     ty::{self, InstanceKind, TypingEnv, normalize_erasing_regions::NormalizationError},
 };
 use rustc_span::source_map::Spanned;
-use std::collections::{HashMap, HashSet};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::time::Instant;
 use tracing::{debug, error, warn};
 
+/// File name for the debug artifact of a function
+///
+/// The path is sanitized with [`sanitize_path_for_filename`]. Long paths are truncated
+/// and suffixed with a hash of the full path, which also keeps them unique.
+fn debug_file_stem(function_path: &str) -> String {
+    const MAX_STEM_CHARS: usize = 150;
+    let sanitized = sanitize_path_for_filename(function_path);
+    if sanitized.chars().count() <= MAX_STEM_CHARS {
+        sanitized
+    } else {
+        let truncated: String = sanitized.chars().take(MAX_STEM_CHARS).collect();
+        format!("{truncated}-{:016x}", stable_hash(function_path))
+    }
+}
+
+/// Crate-wide inputs shared by every `collect_callsites` call
+pub(crate) struct CollectContext<'a, 'tcx> {
+    pub(crate) address_taken_funcs: &'a HashSet<DefId>,
+    /// Built MIR of local functions with `--mir-level built`, empty otherwise
+    pub(crate) built_mir: &'a HashMap<DefId, &'tcx mir::Body<'tcx>>,
+    pub(crate) max_mir_size: Option<usize>,
+    pub(crate) with_locations: bool,
+    /// Directory for per-function debug artifacts with `--no-cleanup`
+    pub(crate) debug_dir: Option<&'a Path>,
+    /// Directory for per-function constraint maps with `--emit-constraint-maps`
+    pub(crate) constraints_dir: Option<&'a Path>,
+    /// Gzip the per-function artifacts, as `--compress` does for the other outputs
+    pub(crate) compress: bool,
+}
+
+/// Write a per-function JSON artifact, logging failures
+fn write_json_artifact(path: &Path, compress: bool, artifact: &serde_json::Value) {
+    let written = write_output(path, compress, |file| {
+        serde_json::to_writer_pretty(file, artifact).map_err(std::io::Error::other)
+    });
+    if let Err(e) = written {
+        tracing::error!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Block index -> shortest path from the entry block, as written by `--emit-constraint-maps`
+fn constraint_map_json(constraints: &HashMap<mir::BasicBlock, BlockPath>) -> serde_json::Value {
+    let paths: BTreeMap<usize, &BlockPath> = constraints.iter().map(|(block, path)| (block.index(), path)).collect();
+    paths
+        .into_iter()
+        .map(|(block, path)| {
+            let blocks: Vec<usize> = path.blocks.iter().map(|block| block.index()).collect();
+            let entry = json!({
+                "length": blocks.len(),
+                "constraints": path.constraints,
+                "blocks": blocks,
+            });
+            (block.to_string(), entry)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
 impl<'tcx> FunctionInstance<'tcx> {
     /// the entrypoint to collect all callsites in a function instance
+    ///
+    /// `collectors` are invoked in the same MIR pass that collects the call sites.
     pub(crate) fn collect_callsites(
         &self,
         tcx: ty::TyCtxt<'tcx>,
-        address_taken_funcs: &HashSet<DefId>,
+        ctx: &CollectContext<'_, 'tcx>,
+        collectors: &mut [Box<dyn MirEdgeCollector<'tcx> + 'tcx>],
+        skipped_large_functions: &mut Vec<(FunctionInstance<'tcx>, usize)>,
+        mono_stats: &mut MonoStats,
+        errors: &mut Vec<AnalysisError<'tcx>>,
     ) -> Vec<CallSite<'tcx>> {
         let def_id = self.def_id();
 
@@ -38,33 +119,75 @@ impl<'tcx> FunctionInstance<'tcx> {
             return Vec::new();
         }
 
-        if !tcx.is_mir_available(def_id) {
+        // Built MIR is only available for local functions, others use optimized MIR
+        let body = ctx
+            .built_mir
+            .get(&def_id)
+            .copied()
+            .or_else(|| optimized_mir_if_available(tcx, def_id));
+        let Some(body) = body else {
             tracing::warn!("Skip no-body(No mir available) function: {:?}", def_id);
             return Vec::new();
+        };
+
+        // Skip excessively large bodies, which tend to dominate analysis time
+        if let Some(max_mir_size) = ctx.max_mir_size {
+            let block_count = body.basic_blocks.len();
+            if block_count > max_mir_size {
+                tracing::warn!(
+                    "Skip large function {} ({} basic blocks > max MIR size {})",
+                    self.full_path(tcx, false),
+                    block_count,
+                    max_mir_size
+                );
+                skipped_large_functions.push((*self, block_count));
+                return Vec::new();
+            }
         }
 
+        let start = Instant::now();
         // Compute function internal constraints,
         // which is a mapping from basic block to the path from the entry block to the basic block.
-        let constraints = timer::measure("1.0.0compute_constraints", || compute_shortest_paths(tcx, def_id));
+        let constraints = timer::measure("1.0.0compute_constraints", || compute_shortest_paths(body));
+        let constraint_map: Option<BTreeMap<usize, usize>> = ctx.debug_dir.map(|_| {
+            constraints
+                .iter()
+                .map(|(block, path)| (block.index(), path.constraints))
+                .collect()
+        });
+        if let Some(constraints_dir) = ctx.constraints_dir {
+            let function_path = self.full_path(tcx, false);
+            let map_path = constraints_dir.join(format!("{}.json", debug_file_stem(&function_path)));
+            write_json_artifact(&map_path, ctx.compress, &constraint_map_json(&constraints));
+        }
 
         // Extract function call information
-        timer::measure("1.0.1extract_function_call", || {
-            self.extract_function_call(tcx, &def_id, constraints, address_taken_funcs)
-        })
-    }
+        let call_sites = timer::measure("1.0.1extract_function_call", || {
+            let mut search_callees = SearchFunctionCall::new(tcx, self, body, constraints, ctx.address_taken_funcs);
+            search_callees.with_locations = ctx.with_locations;
+            MirPass::new(*self, body)
+                .with_collector(&mut search_callees)
+                .with_collectors(collectors)
+                .run();
+            mono_stats.merge(&search_callees.mono_stats);
+            errors.append(&mut search_callees.errors);
+            search_callees.callees
+        });
 
-    /// Extract information about all function calls in `function`
-    fn extract_function_call(
-        &self,
-        tcx: ty::TyCtxt<'tcx>,
-        caller_id: &DefId,
-        constraints: HashMap<mir::BasicBlock, BlockPath>,
-        address_taken_funcs: &HashSet<DefId>,
-    ) -> Vec<CallSite<'tcx>> {
-        let caller_body = tcx.optimized_mir(caller_id);
-        let mut search_callees = SearchFunctionCall::new(tcx, self, caller_body, constraints, address_taken_funcs);
-        search_callees.visit_body(caller_body);
-        search_callees.callees
+        if let (Some(debug_dir), Some(constraint_map)) = (ctx.debug_dir, constraint_map) {
+            let function_path = self.full_path(tcx, false);
+            let artifact = json!({
+                "function_path": function_path,
+                "mir_block_count": body.basic_blocks.len(),
+                "raw_callsite_count": call_sites.len(),
+                "constraint_map": constraint_map,
+                "elapsed_ms": start.elapsed().as_secs_f64() * 1000.0,
+            });
+            let artifact_path = debug_dir.join(format!("{}.json", debug_file_stem(&function_path)));
+            write_json_artifact(&artifact_path, ctx.compress, &artifact);
+        }
+
+        call_sites
     }
 }
 
@@ -78,16 +201,59 @@ struct SearchFunctionCall<'tcx, 'local> {
     current_bb: mir::BasicBlock,
     address_taken_funcs: &'local HashSet<DefId>,
     typing_env: TypingEnv<'tcx>,
+    mono_stats: MonoStats,
+    /// Failed monomorphizations and resolutions, see [`AnalysisError`]
+    errors: Vec<AnalysisError<'tcx>>,
+    with_locations: bool,
 }
 
-impl<'tcx, 'local> Visitor<'tcx> for SearchFunctionCall<'tcx, 'local> {
-    fn visit_basic_block_data(&mut self, block: mir::BasicBlock, data: &mir::BasicBlockData<'tcx>) {
-        // Update current basic block
-        self.current_bb = block;
-        self.super_basic_block_data(block, data);
+/// The call collector that builds the call graph
+impl<'tcx, 'local> MirEdgeCollector<'tcx> for SearchFunctionCall<'tcx, 'local> {
+    fn visit_terminator(
+        &mut self,
+        _caller: FunctionInstance<'tcx>,
+        _body: &mir::Body<'tcx>,
+        terminator: &Terminator<'tcx>,
+        location: mir::Location,
+    ) {
+        self.current_bb = location.block;
+        let first_new_callee = self.callees.len();
+        self.collect_terminator_callees(terminator);
+        if self.callees.len() == first_new_callee {
+            return;
+        }
+
+        // All callees resolved from this terminator share its block and source location
+        let constraint_kinds = self.constraints[&self.current_bb].constraint_kinds();
+        let location = self
+            .with_locations
+            .then(|| self.call_location(terminator.source_info.span));
+        let (abi, arg_count) = match &terminator.kind {
+            TerminatorKind::Call { func, args, .. } | TerminatorKind::TailCall { func, args, .. } => (
+                Some(func.ty(self.caller_body, self.tcx).fn_sig(self.tcx).abi().as_str()),
+                Some(args.len()),
+            ),
+            _ => (None, None),
+        };
+        for call_site in &mut self.callees[first_new_callee..] {
+            call_site.set_basic_block(self.current_bb.as_usize());
+            call_site.set_constraint_kinds(constraint_kinds);
+            if let Some(abi) = abi {
+                call_site.set_abi(abi);
+            }
+            if let Some(arg_count) = arg_count {
+                call_site.set_arg_count(arg_count);
+            }
+            if let Some(location) = &location {
+                call_site.set_call_location(location.clone());
+            }
+        }
     }
+}
 
-    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, _location: mir::Location) {
+impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
+    /// Record the callees of a `Call`, `TailCall` or `Drop` terminator
+    fn collect_terminator_callees(&mut self, terminator: &Terminator<'tcx>) {
         if let TerminatorKind::Call { func, args, .. } | TerminatorKind::TailCall { func, args, .. } = &terminator.kind
         {
             let func_ty = func.ty(self.caller_body, self.tcx);
@@ -108,6 +274,9 @@ impl<'tcx, 'local> Visitor<'tcx> for SearchFunctionCall<'tcx, 'local> {
 
             let callee = match monod_result {
                 Ok(monoed) => {
+                    self.mono_stats.ok += 1;
+                    // Calls on a returned `impl Trait` must resolve to the concrete type's method
+                    let monoed = reveal_opaque_types(self.tcx, monoed);
                     let dyn_receiver = args.iter().find_map(|arg| {
                         let operand = &arg.node;
                         let ty = operand.ty(self.caller_body, self.tcx);
@@ -123,27 +292,39 @@ impl<'tcx, 'local> Visitor<'tcx> for SearchFunctionCall<'tcx, 'local> {
             };
 
             // If callee function is found, add to the call list
-            if let Some(callee) = callee {
-                self.callees.push(CallSite::new(
-                    *self.caller_instance,
-                    callee,
-                    self.constraints[&self.current_bb].constraints,
-                ));
+            if let Some((callee, resolution)) = callee {
+                self.callees.push(
+                    CallSite::new(
+                        *self.caller_instance,
+                        callee,
+                        self.constraints[&self.current_bb].constraints,
+                        self.constraints[&self.current_bb].loop_depth,
+                    )
+                    .with_resolution(resolution),
+                );
             }
         } else if let TerminatorKind::Drop { place, .. } = &terminator.kind
-            && let Some(drop_impl) = self.resolve_drop_impl(place.ty(self.caller_body, self.tcx).ty)
+            && let Some(drop_impl) =
+                self.resolve_drop_impl(reveal_opaque_types(self.tcx, place.ty(self.caller_body, self.tcx).ty))
         {
-            self.callees.push(CallSite::new_with_kind(
-                *self.caller_instance,
-                drop_impl,
-                self.constraints[&self.current_bb].constraints,
-                CallKind::Drop,
-            ));
+            let resolution = if drop_impl.is_non_instance() {
+                ResolutionSource::Unresolved
+            } else {
+                ResolutionSource::Exact
+            };
+            self.callees.push(
+                CallSite::new_with_kind(
+                    *self.caller_instance,
+                    drop_impl,
+                    self.constraints[&self.current_bb].constraints,
+                    self.constraints[&self.current_bb].loop_depth,
+                    CallKind::Drop,
+                )
+                .with_resolution(resolution),
+            );
         }
     }
-}
 
-impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
     fn new(
         tcx: ty::TyCtxt<'tcx>,
         caller_instance: &'local FunctionInstance<'tcx>,
@@ -160,6 +341,19 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
             current_bb: mir::BasicBlock::from_usize(0),
             address_taken_funcs,
             typing_env: TypingEnv::post_analysis(tcx, caller_instance.def_id()),
+            mono_stats: MonoStats::default(),
+            errors: Vec::new(),
+            with_locations: false,
+        }
+    }
+
+    /// Source location of a call, using the outermost macro call site
+    fn call_location(&self, span: rustc_span::Span) -> CallLocation {
+        let loc = self.tcx.sess.source_map().lookup_char_pos(span.source_callsite().lo());
+        CallLocation {
+            file: loc.file.name.prefer_local().to_string(),
+            line: loc.line,
+            col: loc.col_display + 1,
         }
     }
 
@@ -168,16 +362,23 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
     /// If the function is a constant function, return the non-instance function.
     /// Otherwise, return None.
     fn handle_mono_error(
-        &self,
+        &mut self,
         func: &mir::Operand<'tcx>,
         before_mono_ty: ty::Ty<'tcx>,
         err: NormalizationError,
-    ) -> Option<FunctionInstance<'tcx>> {
-        tracing::warn!("Monomorphization failed: {:?}", err);
+    ) -> Option<(FunctionInstance<'tcx>, ResolutionSource)> {
+        // Individual failures are summarized at the end of `perform_mono_analysis`
+        tracing::debug!("Monomorphization failed: {:?}", err);
+        self.mono_stats.normalization_failures += 1;
+        self.errors.push(AnalysisError::MonomorphizationFailed {
+            func: *self.caller_instance,
+            error: format!("{err:?}"),
+        });
         if let Some(fallback) = self.fallback_callable_on_mono_error(func, before_mono_ty) {
-            return Some(fallback);
+            return Some((fallback, ResolutionSource::Unresolved));
         }
-        tracing::warn!("No callable DefId fallback available for {:?}", before_mono_ty);
+        tracing::debug!("No callable DefId fallback available for {:?}", before_mono_ty);
+        self.mono_stats.unresolved += 1;
         None
     }
 
@@ -203,7 +404,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
         first_arg: Option<&mir::Operand<'tcx>>,
         first_arg_ty: Option<ty::Ty<'tcx>>,
         monod_callee: ty::Ty<'tcx>,
-    ) -> Option<FunctionInstance<'tcx>> {
+    ) -> Option<(FunctionInstance<'tcx>, ResolutionSource)> {
         use mir::Operand::*;
         match func {
             Constant(_) => timer::measure("1.0.1.1.0handle_monod_direct_callee", || {
@@ -224,7 +425,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
         first_arg: Option<&mir::Operand<'tcx>>,
         monod_callee: ty::Ty<'tcx>,
         first_arg_ty: Option<ty::Ty<'tcx>>,
-    ) -> Option<FunctionInstance<'tcx>> {
+    ) -> Option<(FunctionInstance<'tcx>, ResolutionSource)> {
         debug!("Found direct call {:?}, func.ty: {:?}", func, monod_callee);
         match monod_callee.kind() {
             ty::TyKind::FnDef(..) => {
@@ -255,7 +456,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
         first_arg: Option<&mir::Operand<'tcx>>,
         first_arg_ty: Option<ty::Ty<'tcx>>,
         monod_callee: ty::Ty<'tcx>,
-    ) -> Option<FunctionInstance<'tcx>> {
+    ) -> Option<(FunctionInstance<'tcx>, ResolutionSource)> {
         debug!("Found indirect call {:?}, func.ty: {:?}", func, monod_callee);
         match monod_callee.kind() {
             ty::TyKind::FnDef(..) => {
@@ -286,7 +487,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
         first_arg_operand: Option<&mir::Operand<'tcx>>,
         first_arg: Option<ty::Ty<'tcx>>,
         monod: ty::Ty<'tcx>,
-    ) -> Option<FunctionInstance<'tcx>> {
+    ) -> Option<(FunctionInstance<'tcx>, ResolutionSource)> {
         let ty::TyKind::FnDef(def_id, monoed_args) = monod.kind() else {
             return None;
         };
@@ -301,13 +502,31 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
         // use caller's context to create TypingEnv, not callee's
         let caller_def_id = self.caller_instance.def_id();
         // Use caller's typing environment for resolution
-        let type_env = TypingEnv::post_analysis(self.tcx, caller_def_id);
-        let result = timer::measure("fn_def resolve_instance", || {
+        let mut type_env = TypingEnv::post_analysis(self.tcx, caller_def_id);
+        let mut result = timer::measure("fn_def resolve_instance", || {
             ty::Instance::try_resolve(self.tcx, type_env, *def_id, monoed_args)
         });
+        // Generic associated functions called via UFCS (e.g. `<HashMap<K, V> as Default>::default()`)
+        // may only resolve under the callee's own bounds, so retry there before giving up
+        if !matches!(result, Ok(Some(_))) && *def_id != caller_def_id {
+            let callee_env = TypingEnv::post_analysis(self.tcx, *def_id);
+            let callee_result = timer::measure("fn_def resolve_instance", || {
+                ty::Instance::try_resolve(self.tcx, callee_env, *def_id, monoed_args)
+            });
+            if let Ok(Some(instance)) = callee_result {
+                debug!("Resolved {:?} in the callee's typing env", monod);
+                type_env = callee_env;
+                result = Ok(Some(instance));
+            }
+        }
 
         match result {
             Err(err) => {
+                self.mono_stats.resolve_failures += 1;
+                self.errors.push(AnalysisError::InstanceResolutionFailed {
+                    func: *self.caller_instance,
+                    callee: *def_id,
+                });
                 error!("Instance [{:?}] resolve failed: {:?}", monod, err)
             }
             Ok(opt_instance) => {
@@ -321,15 +540,21 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
                             self.handle_dyn_trait_method_call(first_arg_operand, first_arg, *def_id)
                         });
                     }
-                    return Some(FunctionInstance::new_instance(instance));
+                    let instance = resolve_fn_trait_shim(self.tcx, type_env, instance);
+                    return Some((FunctionInstance::new_instance(instance), ResolutionSource::Exact));
                 } else {
                     warn!("Resolve [{:#?}] failed, trivial resolve", monod);
-                    return timer::measure("fn_def trivial_resolve", || {
-                        trivial_resolve(self.tcx, *def_id).or_else(|| {
-                            warn!("Trivial resolve [{:?}] failed, using non-instance", def_id);
-                            Some(FunctionInstance::new_non_instance(*def_id))
-                        })
-                    });
+                    let resolved = timer::measure("fn_def trivial_resolve", || trivial_resolve(self.tcx, *def_id));
+                    if let Some(resolved) = resolved {
+                        self.mono_stats.trivial_resolve_fallbacks += 1;
+                        return Some((resolved, ResolutionSource::Trivial));
+                    }
+                    warn!("Trivial resolve [{:?}] failed, using non-instance", def_id);
+                    self.mono_stats.unresolved += 1;
+                    return Some((
+                        FunctionInstance::new_non_instance(*def_id),
+                        ResolutionSource::Unresolved,
+                    ));
                 }
             }
         }
@@ -381,6 +606,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
                         *self.caller_instance,
                         cand,
                         self.constraints[&self.current_bb].constraints,
+                        self.constraints[&self.current_bb].loop_depth,
                         CallKind::DynTrait,
                     ));
                 }
@@ -421,6 +647,7 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
                     *self.caller_instance,
                     cand,
                     self.constraints[&self.current_bb].constraints,
+                    self.constraints[&self.current_bb].loop_depth,
                     CallKind::FnPtr,
                 ));
             }
@@ -442,13 +669,18 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
                 return;
             }
             debug!("fnptr call: found {} sig-matched cands", candidates.len());
+            self.mono_stats.candidate_call_sites += 1;
             for cand in candidates {
-                self.callees.push(CallSite::new_with_kind(
-                    *self.caller_instance,
-                    cand,
-                    self.constraints[&self.current_bb].constraints,
-                    CallKind::FnPtr,
-                ));
+                self.callees.push(
+                    CallSite::new_with_kind(
+                        *self.caller_instance,
+                        cand,
+                        self.constraints[&self.current_bb].constraints,
+                        self.constraints[&self.current_bb].loop_depth,
+                        CallKind::FnPtr,
+                    )
+                    .with_resolution(ResolutionSource::FnptrCandidate),
+                );
             }
         }
     }
@@ -479,22 +711,30 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
                         .resolve_dyn_fn_candidates(operand, requested_kind)
                 })
                 .unwrap_or_default();
-            let candidates = if origin_candidates.is_empty() {
-                timer::measure("candidates_for_dyn_fn_trait", || {
+            let (candidates, resolution) = if origin_candidates.is_empty() {
+                let candidates = timer::measure("candidates_for_dyn_fn_trait", || {
                     candidates_for_dyn_fn_trait(self.tcx, &inputs, output, self.address_taken_funcs)
-                })
+                });
+                (candidates, ResolutionSource::FnptrCandidate)
             } else {
-                origin_candidates
+                (origin_candidates, ResolutionSource::Exact)
             };
             debug!("Found {} candidates for dyn fn trait method", candidates.len());
+            if resolution == ResolutionSource::FnptrCandidate && !candidates.is_empty() {
+                self.mono_stats.candidate_call_sites += 1;
+            }
 
             for cand in candidates {
-                self.callees.push(CallSite::new_with_kind(
-                    *self.caller_instance,
-                    cand,
-                    self.constraints[&self.current_bb].constraints,
-                    CallKind::DynTrait,
-                ));
+                self.callees.push(
+                    CallSite::new_with_kind(
+                        *self.caller_instance,
+                        cand,
+                        self.constraints[&self.current_bb].constraints,
+                        self.constraints[&self.current_bb].loop_depth,
+                        CallKind::DynTrait,
+                    )
+                    .with_resolution(resolution),
+                );
             }
         }
         // Extract trait information
@@ -502,39 +742,49 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
             extract_dyn_trait_info(self.tcx, self.typing_env, first_arg?, def_id)
         {
             debug!("Found dyn trait method: trait={:?}, method={}", tr_id, method_name);
-            let candidates = if Some(tr_id) == fn_trait || Some(tr_id) == fn_mut_trait || Some(tr_id) == fn_once_trait {
-                let origin_candidates = first_arg_operand
-                    .and_then(|operand| requested_kind.map(|kind| (operand, kind)))
-                    .map(|(operand, kind)| self.origin_trace_context().resolve_dyn_fn_candidates(operand, kind))
-                    .unwrap_or_default();
-                if origin_candidates.is_empty() {
-                    // if trait is Fn/FnMut/FnOnce, use signature matching
-                    timer::measure("candidates_for_dyn_fn_trait", || {
-                        candidates_for_dyn_fn_trait(
-                            self.tcx,
-                            &inputs,
-                            output.unwrap_or(self.tcx.types.unit),
-                            self.address_taken_funcs,
-                        )
-                    })
+            let (candidates, resolution) =
+                if Some(tr_id) == fn_trait || Some(tr_id) == fn_mut_trait || Some(tr_id) == fn_once_trait {
+                    let origin_candidates = first_arg_operand
+                        .and_then(|operand| requested_kind.map(|kind| (operand, kind)))
+                        .map(|(operand, kind)| self.origin_trace_context().resolve_dyn_fn_candidates(operand, kind))
+                        .unwrap_or_default();
+                    if origin_candidates.is_empty() {
+                        // if trait is Fn/FnMut/FnOnce, use signature matching
+                        let candidates = timer::measure("candidates_for_dyn_fn_trait", || {
+                            candidates_for_dyn_fn_trait(
+                                self.tcx,
+                                &inputs,
+                                output.unwrap_or(self.tcx.types.unit),
+                                self.address_taken_funcs,
+                            )
+                        });
+                        (candidates, ResolutionSource::FnptrCandidate)
+                    } else {
+                        (origin_candidates, ResolutionSource::Exact)
+                    }
                 } else {
-                    origin_candidates
-                }
-            } else {
-                // for other traits, use trait method dispatch
-                timer::measure("candidates_for_dyn_normal_trait", || {
-                    candidates_for_dyn_normal_trait(self.tcx, tr_id, &method_name)
-                })
-            };
+                    // for other traits, use trait method dispatch
+                    let candidates = timer::measure("candidates_for_dyn_normal_trait", || {
+                        candidates_for_dyn_normal_trait(self.tcx, tr_id, &method_name)
+                    });
+                    (candidates, ResolutionSource::FnptrCandidate)
+                };
             debug!("Found {} candidates for dyn trait method", candidates.len());
+            if resolution == ResolutionSource::FnptrCandidate && !candidates.is_empty() {
+                self.mono_stats.candidate_call_sites += 1;
+            }
 
             for cand in candidates {
-                self.callees.push(CallSite::new_with_kind(
-                    *self.caller_instance,
-                    cand,
-                    self.constraints[&self.current_bb].constraints,
-                    CallKind::DynTrait,
-                ));
+                self.callees.push(
+                    CallSite::new_with_kind(
+                        *self.caller_instance,
+                        cand,
+                        self.constraints[&self.current_bb].constraints,
+                        self.constraints[&self.current_bb].loop_depth,
+                        CallKind::DynTrait,
+                    )
+                    .with_resolution(resolution),
+                );
             }
         } else {
             // If cannot handle as dyn trait method call, fall back to normal resolution
@@ -550,46 +800,267 @@ impl<'tcx, 'local> SearchFunctionCall<'tcx, 'local> {
 // Perform monomorphization while constructing call graph
 pub(crate) fn perform_mono_analysis<'tcx>(
     tcx: ty::TyCtxt<'tcx>,
-    instances: Vec<FunctionInstance<'tcx>>,
+    mut instances: Vec<FunctionInstance<'tcx>>,
     args: &crate::args::CGArgs,
+    collectors: &mut [Box<dyn MirEdgeCollector<'tcx> + 'tcx>],
+    mut caller_stream: Option<&mut CallerStream<'tcx>>,
 ) -> CallGraph<'tcx> {
+    // Must come first: `optimized_mir` on a local function steals its built MIR
+    let built_mir = match args.mir_level {
+        MirLevel::Built => timer::measure("0.4collect_built_mir", || collect_built_mir(tcx)),
+        MirLevel::Optimized => HashMap::new(),
+    };
+
     // 0. Collect all address-taken functions (RTA-like analysis)
     let address_taken_funcs = timer::measure("0.5collect_address_taken", || collect_address_taken_functions(tcx));
     timer::measure("0.6build_sig_index", || build_fn_sig_index(tcx, &address_taken_funcs));
 
+    // Crates deeper than `--deps-depth` in the cargo dependency graph, by crate
+    let crate_depths = args.deps_depth.and_then(|_| rustc_compat::crate_depths());
+    let mut beyond_deps_depth: HashMap<CrateNum, bool> = HashMap::new();
+
+    // Functions left out by `--no-extern-impls`, `--exclude-generated` and `--deps-depth`
+    // are neither seeds nor callees
+    let mut skipped_extern_impls: HashSet<FunctionInstance<'tcx>> = HashSet::new();
+    let mut excluded_generated: HashSet<FunctionInstance<'tcx>> = HashSet::new();
+    let mut pruned_by_deps_depth: HashSet<FunctionInstance<'tcx>> = HashSet::new();
+    let mut is_excluded = |func: FunctionInstance<'tcx>| {
+        if args.no_extern_impls && func.is_in_extern_impl(tcx) {
+            skipped_extern_impls.insert(func);
+            true
+        } else if args.exclude_generated && func.is_generated_code(tcx) {
+            excluded_generated.insert(func);
+            true
+        } else if let (Some(max_depth), Some(crate_depths)) = (args.deps_depth, &crate_depths)
+            && *beyond_deps_depth.entry(func.def_id().krate).or_insert_with(|| {
+                crate_depths
+                    .get(tcx.crate_name(func.def_id().krate).as_str())
+                    .is_some_and(|depth| *depth > max_depth)
+            })
+        {
+            pruned_by_deps_depth.insert(func);
+            true
+        } else {
+            false
+        }
+    };
+    instances.retain(|instance| !is_excluded(*instance));
+
     let mut call_graph = CallGraph::new(instances, args.without_args);
+    if args.annotate_panics {
+        call_graph.panic_cache = Some(Default::default());
+    }
+    // Location-based deduplication needs call locations
+    let with_locations = args.with_locations || args.dedup_strategy == DeduplicationStrategy::ByLocation;
+    // With --no-cleanup, keep intermediate per-function data for debugging
+    let output_dir = args
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let debug_dir = args.no_cleanup.then(|| output_dir.join(args.output_file_name("debug")));
+    let constraints_dir = args
+        .emit_constraint_maps
+        .then(|| output_dir.join(args.output_file_name("constraints")));
+    for dir in [&debug_dir, &constraints_dir].into_iter().flatten() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            error!("Failed to create directory {}: {}", dir.display(), e);
+        }
+    }
+    let collect_ctx = CollectContext {
+        address_taken_funcs: &address_taken_funcs,
+        built_mir: &built_mir,
+        max_mir_size: args.max_mir_size,
+        with_locations,
+        debug_dir: debug_dir.as_deref(),
+        constraints_dir: constraints_dir.as_deref(),
+        compress: args.compress,
+    };
     let mut discovered = HashSet::new();
+    let max_edges = args.max_edges.unwrap_or(usize::MAX);
+    let max_nodes = args.max_nodes.unwrap_or(usize::MAX);
+    // With `--collapse-monomorphizations`, the functions whose body has already been expanded
+    let mut expanded_def_ids: HashSet<DefId> = HashSet::new();
 
-    while let Some(instance) = call_graph.instances.pop_front() {
+    // Breadth-first, so truncation by `--max-edges`/`--max-nodes` keeps the functions closest to the seeds
+    'expand: while let Some(instance) = call_graph.instances.pop_front() {
+        if crate::driver::expansion_deadline_passed() {
+            // The driver timeout is close: report the functions left in the queue instead of
+            // expanding them, so the outputs are written before the driver is killed
+            call_graph
+                .errors
+                .push(AnalysisError::TimeoutExceeded { func: instance });
+            call_graph.errors.extend(
+                call_graph
+                    .instances
+                    .drain(..)
+                    .map(|func| AnalysisError::TimeoutExceeded { func }),
+            );
+            tracing::warn!("Driver timeout is close, stopping the expansion");
+            break;
+        }
+        if !discovered.contains(&instance) && discovered.len() >= max_nodes {
+            call_graph.truncated = true;
+            continue;
+        }
         let _ = discovered.insert(instance);
+        if args.collapse_monomorphizations && !expanded_def_ids.insert(instance.def_id()) {
+            continue;
+        }
         let call_sites = timer::measure("1.0collect_callsites", || {
-            instance.collect_callsites(tcx, &address_taken_funcs)
+            instance.collect_callsites(
+                tcx,
+                &collect_ctx,
+                collectors,
+                &mut call_graph.skipped_large_functions,
+                &mut call_graph.mono_stats,
+                &mut call_graph.errors,
+            )
         });
 
         for call_site in call_sites {
+            // Canonicalize so that instances only differing in lifetimes or shim kind are visited once
+            let call_site = call_site.canonicalize(tcx);
+            if is_excluded(call_site.callee()) {
+                continue;
+            }
+            if call_graph.call_sites.len() >= max_edges {
+                call_graph.truncated = true;
+                break 'expand;
+            }
+            let is_new_callee = !discovered.contains(&call_site.callee());
+            if is_new_callee && discovered.len() >= max_nodes {
+                call_graph.truncated = true;
+                continue;
+            }
+            if let Some(stream) = caller_stream.as_deref_mut() {
+                stream.record(tcx, &call_site, discovered.len());
+            }
             call_graph.call_sites.push(call_site.clone());
-            if discovered.contains(&call_site.callee()) {
+            if !is_new_callee {
                 continue;
             }
             discovered.insert(call_site.callee());
+            // Edges leaving the prefix are recorded and their callees counted, but not expanded
+            if let Some(prefix) = &args.only_prefix
+                && !matches_path_prefix(tcx, call_site.callee(), prefix)
+            {
+                continue;
+            }
+            // Other monomorphizations stay nodes of the graph, but are not expanded again
+            if args.collapse_monomorphizations && expanded_def_ids.contains(&call_site.callee().def_id()) {
+                continue;
+            }
             call_graph.instances.push_back(call_site.callee());
         }
     }
 
     call_graph.total_functions = discovered.len();
+    if args.no_extern_impls {
+        tracing::info!(
+            "Skipped {} functions of impls of foreign traits or types",
+            skipped_extern_impls.len()
+        );
+        call_graph.skipped_extern_impls = Some(skipped_extern_impls.len());
+    }
+    if args.exclude_generated {
+        tracing::info!(
+            "Excluded {} functions generated by macros or build scripts",
+            excluded_generated.len()
+        );
+        call_graph.excluded_generated = Some(excluded_generated.len());
+    }
+    if crate_depths.is_some() {
+        tracing::info!(
+            "Left out {} functions of crates deeper than --deps-depth",
+            pruned_by_deps_depth.len()
+        );
+        call_graph.pruned_by_deps_depth = Some(pruned_by_deps_depth.len());
+    }
     tracing::info!(
         "Analysis complete: {} instances analyzed, {} call sites found",
         discovered.len(),
         call_graph.call_sites.len(),
     );
+    if call_graph.mono_stats.failures() > 0 {
+        tracing::warn!("{}", call_graph.mono_stats);
+    } else {
+        tracing::info!("{}", call_graph.mono_stats);
+    }
+    if call_graph.truncated {
+        tracing::warn!(
+            "Analysis truncated at {} functions and {} call sites (--max-nodes/--max-edges)",
+            discovered.len(),
+            call_graph.call_sites.len()
+        );
+    }
+    let failures = mir_failures(tcx);
+    if !failures.is_empty() {
+        tracing::warn!(
+            "Skipped {} functions whose optimized MIR could not be built: {}",
+            failures.len(),
+            failures
+                .iter()
+                .map(|(path, _)| path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    call_graph.errors.extend(
+        mir_failure_def_ids()
+            .into_iter()
+            .map(|def_id| AnalysisError::MirUnavailable { def_id }),
+    );
+    if !call_graph.errors.is_empty() {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for error in &call_graph.errors {
+            *counts.entry(error.kind()).or_default() += 1;
+        }
+        tracing::warn!(
+            "{} analysis errors ({}), listed as `analysis_errors` in the statistics",
+            call_graph.errors.len(),
+            counts
+                .iter()
+                .map(|(kind, count)| format!("{count} {kind}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if !call_graph.skipped_large_functions.is_empty() {
+        tracing::warn!(
+            "Skipped {} functions exceeding the max MIR size",
+            call_graph.skipped_large_functions.len()
+        );
+    }
+
+    if args.merge_inline {
+        call_graph.merge_inline_calls(tcx);
+    }
 
     // Deduplicate call sites if deduplication is not disabled
     if !args.no_dedup {
-        tracing::info!("Deduplication enabled - removing duplicate call sites");
-        timer::measure("1.1deduplicate_call_sites", || call_graph.deduplicate_call_sites());
+        tracing::info!(
+            "Deduplication enabled ({:?}) - removing duplicate call sites",
+            args.dedup_strategy
+        );
+        timer::measure("1.1deduplicate_call_sites", || {
+            call_graph.deduplicate_call_sites(args.dedup_strategy)
+        });
     } else {
         tracing::info!("Deduplication disabled - keeping all call sites");
     }
 
+    let edges = call_graph.edges_by_resolution();
+    let summary = format!(
+        "{} direct edges, {} fnptr-candidate edges across {} call sites",
+        edges[&ResolutionSource::Exact],
+        edges[&ResolutionSource::FnptrCandidate],
+        call_graph.mono_stats.candidate_call_sites
+    );
+    if edges[&ResolutionSource::FnptrCandidate] > 0 {
+        tracing::warn!("Over-approximated call graph: {summary}");
+    } else {
+        tracing::info!("{summary}");
+    }
+
     call_graph
 }
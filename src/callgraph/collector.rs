@@ -0,0 +1,88 @@
+use rustc_middle::mir::{self, visit::Visitor};
+use rustc_middle::ty::TyCtxt;
+
+use super::function::FunctionInstance;
+
+/// Collects facts from the MIR of every analyzed function
+///
+/// Each function body is walked once per analysis. The call collector that builds the
+/// call graph and all registered collectors are invoked in that same pass, so extra
+/// analyses (e.g. field accesses) do not need to load and walk the MIR again.
+pub trait MirEdgeCollector<'tcx> {
+    /// Called for every statement in the body of `caller`
+    fn visit_statement(
+        &mut self,
+        _caller: FunctionInstance<'tcx>,
+        _body: &mir::Body<'tcx>,
+        _statement: &mir::Statement<'tcx>,
+        _location: mir::Location,
+    ) {
+    }
+
+    /// Called for every terminator in the body of `caller`
+    fn visit_terminator(
+        &mut self,
+        _caller: FunctionInstance<'tcx>,
+        _body: &mir::Body<'tcx>,
+        _terminator: &mir::Terminator<'tcx>,
+        _location: mir::Location,
+    ) {
+    }
+
+    /// Called once after the crate has been analyzed and all outputs are written
+    fn finish(&mut self, _tcx: TyCtxt<'tcx>) {}
+}
+
+/// Creates the additional collectors for one crate, see [`crate::CGDriver::with_collectors`]
+pub type CollectorFactory = for<'tcx> fn(TyCtxt<'tcx>) -> Vec<Box<dyn MirEdgeCollector<'tcx> + 'tcx>>;
+
+/// Drives a set of collectors over the body of one function
+pub(crate) struct MirPass<'a, 'tcx> {
+    caller: FunctionInstance<'tcx>,
+    body: &'a mir::Body<'tcx>,
+    collectors: Vec<&'a mut dyn MirEdgeCollector<'tcx>>,
+}
+
+impl<'a, 'tcx> MirPass<'a, 'tcx> {
+    pub(crate) fn new(caller: FunctionInstance<'tcx>, body: &'a mir::Body<'tcx>) -> Self {
+        Self {
+            caller,
+            body,
+            collectors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_collector(mut self, collector: &'a mut dyn MirEdgeCollector<'tcx>) -> Self {
+        self.collectors.push(collector);
+        self
+    }
+
+    pub(crate) fn with_collectors(mut self, collectors: &'a mut [Box<dyn MirEdgeCollector<'tcx> + 'tcx>]) -> Self {
+        self.collectors.extend(
+            collectors
+                .iter_mut()
+                .map(|collector| collector.as_mut() as &mut dyn MirEdgeCollector<'tcx>),
+        );
+        self
+    }
+
+    /// Walk the body once, invoking every collector
+    pub(crate) fn run(mut self) {
+        let body = self.body;
+        self.visit_body(body);
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for MirPass<'a, 'tcx> {
+    fn visit_statement(&mut self, statement: &mir::Statement<'tcx>, location: mir::Location) {
+        for collector in &mut self.collectors {
+            collector.visit_statement(self.caller, self.body, statement, location);
+        }
+    }
+
+    fn visit_terminator(&mut self, terminator: &mir::Terminator<'tcx>, location: mir::Location) {
+        for collector in &mut self.collectors {
+            collector.visit_terminator(self.caller, self.body, terminator, location);
+        }
+    }
+}
@@ -1,11 +1,7 @@
 //! Utilities for analyzing MIR control flow.
 
-use rustc_hir::def_id::DefId;
-use rustc_middle::{
-    mir::{self, BasicBlock, TerminatorKind},
-    ty::TyCtxt,
-};
-use std::collections::{HashMap, VecDeque};
+use rustc_middle::mir::{self, BasicBlock, TerminatorKind};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Types of constraints that can appear in MIR
 /// FIXME: Add other types of constraints
@@ -13,6 +9,8 @@ use std::collections::{HashMap, VecDeque};
 pub enum ConstraintKind {
     /// Switch on an integer value (if/match)
     SwitchInt,
+    /// Runtime check that panics on failure (overflow, bounds, division)
+    Assert,
     /// Other types of constraints
     _Other(String),
 }
@@ -22,10 +20,50 @@ impl ConstraintKind {
     pub fn from_terminator(terminator: &TerminatorKind<'_>) -> Option<Self> {
         match terminator {
             TerminatorKind::SwitchInt { .. } => Some(ConstraintKind::SwitchInt),
-            //TerminatorKind::Assert { .. } => Some(ConstraintKind::Assert),
+            TerminatorKind::Assert { .. } => Some(ConstraintKind::Assert),
             _ => None,
         }
     }
+
+    /// Whether this kind adds to the constraint count used as path cost
+    ///
+    /// Asserts are only reported in the kind breakdown, so that constraint
+    /// counts keep measuring branching (`SwitchInt`) only.
+    pub fn is_counted(&self) -> bool {
+        matches!(self, ConstraintKind::SwitchInt)
+    }
+}
+
+/// Number of constraints of each kind along a path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ConstraintKindCounts {
+    pub switch_int: usize,
+    pub assert: usize,
+}
+
+impl ConstraintKindCounts {
+    pub fn add(&mut self, kind: &ConstraintKind) {
+        match kind {
+            ConstraintKind::SwitchInt => self.switch_int += 1,
+            ConstraintKind::Assert => self.assert += 1,
+            ConstraintKind::_Other(_) => {}
+        }
+    }
+
+    pub fn sum(self, other: Self) -> Self {
+        Self {
+            switch_int: self.switch_int + other.switch_int,
+            assert: self.assert + other.assert,
+        }
+    }
+
+    /// Render as `{"SwitchInt": n, "Assert": m}`
+    pub fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "SwitchInt": self.switch_int,
+            "Assert": self.assert,
+        })
+    }
 }
 
 /// Represents a path through basic blocks with constraint tracking
@@ -35,6 +73,10 @@ pub struct BlockPath {
     pub blocks: Vec<BasicBlock>,
     /// Number of conditional constraints along the path
     pub constraints: usize,
+    /// Number of loops enclosing the last block of the path
+    pub loop_depth: usize,
+    /// Kinds of the constraints passed along the path, including uncounted ones
+    pub constraint_details: Vec<ConstraintKind>,
 }
 
 impl BlockPath {
@@ -43,22 +85,101 @@ impl BlockPath {
         BlockPath {
             blocks: vec![block],
             constraints: 0,
+            loop_depth: 0,
+            constraint_details: Vec::new(),
         }
     }
 
     /// Extends the path with a new basic block, tracking the path
-    fn extend(&self, block: BasicBlock, is_constraint: bool) -> Self {
+    fn extend(&self, block: BasicBlock, constraint: Option<&ConstraintKind>) -> Self {
         let mut blocks = self.blocks.clone();
         let mut constraints = self.constraints;
+        let mut constraint_details = self.constraint_details.clone();
 
         blocks.push(block);
 
         // If this extension involves a constraint, track it
-        if is_constraint {
-            constraints += 1;
+        if let Some(kind) = constraint {
+            if kind.is_counted() {
+                constraints += 1;
+            }
+            constraint_details.push(kind.clone());
+        }
+
+        BlockPath {
+            blocks,
+            constraints,
+            loop_depth: 0,
+            constraint_details,
+        }
+    }
+
+    /// Number of constraints of each kind along the path
+    pub fn constraint_kinds(&self) -> ConstraintKindCounts {
+        let mut counts = ConstraintKindCounts::default();
+        for kind in &self.constraint_details {
+            counts.add(kind);
         }
+        counts
+    }
+}
+
+/// Computes the loop nesting depth of every basic block
+///
+/// A back-edge is an edge `from -> header` where `header` dominates `from`.
+/// Using dominance instead of plain block order avoids treating join points
+/// (e.g. the end of an `if`/`else`) as loops. The natural loop of a back-edge
+/// is every block that reaches `from` without passing through `header`, and
+/// the depth of a block is the number of natural loops containing it.
+pub struct LoopDepthVisitor<'a, 'tcx> {
+    body: &'a mir::Body<'tcx>,
+}
+
+impl<'a, 'tcx> LoopDepthVisitor<'a, 'tcx> {
+    pub fn new(body: &'a mir::Body<'tcx>) -> Self {
+        LoopDepthVisitor { body }
+    }
+
+    /// Returns the loop depth of every basic block reachable from the entry block
+    pub fn compute(&self) -> HashMap<BasicBlock, usize> {
+        let basic_blocks = &self.body.basic_blocks;
+        let dominators = basic_blocks.dominators();
+        let predecessors = basic_blocks.predecessors();
+        let mut depths: HashMap<BasicBlock, usize> = HashMap::new();
+
+        // Collect loop bodies keyed by header, so multiple back-edges to the same
+        // header (e.g. `continue`) form a single loop
+        let mut loops: HashMap<BasicBlock, HashSet<BasicBlock>> = HashMap::new();
+        for (from, data) in basic_blocks.iter_enumerated() {
+            if !dominators.is_reachable(from) {
+                continue;
+            }
+            depths.entry(from).or_insert(0);
+            let Some(terminator) = data.terminator.as_ref() else {
+                continue;
+            };
+            for header in terminator.successors() {
+                if !dominators.dominates(header, from) {
+                    continue;
+                }
 
-        BlockPath { blocks, constraints }
+                let body = loops.entry(header).or_insert_with(|| HashSet::from([header]));
+                let mut worklist = vec![from];
+                while let Some(block) = worklist.pop() {
+                    if body.insert(block) {
+                        worklist.extend(predecessors[block].iter().copied());
+                    }
+                }
+            }
+        }
+
+        for body in loops.values() {
+            for block in body {
+                *depths.entry(*block).or_insert(0) += 1;
+            }
+        }
+
+        depths
     }
 }
 
@@ -70,8 +191,8 @@ impl BlockPath {
 ///
 /// # Returns
 /// * A map from each basic block to its shortest path from the entry block
-pub fn compute_shortest_paths(tcx: TyCtxt<'_>, def_id: DefId) -> HashMap<BasicBlock, BlockPath> {
-    let body = tcx.optimized_mir(def_id);
+pub fn compute_shortest_paths(body: &mir::Body<'_>) -> HashMap<BasicBlock, BlockPath> {
+    let loop_depths = LoopDepthVisitor::new(body).compute();
     let entry = mir::START_BLOCK;
     let mut result: HashMap<BasicBlock, BlockPath> = HashMap::new();
     let mut best_constraints: HashMap<BasicBlock, usize> = HashMap::new();
@@ -89,9 +210,13 @@ pub fn compute_shortest_paths(tcx: TyCtxt<'_>, def_id: DefId) -> HashMap<BasicBl
 
         // Process all successors of the current block
         if let Some(terminator) = body.basic_blocks[block].terminator.as_ref() {
-            // Current edge weight: 1 if it's a constraint, 0 otherwise
-            let is_constraint_edge = ConstraintKind::from_terminator(&terminator.kind).is_some();
-            let edge_weight = if is_constraint_edge { 1 } else { 0 };
+            // Current edge weight: 1 if it's a counted constraint, 0 otherwise
+            let constraint = ConstraintKind::from_terminator(&terminator.kind);
+            let edge_weight = if constraint.as_ref().is_some_and(ConstraintKind::is_counted) {
+                1
+            } else {
+                0
+            };
 
             for target in terminator.successors() {
                 let next_cost = current_cost + edge_weight;
@@ -100,7 +225,7 @@ pub fn compute_shortest_paths(tcx: TyCtxt<'_>, def_id: DefId) -> HashMap<BasicBl
                 match best_constraints.get(&target) {
                     Some(&best) if next_cost >= best => {}
                     _ => {
-                        let new_path = current_path.extend(target, is_constraint_edge);
+                        let new_path = current_path.extend(target, constraint.as_ref());
                         result.insert(target, new_path);
                         best_constraints.insert(target, next_cost);
 
@@ -116,5 +241,9 @@ pub fn compute_shortest_paths(tcx: TyCtxt<'_>, def_id: DefId) -> HashMap<BasicBl
         }
     }
 
+    for (block, path) in result.iter_mut() {
+        path.loop_depth = loop_depths.get(block).copied().unwrap_or(0);
+    }
+
     result
 }
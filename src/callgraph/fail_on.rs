@@ -0,0 +1,186 @@
+//! `--fail-on` checks, evaluated after the analysis to gate CI jobs
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use serde_json::json;
+use std::io::Write;
+use std::str::FromStr;
+
+use super::fmt::write_output;
+use super::stats;
+use super::types::{CallGraph, ResolutionSource};
+use super::utils::matches_function_path;
+
+/// Condition that fails the analysis when it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FailCheck {
+    /// `cycle`: functions of the crate call each other in a cycle
+    Cycle,
+    /// `unresolved-threshold:N`: more than `N` call sites fell back to unresolved callees
+    UnresolvedThreshold(usize),
+    /// `reachable:<path>`: a function matching the path is reachable from the crate's entry points
+    Reachable(String),
+}
+
+impl FromStr for FailCheck {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (s, None),
+        };
+        match (kind, value) {
+            ("cycle", None) => Ok(Self::Cycle),
+            ("unresolved-threshold", Some(n)) => n
+                .parse()
+                .map(Self::UnresolvedThreshold)
+                .map_err(|_| format!("invalid unresolved call threshold `{n}`")),
+            ("reachable", Some(path)) if !path.is_empty() => Ok(Self::Reachable(path.to_string())),
+            _ => Err(format!(
+                "unknown check `{s}`, expected `cycle`, `unresolved-threshold:N` or `reachable:<path>`"
+            )),
+        }
+    }
+}
+
+/// Outcome of one `--fail-on` check
+#[derive(Debug)]
+pub(crate) struct CheckResult {
+    pub(crate) check: String,
+    pub(crate) triggered: bool,
+    pub(crate) message: String,
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    fn evaluate_fail_check(&self, tcx: TyCtxt<'tcx>, check: &FailCheck, no_generic_stripping: bool) -> (bool, String) {
+        match check {
+            FailCheck::Cycle => match self.find_local_cycle() {
+                Some(cycle) => {
+                    let cycle: Vec<_> = cycle.iter().map(|func| self.function_path(tcx, *func)).collect();
+                    (true, format!("call cycle {} -> {}", cycle.join(" -> "), cycle[0]))
+                }
+                None => (false, "no call cycle".to_string()),
+            },
+            FailCheck::UnresolvedThreshold(threshold) => {
+                let unresolved = self
+                    .call_sites
+                    .iter()
+                    .filter(|call_site| call_site.resolution() == ResolutionSource::Unresolved)
+                    .count();
+                (
+                    unresolved > *threshold,
+                    format!("{unresolved} unresolved call sites (threshold {threshold})"),
+                )
+            }
+            FailCheck::Reachable(path) => {
+                let distances = self.compute_entry_distances(tcx, &stats::entry_points(tcx));
+                let closest = distances
+                    .iter()
+                    .filter(|(func, _)| {
+                        matches_function_path(tcx, **func, path, self.without_args, no_generic_stripping)
+                    })
+                    .min_by_key(|(_, distance)| **distance);
+                match closest {
+                    Some((func, distance)) => (
+                        true,
+                        format!(
+                            "{} is reachable from the entry points in {distance} calls",
+                            self.function_path(tcx, **func)
+                        ),
+                    ),
+                    None => (false, format!("{path} is not reachable from the entry points")),
+                }
+            }
+        }
+    }
+}
+
+/// Evaluate the `--fail-on` checks, logging an error for each triggered one
+///
+/// Checks are validated when the arguments are parsed, including those from `cg4rs.toml`
+/// and the environment. A check that still fails to parse counts as triggered, so that it
+/// cannot silently pass a CI job.
+pub(crate) fn evaluate_fail_checks<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    checks: &[String],
+    no_generic_stripping: bool,
+) -> Vec<CheckResult> {
+    checks
+        .iter()
+        .map(|check| {
+            let (triggered, message) = match check.parse::<FailCheck>() {
+                Ok(parsed) => call_graph.evaluate_fail_check(tcx, &parsed, no_generic_stripping),
+                Err(e) => (true, e),
+            };
+            if triggered {
+                tracing::error!("--fail-on={check} triggered: {message}");
+            } else {
+                tracing::info!("--fail-on={check} passed: {message}");
+            }
+            CheckResult {
+                check: check.clone(),
+                triggered,
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Write the results of the `--fail-on` checks to `<crate_name>-fail-on.json`
+pub(crate) fn output_fail_checks(results: &[CheckResult], tcx: TyCtxt<'_>, options: &crate::args::CGArgs) {
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let summary = json!({
+        "crate_name": crate_name,
+        "failed": results.iter().any(|result| result.triggered),
+        "checks": results
+            .iter()
+            .map(|result| json!({
+                "check": result.check,
+                "triggered": result.triggered,
+                "message": result.message,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    let output = serde_json::to_string_pretty(&summary).unwrap_or_else(|_| "{}".to_string());
+
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let output_path = output_dir.join(options.output_file_name(&format!("{crate_name}-fail-on.json")));
+    match write_output(&output_path, options.compress, |file| write!(file, "{output}")) {
+        Ok(path) => tracing::info!("--fail-on summary written to {}", path.display()),
+        Err(e) => tracing::error!("Failed to write --fail-on summary: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fail_checks() {
+        assert_eq!("cycle".parse(), Ok(FailCheck::Cycle));
+        assert_eq!("unresolved-threshold:5".parse(), Ok(FailCheck::UnresolvedThreshold(5)));
+        assert_eq!(
+            "reachable:my_crate::legacy::old_api".parse(),
+            Ok(FailCheck::Reachable("my_crate::legacy::old_api".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_fail_checks() {
+        for invalid in [
+            "",
+            "cycles",
+            "cycle:1",
+            "unresolved-threshold",
+            "unresolved-threshold:-1",
+            "reachable:",
+        ] {
+            assert!(invalid.parse::<FailCheck>().is_err(), "{invalid}");
+        }
+    }
+}
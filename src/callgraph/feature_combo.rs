@@ -0,0 +1,122 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::fmt::read_output;
+use super::utils::stable_hash;
+
+/// Stable short hash of a feature combination, used in `{crate}-features-{hash}.json`
+///
+/// Features are sorted first so that the same set always maps to the same file name.
+pub(crate) fn feature_combo_hash(features: &[String]) -> String {
+    let mut sorted: Vec<&str> = features.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    format!("{:016x}", stable_hash(&sorted.join(",")))
+}
+
+/// Call sites present in only one of two feature combination outputs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureComboDiff {
+    /// `(caller, callee)` pairs only present in the first output
+    pub only_in_first: Vec<(String, String)>,
+    /// `(caller, callee)` pairs only present in the second output
+    pub only_in_second: Vec<(String, String)>,
+}
+
+impl FeatureComboDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_first.is_empty() && self.only_in_second.is_empty()
+    }
+}
+
+impl std::fmt::Display for FeatureComboDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (caller, callee) in &self.only_in_first {
+            writeln!(f, "- {caller} -> {callee}")?;
+        }
+        for (caller, callee) in &self.only_in_second {
+            writeln!(f, "+ {caller} -> {callee}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Diff two `{crate}-features-{hash}.json` outputs, which may be `--compress`ed
+///
+/// Call sites are compared by caller and callee name; constraint counts are ignored.
+pub fn compare_feature_outputs(first: &Path, second: &Path) -> anyhow::Result<FeatureComboDiff> {
+    let read = |path: &Path| -> anyhow::Result<Value> {
+        let content = read_output(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_slice(&content).with_context(|| format!("failed to parse {}", path.display()))
+    };
+    Ok(diff_feature_outputs(&read(first)?, &read(second)?))
+}
+
+fn diff_feature_outputs(first: &Value, second: &Value) -> FeatureComboDiff {
+    let first = call_site_names(first);
+    let second = call_site_names(second);
+    FeatureComboDiff {
+        only_in_first: first.difference(&second).cloned().collect(),
+        only_in_second: second.difference(&first).cloned().collect(),
+    }
+}
+
+/// Collect `(caller, callee)` name pairs from the `call_graph` entries of an output
+fn call_site_names(output: &Value) -> BTreeSet<(String, String)> {
+    let Some(entries) = output["call_graph"].as_array() else {
+        return BTreeSet::new();
+    };
+
+    entries
+        .iter()
+        .flat_map(|entry| {
+            let caller = entry["caller"]["name"].as_str().unwrap_or_default().to_string();
+            entry["callee"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(move |callee| Some((caller.clone(), callee["name"].as_str()?.to_string())))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn feature_combo_hash_ignores_order() {
+        let a = feature_combo_hash(&["fast".to_string(), "verbose".to_string()]);
+        let b = feature_combo_hash(&["verbose".to_string(), "fast".to_string()]);
+        assert_eq!(a, b);
+        assert_ne!(a, feature_combo_hash(&["fast".to_string()]));
+        assert_eq!(a.len(), 16);
+    }
+
+    #[test]
+    fn diff_reports_call_sites_unique_to_each_side() {
+        let first = json!({
+            "features": ["fast"],
+            "call_graph": [
+                { "caller": { "name": "main" }, "callee": [{ "name": "fast_path" }, { "name": "common" }] }
+            ]
+        });
+        let second = json!({
+            "features": ["verbose"],
+            "call_graph": [
+                { "caller": { "name": "main" }, "callee": [{ "name": "common" }, { "name": "log" }] }
+            ]
+        });
+
+        let diff = diff_feature_outputs(&first, &second);
+        assert_eq!(diff.only_in_first, vec![("main".to_string(), "fast_path".to_string())]);
+        assert_eq!(diff.only_in_second, vec![("main".to_string(), "log".to_string())]);
+        assert_eq!(diff.to_string(), "- main -> fast_path\n+ main -> log\n");
+        assert!(diff_feature_outputs(&first, &first).is_empty());
+    }
+}
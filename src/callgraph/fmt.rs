@@ -1,28 +1,191 @@
+use crate::args::CGArgs;
 use crate::callgraph::CallGraph;
+use crate::callgraph::feature_combo::feature_combo_hash;
+use crate::callgraph::path_utils::sanitize_path_for_filename;
+use crate::callgraph::stats::{build_crate_graph, entry_points};
 use crate::callgraph::types::PathInfo;
-use crate::callgraph::utils::get_crate_version;
+use crate::callgraph::utils::{get_crate_version, matches_function_path, mir_failures, stable_hash};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
 use rustc_middle::ty::TyCtxt;
 use serde_json::json;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, Write};
-use std::path::Path;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use super::function::FunctionInstance;
-use super::types::CallSite;
+use super::types::{AnalysisError, CallSite, CallSortOrder, CallerSortOrder, OutputFormat, ResolutionSource};
 
 impl<'tcx> CallGraph<'tcx> {
+    /// Whether the constraint count of `call_site` is within `--min-constraints`/`--max-constraints`
+    fn within_constraint_range(&self, call_site: &CallSite<'tcx>, options: &CGArgs) -> bool {
+        let constraints = call_site.constraint_count();
+        options.min_constraints.is_none_or(|min| constraints >= min)
+            && options.max_constraints.is_none_or(|max| constraints <= max)
+    }
+
+    /// Number of call sites dropped by `--min-constraints`/`--max-constraints`, `None` if neither is set
+    pub(crate) fn constraint_filtered_count(&self, options: &CGArgs) -> Option<usize> {
+        if options.min_constraints.is_none() && options.max_constraints.is_none() {
+            return None;
+        }
+        Some(
+            self.call_sites
+                .iter()
+                .filter(|call_site| !self.within_constraint_range(call_site, options))
+                .count(),
+        )
+    }
+
+    /// Number of edges per [`ResolutionSource`], with every source present
+    pub(crate) fn edges_by_resolution(&self) -> BTreeMap<ResolutionSource, usize> {
+        let mut counts: BTreeMap<ResolutionSource, usize> =
+            ResolutionSource::ALL.into_iter().map(|source| (source, 0)).collect();
+        for call_site in &self.call_sites {
+            *counts.entry(call_site.resolution()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Call sites to include in call graph outputs
+    ///
+    /// When `--callers-in-crate` is set, only call sites whose caller belongs to that crate
+    /// are kept. Callees from any crate are kept, so outbound edges remain visible.
+    /// When `--hide-generated` is set, call sites from or to rustc-generated helpers are dropped.
+    /// When `--ffi-only` is set, only call sites with a non-Rust callee ABI are kept.
+    /// When `--min-constraints`/`--max-constraints` are set, only call sites within that range are kept.
+    pub(crate) fn output_call_sites(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> Vec<&CallSite<'tcx>> {
+        let call_sites = self
+            .call_sites
+            .iter()
+            .filter(|call_site| {
+                !options.hide_generated
+                    || !(call_site.caller().is_generated(tcx) || call_site.callee().is_generated(tcx))
+            })
+            .filter(|call_site| !options.ffi_only || call_site.is_foreign_abi())
+            .filter(|call_site| !options.cross_crate_only || call_site.package_num() != 0)
+            .filter(|call_site| self.within_constraint_range(call_site, options));
+
+        let Some(crate_name) = &options.callers_in_crate else {
+            return call_sites.collect();
+        };
+
+        let crate_name = crate_name.replace('-', "_");
+        let matches_crate = |krate: rustc_hir::def_id::CrateNum| {
+            if crate_name.is_empty() {
+                krate == rustc_hir::def_id::LOCAL_CRATE
+            } else {
+                tcx.crate_name(krate).as_str() == crate_name
+            }
+        };
+
+        call_sites
+            .filter(|call_site| matches_crate(call_site.caller().def_id().krate))
+            .collect()
+    }
+
+    /// Function an output edge starts at: the caller, or the callee with `--transpose`
+    pub(crate) fn edge_source(&self, call_site: &CallSite<'tcx>, options: &CGArgs) -> FunctionInstance<'tcx> {
+        if options.transpose {
+            call_site.callee()
+        } else {
+            call_site.caller()
+        }
+    }
+
+    /// Function an output edge points to: the callee, or the caller with `--transpose`
+    pub(crate) fn edge_target(&self, call_site: &CallSite<'tcx>, options: &CGArgs) -> FunctionInstance<'tcx> {
+        if options.transpose {
+            call_site.caller()
+        } else {
+            call_site.callee()
+        }
+    }
+
+    /// Sort the calls of one caller for output
+    ///
+    /// Calls are sorted by callee and constraint count, or by MIR basic block with
+    /// `--sort bb-order`, and first by descending loop depth when `--sort-by-loop-depth` is set.
+    fn sort_calls(&self, tcx: TyCtxt<'tcx>, calls: &mut [&CallSite<'tcx>], options: &CGArgs) {
+        calls.sort_by(|a, b| {
+            let a_name = self.function_path(tcx, self.edge_target(a, options));
+            let b_name = self.function_path(tcx, self.edge_target(b, options));
+            let by_loop_depth = if options.sort_by_loop_depth {
+                b.loop_depth().cmp(&a.loop_depth())
+            } else {
+                std::cmp::Ordering::Equal
+            };
+            let by_order = match options.sort {
+                CallSortOrder::Name => a_name
+                    .cmp(&b_name)
+                    .then_with(|| a.constraint_count().cmp(&b.constraint_count())),
+                CallSortOrder::BbOrder => a.basic_block().cmp(&b.basic_block()).then_with(|| a_name.cmp(&b_name)),
+            };
+            by_loop_depth.then(by_order)
+        });
+    }
+
+    /// Node `func` is shown as in text output
+    ///
+    /// With `--collapse-generics`, all instances of a function share one node, keyed by its `DefId`.
+    fn output_node(&self, func: FunctionInstance<'tcx>, options: &CGArgs) -> FunctionInstance<'tcx> {
+        if options.collapse_generics {
+            FunctionInstance::new_non_instance(func.def_id())
+        } else {
+            func
+        }
+    }
+
+    /// Entry distance of an output node, the minimum over its instances with `--collapse-generics`
+    fn output_node_entry_distance(&self, node: FunctionInstance<'tcx>, options: &CGArgs) -> Option<usize> {
+        if !options.collapse_generics {
+            return self.entry_distance(node);
+        }
+        self.entry_distances
+            .as_ref()?
+            .iter()
+            .filter(|(func, _)| func.def_id() == node.def_id())
+            .map(|(_, distance)| *distance)
+            .min()
+    }
+
+    /// Merge the calls to instances of the same callee, keeping the least constrained one
+    ///
+    /// Only merges with `--collapse-generics`, where the calls of all instances of a caller
+    /// are grouped together.
+    fn merge_output_calls<'a>(&self, calls: &[&'a CallSite<'tcx>], options: &CGArgs) -> Vec<&'a CallSite<'tcx>> {
+        if !options.collapse_generics {
+            return calls.to_vec();
+        }
+        let mut merged: HashMap<FunctionInstance<'tcx>, &'a CallSite<'tcx>> = HashMap::new();
+        for &call in calls {
+            merged
+                .entry(self.output_node(self.edge_target(call, options), options))
+                .and_modify(|kept| {
+                    if call.constraint_count() < kept.constraint_count() {
+                        *kept = call;
+                    }
+                })
+                .or_insert(call);
+        }
+        merged.into_values().collect()
+    }
+
     /// Format the call graph as readable text
-    pub(crate) fn format_call_graph(&self, tcx: TyCtxt<'tcx>) -> String {
+    pub(crate) fn format_call_graph(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
         let mut result = String::new();
 
         result.push_str("Call Graph:\n");
         result.push_str("===========\n\n");
 
-        // Organize calls by caller
+        // Organize calls by caller, merging the instances of a caller with `--collapse-generics`
         let mut calls_by_caller: HashMap<FunctionInstance<'tcx>, Vec<&CallSite<'tcx>>> = HashMap::new();
 
-        for call_site in &self.call_sites {
-            calls_by_caller.entry(call_site.caller()).or_default().push(call_site);
+        for call_site in self.output_call_sites(tcx, options) {
+            calls_by_caller
+                .entry(self.output_node(self.edge_source(call_site, options), options))
+                .or_default()
+                .push(call_site);
         }
 
         // Sort callers to get consistent output
@@ -31,29 +194,44 @@ impl<'tcx> CallGraph<'tcx> {
 
         for caller in callers {
             // Get caller name
-            let caller_name = caller.full_path(tcx, self.without_args);
-            result.push_str(&format!("Function: {caller_name}\n"));
+            let caller_name = self.function_path(tcx, caller);
+            result.push_str(&format!("Function: {caller_name}"));
+            if let Some(distance) = self.output_node_entry_distance(caller, options) {
+                result.push_str(&format!(" [distance: {distance}]"));
+            }
+            result.push('\n');
 
             // Get all calls from this caller
             if let Some(calls) = calls_by_caller.get(&caller) {
                 // Sort by callee and constraint count
-                let mut sorted_calls = calls.clone();
-                sorted_calls.sort_by(|a, b| {
-                    let a_name = a.callee().full_path(tcx, self.without_args);
-                    let b_name = b.callee().full_path(tcx, self.without_args);
-                    a_name
-                        .cmp(&b_name)
-                        .then_with(|| a.constraint_count().cmp(&b.constraint_count()))
-                });
+                let mut sorted_calls = self.merge_output_calls(calls, options);
+                self.sort_calls(tcx, &mut sorted_calls, options);
 
                 // Output call information
                 for call in sorted_calls {
-                    let callee_name = call.callee().full_path(tcx, self.without_args);
-                    result.push_str(&format!(
-                        "  -> {} [constraint: {}]\n",
-                        callee_name,
-                        call.constraint_count()
-                    ));
+                    let callee = self.output_node(self.edge_target(call, options), options);
+                    let callee_name = self.function_path(tcx, callee);
+                    if options.sort_by_loop_depth {
+                        result.push_str(&format!(
+                            "  -> {} [constraint: {}, loop depth: {}]",
+                            callee_name,
+                            call.constraint_count(),
+                            call.loop_depth()
+                        ));
+                    } else {
+                        result.push_str(&format!(
+                            "  -> {} [constraint: {}]",
+                            callee_name,
+                            call.constraint_count()
+                        ));
+                    }
+                    if call.is_foreign_abi() {
+                        result.push_str(&format!(" [abi: {}]", call.abi()));
+                    }
+                    if let Some(distance) = self.output_node_entry_distance(callee, options) {
+                        result.push_str(&format!(" [distance: {distance}]"));
+                    }
+                    result.push('\n');
                 }
 
                 result.push('\n');
@@ -64,12 +242,131 @@ impl<'tcx> CallGraph<'tcx> {
     }
 
     /// Format the call graph as JSON
-    pub(crate) fn format_call_graph_as_json(&self, tcx: TyCtxt<'tcx>) -> String {
+    pub(crate) fn format_call_graph_as_json(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let call_sites = self.output_call_sites(tcx, options);
+        let json_entries = self.call_graph_json_entries(tcx, &call_sites, options);
+
+        // Format the entire array as a pretty-printed JSON string
+        serde_json::to_string_pretty(&json_entries).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Format the call graph as JSON with a node table and edges between numeric node ids
+    ///
+    /// Ids are assigned in `(path_hash, name)` order, so they only change when the set of
+    /// functions does. Each edge keeps the smallest constraint count of its call sites.
+    pub(crate) fn format_call_graph_as_numeric_json(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let call_sites = self.output_call_sites(tcx, options);
+
+        let mut nodes: Vec<(String, String, FunctionInstance<'tcx>)> = call_sites
+            .iter()
+            .flat_map(|call_site| {
+                [
+                    self.edge_source(call_site, options),
+                    self.edge_target(call_site, options),
+                ]
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|func| (func.path_hash(tcx), self.function_path(tcx, func).to_string(), func))
+            .collect();
+        nodes.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        let ids: HashMap<FunctionInstance<'tcx>, usize> =
+            nodes.iter().enumerate().map(|(id, (_, _, func))| (*func, id)).collect();
+
+        let mut edges: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+        for call_site in &call_sites {
+            let constraints = edges
+                .entry((
+                    ids[&self.edge_source(call_site, options)],
+                    ids[&self.edge_target(call_site, options)],
+                ))
+                .or_insert(usize::MAX);
+            *constraints = (*constraints).min(call_site.constraint_count());
+        }
+
+        let nodes = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(id, (path_hash, name, func))| {
+                json!({
+                    "id": id,
+                    "name": name,
+                    "path": tcx.def_path_str(func.def_id()),
+                    "crate": tcx.crate_name(func.def_id().krate).to_string(),
+                    "version": get_crate_version(tcx, func.def_id()),
+                    "path_hash": path_hash,
+                })
+            })
+            .collect::<Vec<_>>();
+        let edges = edges
+            .into_iter()
+            .map(|((from, to), constraint_count)| json!({ "from": from, "to": to, "constraint_count": constraint_count }))
+            .collect::<Vec<_>>();
+
+        let mut result = json!({
+            "crate_name": tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string(),
+            "nodes": nodes,
+            "edges": edges
+        });
+        if let Some(compile_target) = &self.compile_target {
+            result["compile_target"] = json!(compile_target);
+        }
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Format the call graph as one JSON document per callee crate
+    ///
+    /// Returns `(target_crate_name, json)` pairs, where each document only contains
+    /// the call sites whose callee is defined in `target_crate_name`.
+    pub(crate) fn format_per_crate_call_graphs_as_json(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        options: &CGArgs,
+    ) -> Vec<(String, String)> {
+        let source_crate = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+
+        // Group call sites by the crate of the callee
+        let mut calls_by_crate: HashMap<rustc_hir::def_id::CrateNum, Vec<&CallSite<'tcx>>> = HashMap::new();
+        for call_site in self.output_call_sites(tcx, options) {
+            calls_by_crate
+                .entry(self.edge_target(call_site, options).def_id().krate)
+                .or_default()
+                .push(call_site);
+        }
+
+        let mut outputs = Vec::new();
+        for (krate, calls) in calls_by_crate {
+            let target_crate = tcx.crate_name(krate).to_string();
+            let entries = self.call_graph_json_entries(tcx, &calls, options);
+            let result = json!({
+                "source_crate": source_crate,
+                "target_crate": target_crate,
+                "call_graph": entries
+            });
+            outputs.push((
+                target_crate,
+                serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string()),
+            ));
+        }
+        outputs.sort_by(|a, b| a.0.cmp(&b.0));
+        outputs
+    }
+
+    /// Build the caller/callee JSON records for the given call sites
+    fn call_graph_json_entries(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        call_sites: &[&CallSite<'tcx>],
+        options: &CGArgs,
+    ) -> Vec<serde_json::Value> {
         // Create a map to organize calls by caller
         let mut calls_by_caller: HashMap<FunctionInstance<'tcx>, Vec<&CallSite<'tcx>>> = HashMap::new();
 
-        for call_site in &self.call_sites {
-            calls_by_caller.entry(call_site.caller()).or_default().push(call_site);
+        for &call_site in call_sites {
+            calls_by_caller
+                .entry(self.edge_source(call_site, options))
+                .or_default()
+                .push(call_site);
         }
 
         // Sort callers to get consistent output
@@ -81,7 +378,7 @@ impl<'tcx> CallGraph<'tcx> {
 
         for caller in callers {
             // Get caller name and information
-            let caller_name = caller.full_path(tcx, self.without_args);
+            let caller_name = self.function_path(tcx, caller).to_string();
             let caller_def_id = caller.def_id();
             let caller_path = tcx.def_path_str(caller_def_id);
 
@@ -89,32 +386,64 @@ impl<'tcx> CallGraph<'tcx> {
             if let Some(calls) = calls_by_caller.get(&caller) {
                 // Sort by callee for consistent output
                 let mut sorted_calls = calls.clone();
-                sorted_calls.sort_by(|a, b| {
-                    let a_name = a.callee().full_path(tcx, self.without_args);
-                    let b_name = b.callee().full_path(tcx, self.without_args);
-                    a_name
-                        .cmp(&b_name)
-                        .then_with(|| a.constraint_count().cmp(&b.constraint_count()))
-                });
+                self.sort_calls(tcx, &mut sorted_calls, options);
 
                 // Create an array of callee objects
                 let mut callees = Vec::new();
                 for call in sorted_calls {
-                    let callee_name = call.callee().full_path(tcx, self.without_args);
-                    let callee_def_id = call.callee().def_id();
+                    // The listed function, and the called one that per-call attributes describe;
+                    // they differ with `--transpose`
+                    let listed = self.edge_target(call, options);
+                    let called = call.callee();
+                    let callee_name = self.function_path(tcx, listed).to_string();
+                    let callee_def_id = listed.def_id();
                     let callee_path = tcx.def_path_str(callee_def_id);
 
                     // Get actual version information for this callee
                     let version = get_crate_version(tcx, callee_def_id);
 
                     // Add callee entry
-                    callees.push(json!({
+                    let mut callee_entry = json!({
                         "name": callee_name,
                         "version": version,
                         "path": callee_path,
                         "constraint_depth": call.constraint_count(),
-                        "package_num": call.package_num()
-                    }));
+                        "loop_depth": call.loop_depth(),
+                        "package_num": call.package_num(),
+                        "resolution": call.resolution().as_str(),
+                        "abi": call.abi(),
+                        "arg_count": call.arg_count(),
+                        "path_hash": listed.path_hash(tcx),
+                        "resolved": called.is_instance(),
+                        "is_build_script": self.is_build_script,
+                        "is_proc_macro": self.is_proc_macro
+                    });
+                    if let Some(declared) = called.declared_arg_count(tcx) {
+                        callee_entry["declared_arg_count"] = json!(declared);
+                        // `rust-call` calls through `Fn*` traits pass the closure and an argument tuple
+                        if let Some(arg_count) = call.arg_count()
+                            && call.abi() != "rust-call"
+                        {
+                            callee_entry["arg_count_mismatch"] = json!(arg_count != declared);
+                        }
+                    }
+                    if let Some(location) = call.call_location() {
+                        callee_entry["call_location"] = json!(location.to_string());
+                    }
+                    if let Some(assoc_info) = called.assoc_info(tcx) {
+                        callee_entry["impl_self_ty"] = json!(assoc_info.impl_self_ty);
+                        callee_entry["trait"] = json!(assoc_info.trait_path);
+                    }
+                    if let Some(may_panic) = self.may_panic(tcx, called) {
+                        callee_entry["may_panic"] = json!(may_panic);
+                    }
+                    if let Some(distance) = self.entry_distance(listed) {
+                        callee_entry["distance_from_entry"] = json!(distance);
+                    }
+                    if let Some(depth) = self.depth_from_entry(listed) {
+                        callee_entry["depth_from_entry"] = json!(depth);
+                    }
+                    callees.push(callee_entry);
                 }
 
                 // Get actual version information for caller
@@ -124,34 +453,465 @@ impl<'tcx> CallGraph<'tcx> {
                 let max_constraint_depth = calls.iter().map(|c| c.constraint_count()).max().unwrap_or(0);
 
                 // Create the full entry with caller and callees
-                let entry = json!({
+                let mut entry = json!({
                     "caller": {
                         "name": caller_name,
                         "version": caller_version,
                         "path": caller_path,
+                        "path_hash": caller.path_hash(tcx),
+                        "resolved": caller.is_instance(),
                         "constraint_depth": max_constraint_depth,
                     },
                     "callee": callees
                 });
+                if let Some(distance) = self.entry_distance(caller) {
+                    entry["caller"]["distance_from_entry"] = json!(distance);
+                }
+                if let Some(depth) = self.depth_from_entry(caller) {
+                    entry["caller"]["depth_from_entry"] = json!(depth);
+                }
+                if let Some(compile_target) = &self.compile_target {
+                    entry["compile_target"] = json!(compile_target);
+                }
 
                 json_entries.push(entry);
             }
         }
 
-        // Format the entire array as a pretty-printed JSON string
-        serde_json::to_string_pretty(&json_entries).unwrap_or_else(|_| "[]".to_string())
+        json_entries
+    }
+
+    /// Format the call graph of a feature-combination run as JSON
+    ///
+    /// The call graph entries are wrapped together with the enabled features,
+    /// so outputs of different combinations can be diffed with `compare_feature_outputs`.
+    pub(crate) fn format_feature_combo_as_json(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        features: &[String],
+        options: &CGArgs,
+    ) -> String {
+        let call_sites = self.output_call_sites(tcx, options);
+        let result = json!({
+            "crate_name": tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string(),
+            "features": features,
+            "call_graph": self.call_graph_json_entries(tcx, &call_sites, options)
+        });
+
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Format the call graph as a Graphviz digraph
+    ///
+    /// Edges are labeled with the constraint count of the call site. With `--dot-cluster`,
+    /// nodes are grouped into one `cluster_<crate>` subgraph per crate, and edges between
+    /// crates are dashed. With `--entry-point`, node labels include the depth from the entry.
+    pub(crate) fn format_call_graph_as_dot(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let node_crate = |func: FunctionInstance<'tcx>| tcx.crate_name(func.def_id().krate).to_string();
+
+        let mut edges: Vec<(String, String, usize, bool)> = Vec::new();
+        let mut clusters: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut depths: BTreeMap<String, usize> = BTreeMap::new();
+        for call_site in self.output_call_sites(tcx, options) {
+            let caller = self
+                .function_path(tcx, self.edge_source(call_site, options))
+                .to_string();
+            let callee = self
+                .function_path(tcx, self.edge_target(call_site, options))
+                .to_string();
+            for (func, name) in [
+                (self.edge_source(call_site, options), &caller),
+                (self.edge_target(call_site, options), &callee),
+            ] {
+                // Instances sharing a name keep the smallest depth
+                if let Some(depth) = self.depth_from_entry(func) {
+                    let node_depth = depths.entry(name.clone()).or_insert(depth);
+                    *node_depth = (*node_depth).min(depth);
+                }
+            }
+            let caller_crate = node_crate(self.edge_source(call_site, options));
+            let callee_crate = node_crate(self.edge_target(call_site, options));
+            let cross_crate = caller_crate != callee_crate;
+            if options.dot_cluster {
+                clusters.entry(caller_crate).or_default().insert(caller.clone());
+                clusters.entry(callee_crate).or_default().insert(callee.clone());
+            }
+            edges.push((caller, callee, call_site.constraint_count(), cross_crate));
+        }
+        edges.sort();
+        edges.dedup();
+
+        let node_attrs = |node: &str| match depths.get(node) {
+            Some(depth) => format!(" [label=\"{}\\ndepth: {}\"]", escape_dot(node), depth),
+            None => String::new(),
+        };
+
+        let mut result = String::new();
+        result.push_str("digraph callgraph {\n");
+        result.push_str("    node [shape=box];\n");
+        for (crate_name, nodes) in &clusters {
+            result.push_str(&format!("    subgraph cluster_{crate_name} {{\n"));
+            result.push_str(&format!(
+                "        label=\"{}\";\n        style=filled;\n        fillcolor=\"{}\";\n",
+                escape_dot(crate_name),
+                cluster_color(crate_name)
+            ));
+            for node in nodes {
+                result.push_str(&format!("        \"{}\"{};\n", escape_dot(node), node_attrs(node)));
+            }
+            result.push_str("    }\n");
+        }
+        if !options.dot_cluster {
+            for node in depths.keys() {
+                result.push_str(&format!("    \"{}\"{};\n", escape_dot(node), node_attrs(node)));
+            }
+        }
+        for (caller, callee, constraints, cross_crate) in edges {
+            let style = if options.dot_cluster {
+                if cross_crate { ", style=dashed" } else { ", style=solid" }
+            } else {
+                ""
+            };
+            result.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];\n",
+                escape_dot(&caller),
+                escape_dot(&callee),
+                constraints,
+                style
+            ));
+        }
+        result.push_str("}\n");
+        result
+    }
+
+    /// Format the call graph with one `caller -> callee [constraints]` line per call site
+    ///
+    /// There are no headers and no grouping by caller, so every line stands on its own
+    /// for line-based tools like `grep`, `sort` and `uniq`.
+    pub(crate) fn format_call_graph_compact(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let mut lines: Vec<String> = self
+            .output_call_sites(tcx, options)
+            .into_iter()
+            .map(|call_site| {
+                format!(
+                    "{} -> {} [{}]\n",
+                    self.function_path(tcx, self.edge_source(call_site, options)),
+                    self.function_path(tcx, self.edge_target(call_site, options)),
+                    call_site.constraint_count()
+                )
+            })
+            .collect();
+        lines.sort();
+        lines.concat()
+    }
+
+    /// Format the calls into other crates, grouped by callee crate and version
+    pub(crate) fn format_cross_crate_calls(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let mut calls_by_crate: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+        for call_site in self.output_call_sites(tcx, options) {
+            let callee_def_id = call_site.callee().def_id();
+            if call_site.package_num() == 0 {
+                continue;
+            }
+            calls_by_crate
+                .entry((
+                    tcx.crate_name(callee_def_id.krate).to_string(),
+                    get_crate_version(tcx, callee_def_id),
+                ))
+                .or_default()
+                .insert(format!(
+                    "{} -> {} [constraint: {}]",
+                    self.function_path(tcx, call_site.caller()),
+                    self.function_path(tcx, call_site.callee()),
+                    call_site.constraint_count()
+                ));
+        }
+
+        let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE);
+        let mut result = format!("Cross-Crate Calls of {crate_name}:\n");
+        result.push_str("==========================\n\n");
+        for ((callee_crate, version), calls) in &calls_by_crate {
+            result.push_str(&format!("{callee_crate} {version} ({} calls):\n", calls.len()));
+            for call in calls {
+                result.push_str(&format!("  {call}\n"));
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Format the call graph as CSV, one row per call site
+    pub(crate) fn format_call_graph_as_csv(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let mut rows: Vec<[String; 6]> = self
+            .output_call_sites(tcx, options)
+            .into_iter()
+            .map(|call_site| {
+                [
+                    self.function_path(tcx, self.edge_source(call_site, options))
+                        .to_string(),
+                    self.function_path(tcx, self.edge_target(call_site, options))
+                        .to_string(),
+                    call_site.constraint_count().to_string(),
+                    call_site.loop_depth().to_string(),
+                    format!("{:?}", call_site.call_kind()),
+                    call_site.resolution().as_str().to_string(),
+                ]
+            })
+            .collect();
+        rows.sort();
+
+        let mut result = String::from("caller,callee,constraint_depth,loop_depth,call_kind,resolution\n");
+        for row in rows {
+            let fields = row.iter().map(String::as_str).map(escape_csv).collect::<Vec<_>>();
+            result.push_str(&fields.join(","));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Format the call graph as a Mermaid flowchart
+    ///
+    /// Nodes get short ids (`n0`, `n1`, ...) since paths are not valid Mermaid ids.
+    pub(crate) fn format_call_graph_as_mermaid(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let edges = self.output_edges(tcx, options);
+
+        let mut node_ids: HashMap<String, usize> = HashMap::new();
+        let mut result = String::from("flowchart LR\n");
+        for (caller, callee, constraints) in &edges {
+            for name in [caller, callee] {
+                if !node_ids.contains_key(name) {
+                    let id = node_ids.len();
+                    node_ids.insert(name.clone(), id);
+                    result.push_str(&format!("    n{}[\"{}\"]\n", id, escape_mermaid(name)));
+                }
+            }
+            result.push_str(&format!(
+                "    n{} -->|{}| n{}\n",
+                node_ids[caller], constraints, node_ids[callee]
+            ));
+        }
+        result
+    }
+
+    /// Sorted `(caller, callee, constraint count)` edges of the output call sites
+    fn output_edges(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> Vec<(String, String, usize)> {
+        let mut edges: Vec<(String, String, usize)> = self
+            .output_call_sites(tcx, options)
+            .into_iter()
+            .map(|call_site| {
+                (
+                    self.function_path(tcx, self.edge_source(call_site, options))
+                        .to_string(),
+                    self.function_path(tcx, self.edge_target(call_site, options))
+                        .to_string(),
+                    call_site.constraint_count(),
+                )
+            })
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Format the call graph as Cytoscape.js elements JSON
+    ///
+    /// Nodes are deduplicated by their rendered path, and each edge's `weight`
+    /// is the constraint count of the call site.
+    pub(crate) fn format_call_graph_as_cytoscape(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let mut node_names: HashSet<String> = HashSet::new();
+        let mut edges = Vec::new();
+
+        for call_site in self.output_call_sites(tcx, options) {
+            let caller_name = self
+                .function_path(tcx, self.edge_source(call_site, options))
+                .to_string();
+            let callee_name = self
+                .function_path(tcx, self.edge_target(call_site, options))
+                .to_string();
+            node_names.insert(caller_name.clone());
+            node_names.insert(callee_name.clone());
+            edges.push((caller_name, callee_name, call_site.constraint_count()));
+        }
+
+        // Sort to get consistent output
+        let mut node_names: Vec<String> = node_names.into_iter().collect();
+        node_names.sort();
+        edges.sort();
+
+        let nodes = node_names
+            .into_iter()
+            .map(|name| json!({ "data": { "id": name, "label": name } }))
+            .collect::<Vec<_>>();
+        let edges = edges
+            .into_iter()
+            .map(|(source, target, weight)| json!({ "data": { "source": source, "target": target, "weight": weight } }))
+            .collect::<Vec<_>>();
+
+        let result = json!({
+            "elements": {
+                "nodes": nodes,
+                "edges": edges
+            }
+        });
+
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// Format analysis statistics as JSON
+    pub(crate) fn format_statistics_as_json(&self, tcx: TyCtxt<'tcx>, options: &CGArgs) -> String {
+        let skipped_large_functions = self
+            .skipped_large_functions
+            .iter()
+            .map(|(func, block_count)| {
+                json!({
+                    "name": self.function_path(tcx, *func).to_string(),
+                    "path": tcx.def_path_str(func.def_id()),
+                    "basic_blocks": block_count
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut result = json!({
+            "crate_name": tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string(),
+            "compile_target": self.compile_target,
+            "total_functions": self.total_functions,
+            "total_call_sites": self.call_sites.len(),
+            "monomorphization": {
+                "ok": self.mono_stats.ok,
+                "normalization_failures": self.mono_stats.normalization_failures,
+                "resolve_failures": self.mono_stats.resolve_failures,
+                "trivial_resolve_fallbacks": self.mono_stats.trivial_resolve_fallbacks,
+                "unresolved": self.mono_stats.unresolved
+            },
+            "resolution": {
+                "edges": self
+                    .edges_by_resolution()
+                    .into_iter()
+                    .map(|(source, count)| (source.as_str().to_string(), json!(count)))
+                    .collect::<serde_json::Map<_, _>>(),
+                "candidate_call_sites": self.mono_stats.candidate_call_sites
+            },
+            "skipped_large_functions": skipped_large_functions,
+            "mir_failures": mir_failures(tcx)
+                .into_iter()
+                .map(|(path, error)| json!({ "path": path, "error": error }))
+                .collect::<Vec<_>>(),
+            "analysis_errors": self.analysis_errors_json(tcx),
+            "truncated": self.truncated
+        });
+        if let Some(filtered) = self.constraint_filtered_count(options) {
+            result["filtered_by_constraints"] = json!(filtered);
+        }
+        if self.version_filter_applied {
+            result["version_filter_applied"] = json!(true);
+        }
+        if let Some(skipped) = self.skipped_extern_impls {
+            result["skipped_extern_impls"] = json!(skipped);
+        }
+        if let Some(excluded) = self.excluded_generated {
+            result["excluded_generated"] = json!(excluded);
+        }
+        if let Some(pruned) = self.pruned_by_deps_depth {
+            result["pruned_by_deps_depth"] = json!(pruned);
+        }
+        if let Some(top_n) = options.top_instantiations {
+            result["top_instantiations"] = json!(
+                self.sorted_instantiation_counts(tcx)
+                    .into_iter()
+                    .take(top_n)
+                    .map(|(path, count)| json!({ "path": path, "instantiations": count }))
+                    .collect::<Vec<_>>()
+            );
+        }
+        if let Some(histogram) = self.distance_histogram() {
+            result["max_distance"] = json!(histogram.keys().next_back().copied().unwrap_or(0));
+            result["distance_histogram"] = json!(histogram);
+        }
+        if let Some(chain) = &self.deepest_call_chain {
+            result["max_call_depth"] = json!(chain.len());
+            result["deepest_chain"] = json!(
+                chain
+                    .iter()
+                    .map(|func| self.function_path(tcx, *func).to_string())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// [`AnalysisError`]s as JSON, sorted by kind and function for stable output
+    fn analysis_errors_json(&self, tcx: TyCtxt<'tcx>) -> Vec<serde_json::Value> {
+        let mut errors: Vec<serde_json::Value> = self
+            .errors
+            .iter()
+            .map(|error| match error {
+                AnalysisError::MonomorphizationFailed { func, error: message } => json!({
+                    "kind": error.kind(),
+                    "function": self.function_path(tcx, *func).to_string(),
+                    "error": message
+                }),
+                AnalysisError::InstanceResolutionFailed { func, callee } => json!({
+                    "kind": error.kind(),
+                    "function": self.function_path(tcx, *func).to_string(),
+                    "callee": tcx.def_path_str(*callee)
+                }),
+                AnalysisError::MirUnavailable { def_id } => json!({
+                    "kind": error.kind(),
+                    "function": tcx.def_path_str(*def_id)
+                }),
+                AnalysisError::TimeoutExceeded { func } => json!({
+                    "kind": error.kind(),
+                    "function": self.function_path(tcx, *func).to_string()
+                }),
+            })
+            .collect();
+        errors.sort_by_key(|error| error.to_string());
+        errors
+    }
+
+    /// Format the number of distinct monomorphizations of each generic function,
+    /// see [`super::stats::compute_instantiation_counts`]
+    pub(crate) fn format_mono_report(&self, tcx: TyCtxt<'tcx>) -> String {
+        let mut result = String::new();
+        result.push_str("Monomorphization Report:\n");
+        result.push_str("========================\n\n");
+        for (path, count) in self.sorted_instantiation_counts(tcx) {
+            let noun = if count == 1 { "instantiation" } else { "instantiations" };
+            result.push_str(&format!("{path} -> {count} {noun}\n"));
+        }
+
+        result
+    }
+
+    /// Sort `--find-callers` results in the order selected with `--sort-callers-by`
+    ///
+    /// Ties are broken by the default order of [`PathInfo`], so the output is stable.
+    fn sort_callers(&self, callers: &mut [PathInfo<'tcx>], options: &CGArgs) {
+        match options.sort_callers_by {
+            CallerSortOrder::Name => callers.sort(),
+            CallerSortOrder::Constraints => {
+                callers.sort_by(|a, b| a.constraints.cmp(&b.constraints).then_with(|| a.cmp(b)))
+            }
+            CallerSortOrder::Distance => callers.sort_by(|a, b| a.hop_count.cmp(&b.hop_count).then_with(|| a.cmp(b))),
+        }
     }
 
     /// Format caller information as readable text
-    pub(crate) fn format_callers(&self, tcx: TyCtxt<'tcx>, target_path: &str, callers: Vec<PathInfo<'tcx>>) -> String {
+    pub(crate) fn format_callers(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        target_path: &str,
+        callers: Vec<PathInfo<'tcx>>,
+        options: &CGArgs,
+    ) -> String {
         let mut result = String::new();
 
         result.push_str(&format!("Callers of functions matching '{target_path}':\n"));
         result.push_str("==================================\n\n");
 
-        // Sort callers to get consistent output - first by constraint count, then by name
         let mut sorted_callers = callers;
-        sorted_callers.sort();
+        self.sort_callers(&mut sorted_callers, options);
 
         for PathInfo {
             caller,
@@ -160,17 +920,18 @@ impl<'tcx> CallGraph<'tcx> {
             package_num,
             package_num_unique,
             path_len,
+            hop_count,
             ..
         } in &sorted_callers
         {
-            let caller_name = caller.full_path(tcx, self.without_args);
+            let caller_name = self.function_path(tcx, *caller);
             let call_path = call_path
                 .iter()
-                .map(|node| node.full_path(tcx, self.without_args))
+                .map(|node| self.function_path(tcx, *node))
                 .collect::<Vec<_>>()
                 .join(" -> ");
             result.push_str(&format!(
-                "- {caller_name} [path constraints: {constraints}, package num: {package_num}, package num unique: {package_num_unique}, path len: {path_len}]\n"
+                "- {caller_name} [path constraints: {constraints}, package num: {package_num}, package num unique: {package_num_unique}, path len: {path_len}, hop count: {hop_count}]\n"
             ));
             result.push_str(&format!("  path: {call_path}\n"));
         }
@@ -179,16 +940,45 @@ impl<'tcx> CallGraph<'tcx> {
         result
     }
 
+    /// Format caller information with one `caller -> target [path constraints]` line per caller
+    ///
+    /// The target is the matched function the caller's least constrained path ends at.
+    pub(crate) fn format_callers_compact(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        callers: Vec<PathInfo<'tcx>>,
+        options: &CGArgs,
+    ) -> String {
+        let mut sorted_callers = callers;
+        self.sort_callers(&mut sorted_callers, options);
+        let mut lines: Vec<String> = sorted_callers
+            .iter()
+            .map(|path_info| {
+                let target = path_info.call_path.last().copied().unwrap_or(path_info.caller);
+                format!(
+                    "{} -> {} [{}]\n",
+                    self.function_path(tcx, path_info.caller),
+                    self.function_path(tcx, target),
+                    path_info.constraints
+                )
+            })
+            .collect();
+        if options.sort_callers_by == CallerSortOrder::Name {
+            lines.sort();
+        }
+        lines.concat()
+    }
+
     /// Format caller information as JSON
     pub(crate) fn format_callers_as_json(
         &self,
         tcx: TyCtxt<'tcx>,
         target_path: &str,
         callers: Vec<PathInfo<'tcx>>,
+        options: &CGArgs,
     ) -> String {
-        // Sort callers to get consistent output - first by constraint count, then by name
         let mut sorted_callers = callers;
-        sorted_callers.sort();
+        self.sort_callers(&mut sorted_callers, options);
 
         // Create array for caller information
         let mut caller_entries = Vec::new();
@@ -200,17 +990,19 @@ impl<'tcx> CallGraph<'tcx> {
             package_num,
             package_num_unique,
             path_len,
+            hop_count,
             dyn_edges,
             fnptr_edges,
             generic_args_len_sum,
+            constraint_kinds,
         } in &sorted_callers
         {
-            let caller_name = caller.full_path(tcx, self.without_args);
+            let caller_name = self.function_path(tcx, *caller).to_string();
             let caller_def_id = caller.def_id();
             let caller_path = tcx.def_path_str(caller_def_id);
             let call_path = call_path
                 .iter()
-                .map(|node| node.full_path(tcx, self.without_args))
+                .map(|node| self.function_path(tcx, *node).to_string())
                 .collect::<Vec<_>>();
 
             // Get version information
@@ -222,9 +1014,12 @@ impl<'tcx> CallGraph<'tcx> {
                 "version": version,
                 "path": caller_path,
                 "path_constraints": constraints,
+                "constraint_kinds": constraint_kinds.to_json(),
                 "path_package_num": package_num,
+                "package_edges": package_num,
                 "path_package_num_unique": package_num_unique,
                 "path_len": path_len,
+                "hop_count": hop_count,
                 "call_path": call_path,
                 "path_dyn_edges": dyn_edges,
                 "path_fnptr_edges": fnptr_edges,
@@ -258,7 +1053,13 @@ impl<'tcx> CallGraph<'tcx> {
         let mut target_callees: HashSet<FunctionInstance<'tcx>> = HashSet::new();
         let mut kind_counts: HashMap<&'static str, usize> = HashMap::new();
         for cs in &self.call_sites {
-            if crate::callgraph::utils::matches_function_path(tcx, cs.callee(), target_path, self.without_args) {
+            if crate::callgraph::utils::matches_function_path(
+                tcx,
+                cs.callee(),
+                target_path,
+                self.without_args,
+                options.no_generic_stripping,
+            ) {
                 target_callees.insert(cs.callee());
                 let k = match cs.call_kind() {
                     crate::callgraph::types::CallKind::Direct => "Direct",
@@ -334,59 +1135,468 @@ impl<'tcx> CallGraph<'tcx> {
     }
 }
 
-pub(crate) fn output_call_graph_result<'tcx>(
-    call_graph: &CallGraph<'tcx>,
-    tcx: TyCtxt<'tcx>,
-    options: &crate::args::CGArgs,
-) {
-    let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+pub(crate) fn output_call_graph_result<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'tcx>, options: &CGArgs) {
+    let crate_name = sanitize_path_for_filename(tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).as_str());
 
     let output_dir = options
         .output_dir
         .clone()
         .unwrap_or_else(|| std::path::PathBuf::from("./target"));
-    let output_path = output_dir.join(format!("{crate_name}-callgraph.txt"));
+    if let Some(filtered) = call_graph.constraint_filtered_count(options) {
+        tracing::info!(
+            "Filtered {} of {} call sites outside the constraint range",
+            filtered,
+            call_graph.call_sites.len()
+        );
+    }
 
-    // If JSON output is requested, write call graph as JSON
-    if options.json_output {
-        let json_output = call_graph.format_call_graph_as_json(tcx);
+    // The crate-level graph replaces the function-level outputs
+    let formats = if options.crate_graph {
+        output_crate_graph(call_graph, tcx, options, &output_dir);
+        Vec::new()
+    } else {
+        options.effective_output_formats()
+    };
 
-        // Output to file
-        let output_path = options
-            .output_dir
-            .clone()
-            .unwrap_or_else(|| std::path::PathBuf::from("./target"))
-            .join("callgraph.json");
+    // Write the call graph in every requested format
+    for format in formats {
+        let (file_name, output) = match format {
+            OutputFormat::Text if options.compact_text => (
+                format!("{crate_name}-callgraph.txt"),
+                call_graph.format_call_graph_compact(tcx, options),
+            ),
+            OutputFormat::Text => (
+                format!("{crate_name}-callgraph.txt"),
+                call_graph.format_call_graph(tcx, options),
+            ),
+            OutputFormat::Json if options.numeric_ids => (
+                "callgraph.json".to_string(),
+                call_graph.format_call_graph_as_numeric_json(tcx, options),
+            ),
+            OutputFormat::Json => (
+                "callgraph.json".to_string(),
+                call_graph.format_call_graph_as_json(tcx, options),
+            ),
+            OutputFormat::Dot => (
+                format!("{crate_name}-callgraph.dot"),
+                call_graph.format_call_graph_as_dot(tcx, options),
+            ),
+            OutputFormat::Csv => (
+                format!("{crate_name}-callgraph.csv"),
+                call_graph.format_call_graph_as_csv(tcx, options),
+            ),
+            OutputFormat::Mermaid => (
+                format!("{crate_name}-callgraph.mmd"),
+                call_graph.format_call_graph_as_mermaid(tcx, options),
+            ),
+            OutputFormat::Cytoscape => (
+                format!("{crate_name}-callgraph.cytoscape.json"),
+                call_graph.format_call_graph_as_cytoscape(tcx, options),
+            ),
+        };
 
-        if let Err(e) = std::fs::write(&output_path, json_output) {
-            tracing::error!("Failed to write JSON call graph to file: {:?}", e);
+        let sink = if options.stdout {
+            OutputSink::Stdout
         } else {
-            tracing::info!("JSON call graph written to: {:?}", output_path);
+            OutputSink::File(output_dir.join(options.output_file_name(&file_name)))
+        };
+        match sink.write(options.compress, |out| write!(out, "{output}")) {
+            Ok(OutputSink::File(path)) => tracing::info!("Call graph ({:?}) written to {}", format, path.display()),
+            Ok(OutputSink::Stdout) => {}
+            Err(e) => tracing::error!("Failed to write {:?} call graph: {}", format, e),
         }
-    } else {
-        let formatted_callgraph = call_graph.format_call_graph(tcx);
 
-        match write_to_file(&output_path, |file| write!(file, "{formatted_callgraph}")) {
-            Ok(_) => tracing::info!("Call graph written to {}", output_path.display()),
-            Err(e) => tracing::error!("Failed to write call graph: {}", e),
+        // JSON output comes with analysis statistics
+        if format == OutputFormat::Json && !options.stdout {
+            let stats_path = output_dir.join(options.output_file_name(&format!("{crate_name}-callgraph-stats.json")));
+            let stats_output = call_graph.format_statistics_as_json(tcx, options);
+            match write_output(&stats_path, options.compress, |file| write!(file, "{stats_output}")) {
+                Ok(path) => tracing::info!("Call graph statistics written to {}", path.display()),
+                Err(e) => tracing::error!("Failed to write call graph statistics: {}", e),
+            }
+        }
+    }
+
+    // If cross-crate calls are requested, also list them grouped by callee crate
+    if options.cross_crate_only {
+        let cross_crate_path =
+            output_dir.join(options.output_file_name(&format!("{crate_name}-cross-crate-calls.txt")));
+        let cross_crate_output = call_graph.format_cross_crate_calls(tcx, options);
+        match write_output(&cross_crate_path, options.compress, |file| {
+            write!(file, "{cross_crate_output}")
+        }) {
+            Ok(path) => tracing::info!("Cross-crate calls written to {}", path.display()),
+            Err(e) => tracing::error!("Failed to write cross-crate calls: {}", e),
+        }
+    }
+
+    // If binary output is requested, write the owned graph model for fast reloading
+    if options.binary_output {
+        let binary_path = output_dir.join(options.output_file_name(&format!("{crate_name}-callgraph.bin")));
+        match call_graph.to_model(tcx, options).to_binary() {
+            Ok(bytes) => match write_output(&binary_path, options.compress, |file| file.write_all(&bytes)) {
+                Ok(path) => tracing::info!("Binary call graph written to {}", path.display()),
+                Err(e) => tracing::error!("Failed to write binary call graph: {}", e),
+            },
+            Err(e) => tracing::error!("Failed to serialize binary call graph: {}", e),
+        }
+    }
+
+    // If a monomorphization report is requested, count instantiations per generic function
+    if options.mono_report {
+        let mono_report_path = output_dir.join(options.output_file_name(&format!("{crate_name}-mono-report.txt")));
+        let mono_report = call_graph.format_mono_report(tcx);
+        match write_output(&mono_report_path, options.compress, |file| {
+            write!(file, "{mono_report}")
+        }) {
+            Ok(path) => tracing::info!("Monomorphization report written to {}", path.display()),
+            Err(e) => tracing::error!("Failed to write monomorphization report: {}", e),
+        }
+    }
+
+    // If the most instantiated generic functions are requested, list the top N
+    if let Some(top_n) = options.top_instantiations {
+        let top_path = output_dir.join(options.output_file_name(&format!("{crate_name}-top-instantiations.txt")));
+        let mut top_output = format!("Top {top_n} Instantiated Generic Functions:\n");
+        top_output.push_str("==========================================\n\n");
+        for (path, count) in call_graph.sorted_instantiation_counts(tcx).into_iter().take(top_n) {
+            top_output.push_str(&format!("{path} -> {count}\n"));
+        }
+        match write_output(&top_path, options.compress, |file| write!(file, "{top_output}")) {
+            Ok(path) => tracing::info!("Top instantiations written to {}", path.display()),
+            Err(e) => tracing::error!("Failed to write top instantiations: {}", e),
+        }
+    }
+
+    // In a feature-combination run, also write a per-combination JSON file
+    if let Some(features) = &call_graph.feature_combo {
+        let combo_path = output_dir
+            .join(options.output_file_name(&format!("{crate_name}-features-{}.json", feature_combo_hash(features))));
+        let combo_output = call_graph.format_feature_combo_as_json(tcx, features, options);
+        match write_output(&combo_path, options.compress, |file| write!(file, "{combo_output}")) {
+            Ok(path) => tracing::info!(
+                "Call graph for features [{}] written to {}",
+                features.join(","),
+                path.display()
+            ),
+            Err(e) => tracing::error!("Failed to write feature combination call graph: {}", e),
+        }
+    }
+
+    // If per-crate output is requested, write one JSON file per callee crate
+    if options.per_crate_output {
+        for (dep_crate_name, json_output) in call_graph.format_per_crate_call_graphs_as_json(tcx, options) {
+            let per_crate_path =
+                output_dir.join(options.output_file_name(&format!("{crate_name}-{dep_crate_name}-callgraph.json")));
+            match write_output(&per_crate_path, options.compress, |file| write!(file, "{json_output}")) {
+                Ok(path) => tracing::info!("Per-crate call graph written to {}", path.display()),
+                Err(e) => tracing::error!("Failed to write per-crate call graph: {}", e),
+            }
         }
     }
 
     if options.cg_debug {
-        let debug_path = output_dir.join(format!("{crate_name}-callgraph-debug.txt"));
-        let _ = write_to_file(&debug_path, |file| {
+        let debug_path = output_dir.join(options.output_file_name(&format!("{crate_name}-callgraph-debug.txt")));
+        let _ = write_output(&debug_path, options.compress, |file| {
             writeln!(file, "call_graph: {:#?}", call_graph.call_sites)
         });
     }
 }
 
+/// Write `crate-graph.{txt,json,dot}` for the requested output formats
+fn output_crate_graph<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'tcx>, options: &CGArgs, output_dir: &Path) {
+    let edges: BTreeMap<(String, String), usize> = crate::timer::measure("build_crate_graph", || {
+        build_crate_graph(call_graph, tcx, options).into_iter().collect()
+    });
+    let versions: BTreeMap<String, String> = std::iter::once(rustc_hir::def_id::LOCAL_CRATE)
+        .chain(tcx.crates(()).iter().copied())
+        .map(|krate| (tcx.crate_name(krate).to_string(), krate))
+        .filter(|(name, _)| edges.keys().any(|(caller, callee)| caller == name || callee == name))
+        .map(|(name, krate)| (name, get_crate_version(tcx, krate.as_def_id())))
+        .collect();
+    let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+
+    for format in options.effective_output_formats() {
+        let (extension, output) = match format {
+            OutputFormat::Text => ("txt", format_crate_graph(&crate_name, &edges)),
+            OutputFormat::Json => ("json", format_crate_graph_as_json(&crate_name, &edges, &versions)),
+            OutputFormat::Dot => ("dot", format_crate_graph_as_dot(&edges, &versions)),
+            _ => {
+                tracing::warn!("The crate graph does not support {:?} output", format);
+                continue;
+            }
+        };
+        let output_path = output_dir.join(options.output_file_name(&format!("crate-graph.{extension}")));
+        match write_output(&output_path, options.compress, |file| write!(file, "{output}")) {
+            Ok(path) => tracing::info!("Crate graph ({:?}) written to {}", format, path.display()),
+            Err(e) => tracing::error!("Failed to write {:?} crate graph: {}", format, e),
+        }
+    }
+}
+
+fn format_crate_graph(crate_name: &str, edges: &BTreeMap<(String, String), usize>) -> String {
+    let mut result = String::new();
+    result.push_str(&format!("Crate Graph for {crate_name}:\n"));
+    result.push_str("==========================================\n\n");
+    for ((caller_crate, callee_crate), calls) in edges {
+        result.push_str(&format!("{caller_crate} -> {callee_crate} [calls: {calls}]\n"));
+    }
+    result
+}
+
+fn format_crate_graph_as_json(
+    crate_name: &str,
+    edges: &BTreeMap<(String, String), usize>,
+    versions: &BTreeMap<String, String>,
+) -> String {
+    let nodes: Vec<_> = versions
+        .iter()
+        .map(|(name, version)| json!({ "name": name, "version": version }))
+        .collect();
+    let edges: Vec<_> = edges
+        .iter()
+        .map(|((caller_crate, callee_crate), calls)| {
+            json!({ "caller_crate": caller_crate, "callee_crate": callee_crate, "calls": calls })
+        })
+        .collect();
+    let result = json!({
+        "crate_name": crate_name,
+        "nodes": nodes,
+        "edges": edges,
+    });
+    serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn format_crate_graph_as_dot(edges: &BTreeMap<(String, String), usize>, versions: &BTreeMap<String, String>) -> String {
+    let mut result = String::new();
+    result.push_str("digraph crate_graph {\n");
+    result.push_str("    node [shape=box];\n");
+    for (name, version) in versions {
+        result.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n{}\"];\n",
+            escape_dot(name),
+            escape_dot(name),
+            escape_dot(version)
+        ));
+    }
+    for ((caller_crate, callee_crate), calls) in edges {
+        result.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+            escape_dot(caller_crate),
+            escape_dot(callee_crate),
+            calls
+        ));
+    }
+    result.push_str("}\n");
+    result
+}
+
+/// Write `<crate>-FAILED.txt` to mark a crate whose analysis panicked
+pub(crate) fn output_failure_marker(crate_name: &str, message: &str, options: &CGArgs) {
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let marker_path =
+        output_dir.join(options.output_file_name(&format!("{}-FAILED.txt", sanitize_path_for_filename(crate_name))));
+    if let Err(e) = write_to_file(&marker_path, |file| {
+        writeln!(file, "Analysis of crate {crate_name} failed:\n{message}")
+    }) {
+        tracing::error!("Failed to write failure marker: {}", e);
+    }
+}
+
+/// Name of the index in the output directory listing every analyzed crate
+const CRATE_INDEX_FILE: &str = "cg4rs-index.csv";
+
+/// Remove the [`CRATE_INDEX_FILE`] of an earlier run, before the crates of this run are analyzed
+pub(crate) fn reset_crate_index(options: &CGArgs) {
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let index_path = output_dir.join(options.output_file_name(CRATE_INDEX_FILE));
+    match std::fs::remove_file(&index_path) {
+        Ok(()) => tracing::debug!("Removed the crate index of an earlier run: {:?}", index_path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => tracing::error!("Failed to remove crate index {:?}: {}", index_path, e),
+    }
+}
+
+/// Append the graph size and analysis time of this crate to [`CRATE_INDEX_FILE`]
+///
+/// The crates of a workspace are analyzed by concurrent rustc processes, so the file is
+/// locked while the row (and, for a new file, the header) is appended. The launcher
+/// removes the file once per run with [`reset_crate_index`].
+pub(crate) fn append_crate_index<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    options: &CGArgs,
+    duration: std::time::Duration,
+) {
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let index_path = output_dir.join(options.output_file_name(CRATE_INDEX_FILE));
+    let row = format!(
+        "{},{},{},{}\n",
+        tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE),
+        call_graph.total_functions,
+        call_graph.call_sites.len(),
+        duration.as_millis()
+    );
+
+    let append = || -> io::Result<()> {
+        std::fs::create_dir_all(&output_dir)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&index_path)?;
+        // Released when the file is closed
+        file.lock()?;
+        let mut content = String::new();
+        if file.metadata()?.len() == 0 {
+            content.push_str("crate_name,node_count,edge_count,duration_ms\n");
+        }
+        content.push_str(&row);
+        file.write_all(content.as_bytes())
+    };
+    match append() {
+        Ok(()) => tracing::info!("Crate index updated: {:?}", index_path),
+        Err(e) => tracing::error!("Failed to append to crate index {:?}: {}", index_path, e),
+    }
+}
+
+/// JSON Lines file that `--stream-callers` fills with direct callers of the `--find-callers`
+/// targets while the analysis is still running
+///
+/// Each line is written and flushed as soon as a call to a matching function is recorded,
+/// so long runs can be watched (or interrupted) before the final callers reports exist.
+pub(crate) struct CallerStream<'tcx> {
+    file: JsonLinesFile,
+    targets: Vec<String>,
+    without_args: bool,
+    no_generic_stripping: bool,
+    /// Indices into `targets` matched by each callee seen so far
+    matches: HashMap<FunctionInstance<'tcx>, Vec<usize>>,
+    /// (target index, caller) pairs already written
+    written: HashSet<(usize, FunctionInstance<'tcx>)>,
+    /// Display paths of the callers written so far
+    caller_paths: HashMap<FunctionInstance<'tcx>, Rc<str>>,
+}
+
+impl<'tcx> CallerStream<'tcx> {
+    /// Open `callers-stream-<crate>.jsonl` in the output directory, `None` without
+    /// `--stream-callers` and `--find-callers` or if the file cannot be created
+    pub(crate) fn open(tcx: TyCtxt<'tcx>, options: &CGArgs) -> Option<Self> {
+        if !options.stream_callers || options.find_callers.is_empty() {
+            return None;
+        }
+        let output_dir = options
+            .output_dir
+            .clone()
+            .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+        let path = output_dir.join(options.output_file_name(&format!(
+            "callers-stream-{}.jsonl",
+            tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE)
+        )));
+        let file = std::fs::create_dir_all(&output_dir).and_then(|()| JsonLinesFile::create(path.clone()));
+        match file {
+            Ok(file) => {
+                tracing::info!("Streaming callers to: {:?}", path);
+                Some(Self {
+                    file,
+                    targets: options.find_callers.clone(),
+                    without_args: options.without_args,
+                    no_generic_stripping: options.no_generic_stripping,
+                    matches: HashMap::new(),
+                    written: HashSet::new(),
+                    caller_paths: HashMap::new(),
+                })
+            }
+            Err(e) => {
+                tracing::error!("Failed to create callers stream {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Write a line for each target `call_site` calls that its caller was not yet reported for
+    ///
+    /// `step` is the number of functions expanded so far by the analysis.
+    pub(crate) fn record(&mut self, tcx: TyCtxt<'tcx>, call_site: &CallSite<'tcx>, step: usize) {
+        let callee = call_site.callee();
+        let (targets, without_args, no_generic_stripping) =
+            (&self.targets, self.without_args, self.no_generic_stripping);
+        let matched = self.matches.entry(callee).or_insert_with(|| {
+            targets
+                .iter()
+                .enumerate()
+                .filter(|(_, target)| matches_function_path(tcx, callee, target, without_args, no_generic_stripping))
+                .map(|(index, _)| index)
+                .collect()
+        });
+        if matched.is_empty() {
+            return;
+        }
+
+        let caller = call_site.caller();
+        let mut lines = String::new();
+        for &index in matched.iter() {
+            if self.written.insert((index, caller)) {
+                let caller_path = self
+                    .caller_paths
+                    .entry(caller)
+                    .or_insert_with(|| caller.full_path(tcx, self.without_args).into());
+                let line = json!({
+                    "discovered_at_step": step,
+                    "target": self.targets[index],
+                    "caller": &**caller_path,
+                });
+                lines.push_str(&format!("{line}\n"));
+            }
+        }
+        if !lines.is_empty() {
+            self.file.append(&lines);
+        }
+    }
+}
+
+/// A JSON lines file that is flushed after every append, so that readers only see whole lines
+struct JsonLinesFile {
+    path: PathBuf,
+    writer: io::BufWriter<std::fs::File>,
+}
+
+impl JsonLinesFile {
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let file = std::fs::File::create(&path)?;
+        Ok(Self {
+            path,
+            writer: io::BufWriter::new(file),
+        })
+    }
+
+    /// Append `lines`, which end with a newline, and flush them to the file
+    fn append(&mut self, lines: &str) {
+        let result = self
+            .writer
+            .write_all(lines.as_bytes())
+            .and_then(|()| self.writer.flush());
+        if let Err(e) = result {
+            tracing::error!("Failed to write to {:?}: {}", self.path, e);
+        }
+    }
+}
+
 // Helper function to output callers result (reduces code duplication)
 pub(crate) fn output_callers_result<'tcx>(
     call_graph: &CallGraph<'tcx>,
     tcx: TyCtxt<'tcx>,
     target: &str,
     callers: Vec<PathInfo<'tcx>>,
-    options: &crate::args::CGArgs,
+    options: &CGArgs,
     file_prefix: &str,
 ) {
     // Get output directory
@@ -394,31 +1604,225 @@ pub(crate) fn output_callers_result<'tcx>(
         .output_dir
         .clone()
         .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    // Target paths contain `::` and generics, which are not portable in file names
+    let file_prefix = sanitize_path_for_filename(file_prefix);
 
     // Determine output format (text or JSON)
-    if options.json_output {
+    if options.effective_output_formats().contains(&OutputFormat::Json) {
         // Generate JSON output for callers
-        let callers_json = call_graph.format_callers_as_json(tcx, target, callers);
+        let callers_json = call_graph.format_callers_as_json(tcx, target, callers, options);
 
         // Output to JSON file
-        let json_output_path = output_dir.join(format!("{file_prefix}.json"));
+        let json_output_path = output_dir.join(options.output_file_name(&format!("{file_prefix}.json")));
 
-        if let Err(e) = std::fs::write(&json_output_path, callers_json) {
-            tracing::error!("Failed to write callers to JSON file: {:?}", e);
-        } else {
-            tracing::info!("Callers JSON output written to: {:?}", json_output_path);
+        match write_output(&json_output_path, options.compress, |file| {
+            write!(file, "{callers_json}")
+        }) {
+            Ok(path) => tracing::info!("Callers JSON output written to: {:?}", path),
+            Err(e) => tracing::error!("Failed to write callers to JSON file: {:?}", e),
         }
     } else {
         // Generate text output for callers
-        let callers_output = call_graph.format_callers(tcx, target, callers);
+        let callers_output = if options.compact_text {
+            call_graph.format_callers_compact(tcx, callers, options)
+        } else {
+            call_graph.format_callers(tcx, target, callers, options)
+        };
 
         // Output to text file
-        let output_path = output_dir.join(format!("{file_prefix}.txt"));
+        let output_path = output_dir.join(options.output_file_name(&format!("{file_prefix}.txt")));
 
-        if let Err(e) = std::fs::write(&output_path, callers_output) {
-            tracing::error!("Failed to write callers to file: {:?}", e);
-        } else {
-            tracing::info!("Callers output written to: {:?}", output_path);
+        match write_output(&output_path, options.compress, |file| write!(file, "{callers_output}")) {
+            Ok(path) => tracing::info!("Callers output written to: {:?}", path),
+            Err(e) => tracing::error!("Failed to write callers to file: {:?}", e),
+        }
+    }
+}
+
+/// Write the simple call paths from the entry points to `target` for `--all-paths-to`
+pub(crate) fn output_all_paths<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'tcx>, target: &str, options: &CGArgs) {
+    let (paths, truncated) = crate::timer::measure("find_all_paths", || {
+        call_graph.all_paths_to(
+            tcx,
+            target,
+            options.max_paths,
+            options.max_path_len,
+            options.no_generic_stripping,
+        )
+    });
+
+    let mut result = String::new();
+    result.push_str(&format!(
+        "Call paths from the entry points to functions matching '{target}':\n"
+    ));
+    result.push_str("==================================\n\n");
+    for path in &paths {
+        let path = path
+            .iter()
+            .map(|func| call_graph.function_path(tcx, *func))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        result.push_str(&format!("{path}\n"));
+    }
+    result.push_str(&format!(
+        "\nTotal: {} paths of at most {} calls\n",
+        paths.len(),
+        options.max_path_len
+    ));
+    if truncated {
+        tracing::warn!("--all-paths-to {} stopped after {} paths", target, paths.len());
+        result.push_str("Search stopped early (--max-paths or search budget reached), more paths may exist\n");
+    }
+
+    let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE);
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let file_name = sanitize_path_for_filename(&format!("{crate_name}-all-paths-{target}"));
+    match write_output(
+        &output_dir.join(options.output_file_name(&format!("{file_name}.txt"))),
+        options.compress,
+        |file| write!(file, "{result}"),
+    ) {
+        Ok(path) => tracing::info!("Call paths written to {}", path.display()),
+        Err(e) => tracing::error!("Failed to write call paths: {}", e),
+    }
+}
+
+/// Write the call paths from the roots as folded stacks, `<crate>-callgraph.folded`
+///
+/// Each path becomes one `root;callee;...;leaf 1` line, the input format of `inferno` and
+/// `flamegraph.pl`. The flamegraph is static: widths count possible call chains, not samples.
+pub(crate) fn output_folded_stacks<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'tcx>, options: &CGArgs) {
+    let roots = match &options.entry_point {
+        Some(entry_point) => {
+            call_graph.functions_matching_paths(tcx, std::slice::from_ref(entry_point), options.no_generic_stripping)
+        }
+        None => entry_points(tcx),
+    };
+    let (paths, truncated) = crate::timer::measure("find_folded_stacks", || {
+        call_graph.all_paths_from(&roots, options.max_paths, options.max_path_len)
+    });
+    if truncated {
+        tracing::warn!("--folded-output stopped after {} paths", paths.len());
+    }
+
+    // `;` separates frames, so it cannot appear in a frame, e.g. in `[u8; 4]`
+    let mut lines: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            let frames = path
+                .iter()
+                .map(|func| call_graph.function_path(tcx, *func).replace(';', ","))
+                .collect::<Vec<_>>();
+            format!("{} 1\n", frames.join(";"))
+        })
+        .collect();
+    lines.sort();
+
+    let crate_name = sanitize_path_for_filename(tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).as_str());
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    match write_output(
+        &output_dir.join(options.output_file_name(&format!("{crate_name}-callgraph.folded"))),
+        options.compress,
+        |file| write!(file, "{}", lines.concat()),
+    ) {
+        Ok(path) => tracing::info!("Folded stacks written to {}", path.display()),
+        Err(e) => tracing::error!("Failed to write folded stacks: {}", e),
+    }
+}
+
+/// Pastel fill color for a crate cluster, derived from a stable hash of the crate name
+fn cluster_color(crate_name: &str) -> String {
+    let hash = stable_hash(crate_name);
+    let channel = |shift: u32| 0xa0 | ((hash >> shift) & 0x5f) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(8), channel(16))
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Quote a CSV field if it contains a separator, quote or newline
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Escape characters Mermaid would interpret in a quoted label
+fn escape_mermaid(s: &str) -> String {
+    s.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
+
+/// Write an output file, gzip-compressed when `compress` is set
+///
+/// Compressed outputs get a `.gz` suffix appended to their extension (e.g. `.json.gz`,
+/// `.txt.gz`), and content is streamed through a `GzEncoder` into the file.
+///
+/// # Returns
+/// * `io::Result<PathBuf>` - The path that was actually written
+pub(super) fn write_output<F>(path: &Path, compress: bool, write_fn: F) -> io::Result<PathBuf>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    if !compress {
+        write_to_file(path, |file| write_fn(file))?;
+        return Ok(path.to_path_buf());
+    }
+
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    let gz_path = PathBuf::from(gz_path);
+    write_to_file(&gz_path, |file| {
+        let mut encoder = GzEncoder::new(io::BufWriter::new(file), Compression::default());
+        write_fn(&mut encoder)?;
+        encoder.finish()?.flush()
+    })?;
+    Ok(gz_path)
+}
+
+/// Read an output file, decompressing it first if it was written with `--compress`
+///
+/// Files are recognized as compressed by their `.gz` suffix, like `write_output` names them.
+pub(super) fn read_output(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = std::fs::read(path)?;
+    if path.extension().is_none_or(|ext| ext != "gz") {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Destination of the call graph output
+pub(super) enum OutputSink {
+    /// A file, gzip-compressed with `--compress`
+    File(PathBuf),
+    /// Stdout with `--stdout`, never compressed so it can be piped into other tools
+    Stdout,
+}
+
+impl OutputSink {
+    /// Write to the sink, returning where the output actually went
+    pub(super) fn write<F>(self, compress: bool, write_fn: F) -> io::Result<OutputSink>
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()>,
+    {
+        match self {
+            OutputSink::File(path) => write_output(&path, compress, write_fn).map(OutputSink::File),
+            OutputSink::Stdout => {
+                let mut stdout = io::stdout().lock();
+                write_fn(&mut stdout)?;
+                stdout.flush()?;
+                Ok(OutputSink::Stdout)
+            }
         }
     }
 }
@@ -449,3 +1853,28 @@ where
     tracing::info!("Successfully wrote to file: {}", path.as_ref().display());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_lines_file_is_readable_after_each_append() {
+        let dir = std::env::temp_dir().join(format!("cg4rs-json-lines-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stream.jsonl");
+        let mut file = JsonLinesFile::create(path.clone()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        file.append("{\"caller\":\"a\"}\n");
+        let first = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(first, "{\"caller\":\"a\"}\n");
+
+        file.append("{\"caller\":\"b\"}\n");
+        let second = std::fs::read_to_string(&path).unwrap();
+        assert!(second.len() > first.len());
+        assert_eq!(second.lines().count(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
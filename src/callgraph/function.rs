@@ -1,9 +1,30 @@
-use rustc_hir::def_id::DefId;
+use super::utils::optimized_mir_if_available;
+use rustc_hir::{def::DefKind, def_id::DefId};
 use rustc_middle::{
     middle::exported_symbols::ExportedSymbol,
+    mir::{Operand, TerminatorKind},
     ty::{self, TyCtxt, TypingEnv},
 };
 
+/// Def path fragments of helper functions generated by rustc during lowering,
+/// hidden from outputs with `--hide-generated`
+///
+/// Matched against `def_path_str`, which keeps the `{closure#N}` form. Coroutines
+/// (formerly generators) are included under their current name as well.
+pub const GENERATED_FN_PATTERNS: &[&str] = &[
+    "{closure#",
+    "{generator#",
+    "{coroutine#",
+    "{async_fn_env#",
+    "{constant#",
+];
+
+/// A function in the call graph
+///
+/// `Hash`/`Eq` are derived from the underlying `ty::Instance`, which compares its generic
+/// args structurally and distinguishes shims from the function they wrap. Instances must
+/// therefore be canonicalized with [`Self::canonicalize`] before they are used as keys,
+/// otherwise instances that only differ in lifetimes or shim kind are treated as distinct.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FunctionInstance<'tcx> {
     Instance(ty::Instance<'tcx>),
@@ -19,6 +40,26 @@ impl<'tcx> FunctionInstance<'tcx> {
         Self::NonInstance(def_id)
     }
 
+    /// Canonical form of the instance, so logically identical functions collapse to one node
+    ///
+    /// Erases all regions, e.g. the early-bound lifetimes in the identity args produced by
+    /// `type_of`, and replaces reify and vtable shims by the function they wrap. These
+    /// shims have the same `(def_id, args)` as the function and only forward to it.
+    pub(crate) fn canonicalize(self, tcx: TyCtxt<'tcx>) -> Self {
+        match self {
+            Self::Instance(instance) => {
+                let def = match instance.def {
+                    ty::InstanceKind::ReifyShim(def_id, _) | ty::InstanceKind::VTableShim(def_id) => {
+                        ty::InstanceKind::Item(def_id)
+                    }
+                    def => def,
+                };
+                Self::Instance(tcx.erase_regions(ty::Instance { def, ..instance }))
+            }
+            Self::NonInstance(_) => self,
+        }
+    }
+
     pub(crate) fn instance(&self) -> Option<ty::Instance<'tcx>> {
         match self {
             Self::Instance(instance) => Some(*instance),
@@ -43,8 +84,173 @@ impl<'tcx> FunctionInstance<'tcx> {
         !self.is_instance()
     }
 
+    /// Hex-encoded `DefPathHash` of the function, stable across crates and compilation sessions
+    ///
+    /// The hash identifies the definition only, so a resolved instance and an unresolved
+    /// reference to the same function share it. Use [`Self::is_instance`] to tell them apart.
+    pub(crate) fn path_hash(&self, tcx: TyCtxt<'tcx>) -> String {
+        let (hi, lo) = tcx.def_path_hash(self.def_id()).0.as_value();
+        format!("{hi:016x}{lo:016x}")
+    }
+
+    /// Number of parameters in the declared signature of the function
+    ///
+    /// `None` for closures, shims without a function item and C-variadic functions,
+    /// whose calls may legitimately pass a different number of arguments.
+    pub(crate) fn declared_arg_count(&self, tcx: TyCtxt<'tcx>) -> Option<usize> {
+        let def_id = self.def_id();
+        if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+            return None;
+        }
+        let sig = tcx.fn_sig(def_id).skip_binder().skip_binder();
+        (!sig.c_variadic).then(|| sig.inputs().len())
+    }
+
+    /// Whether this is a compiler-generated function rather than a user-written one,
+    /// i.e. a closure, coroutine, constructor or shim
+    pub(crate) fn is_synthetic(&self, tcx: TyCtxt<'tcx>) -> bool {
+        if let Self::Instance(instance) = self
+            && !matches!(instance.def, ty::InstanceKind::Item(_))
+        {
+            return true;
+        }
+
+        let def_id = self.def_id();
+        if matches!(
+            tcx.def_kind(def_id),
+            DefKind::Closure | DefKind::SyntheticCoroutineBody | DefKind::Ctor(..)
+        ) {
+            return true;
+        }
+
+        let path = tcx.def_path_str(def_id);
+        path.contains("{closure") || path.contains("{constructor")
+    }
+
+    /// Whether this is a rustc-generated helper function, see [`GENERATED_FN_PATTERNS`]
+    pub(crate) fn is_generated(&self, tcx: TyCtxt<'tcx>) -> bool {
+        let path = tcx.def_path_str(self.def_id());
+        GENERATED_FN_PATTERNS.iter().any(|pattern| path.contains(pattern))
+    }
+
+    /// Whether the body of this function can panic directly
+    ///
+    /// True if its MIR contains an `Assert` terminator (e.g. overflow or bounds checks)
+    /// or a call to one of the panic lang items (`panic`, `panic_fmt`, `begin_panic`).
+    /// Panics in transitive callees are not considered.
+    pub(crate) fn may_panic(&self, tcx: TyCtxt<'tcx>) -> bool {
+        if self.is_non_instance() {
+            return false;
+        }
+        let Some(body) = optimized_mir_if_available(tcx, self.def_id()) else {
+            return false;
+        };
+
+        let li = tcx.lang_items();
+        let panic_fns = [li.panic_fn(), li.panic_fmt(), li.begin_panic_fn()];
+        body.basic_blocks
+            .iter()
+            .filter_map(|data| data.terminator.as_ref())
+            .any(|terminator| match &terminator.kind {
+                TerminatorKind::Assert { .. } => true,
+                TerminatorKind::Call {
+                    func: Operand::Constant(constant),
+                    ..
+                }
+                | TerminatorKind::TailCall {
+                    func: Operand::Constant(constant),
+                    ..
+                } => match constant.const_.ty().kind() {
+                    ty::TyKind::FnDef(callee_id, _) => panic_fns.contains(&Some(*callee_id)),
+                    _ => false,
+                },
+                _ => false,
+            })
+    }
+
+    /// Impl `Self` type and trait of an associated function instance
+    ///
+    /// Returns `None` for non-instances and functions that are not associated functions.
+    /// For trait methods that were not resolved to an impl (e.g. virtual calls),
+    /// the `Self` type is taken from the first generic arg.
+    pub(crate) fn assoc_info(&self, tcx: TyCtxt<'tcx>) -> Option<AssocInfo> {
+        let Self::Instance(instance) = self else {
+            return None;
+        };
+        let def_id = instance.def_id();
+        if tcx.def_kind(def_id) != DefKind::AssocFn {
+            return None;
+        }
+
+        let container = tcx.parent(def_id);
+        match tcx.def_kind(container) {
+            DefKind::Impl { .. } => Some(AssocInfo {
+                impl_self_ty: Some(tcx.type_of(container).instantiate(tcx, instance.args).to_string()),
+                trait_path: tcx
+                    .trait_id_of_impl(container)
+                    .map(|trait_id| tcx.def_path_str(trait_id)),
+            }),
+            DefKind::Trait => Some(AssocInfo {
+                impl_self_ty: instance.args.types().next().map(|self_ty| self_ty.to_string()),
+                trait_path: Some(tcx.def_path_str(container)),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether this function comes from generated source code, see `--exclude-generated`
+    ///
+    /// That is, its definition was expanded from a macro (including derives) or is in a file
+    /// under a build script's output directory, e.g. `include!`d `prost` or `bindgen` output.
+    pub(crate) fn is_generated_code(&self, tcx: TyCtxt<'tcx>) -> bool {
+        let span = tcx.def_span(self.def_id());
+        if span.from_expansion() {
+            return true;
+        }
+        let loc = tcx.sess.source_map().lookup_char_pos(span.lo());
+        is_in_build_output(std::path::Path::new(&loc.file.name.prefer_local().to_string()))
+    }
+
+    /// Whether the function is declared `#[inline(always)]`, see `--merge-inline`
+    pub(crate) fn is_inline_always(&self, tcx: TyCtxt<'tcx>) -> bool {
+        let def_id = self.def_id();
+        matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn | DefKind::Closure)
+            && tcx.codegen_fn_attrs(def_id).inline.always()
+    }
+
+    /// Whether this is a method of a local impl block that involves another crate,
+    /// i.e. an impl of a foreign trait or for a foreign type, see `--no-extern-impls`
+    pub(crate) fn is_in_extern_impl(&self, tcx: TyCtxt<'tcx>) -> bool {
+        let def_id = self.def_id();
+        if !def_id.is_local() || tcx.def_kind(def_id) != DefKind::AssocFn {
+            return false;
+        }
+        let container = tcx.parent(def_id);
+        if !matches!(tcx.def_kind(container), DefKind::Impl { .. }) {
+            return false;
+        }
+        let foreign_trait = tcx
+            .trait_id_of_impl(container)
+            .is_some_and(|trait_id| !trait_id.is_local());
+        let foreign_self_ty = tcx
+            .type_of(container)
+            .skip_binder()
+            .ty_adt_def()
+            .is_some_and(|adt| !adt.did().is_local());
+        foreign_trait || foreign_self_ty
+    }
+
     /// Convert function instance to readable string
     pub(crate) fn full_path(&self, tcx: TyCtxt<'tcx>, without_args: bool) -> String {
+        // Closures and coroutines are rendered with their enclosing function and source location
+        if tcx.is_closure_like(self.def_id()) {
+            let args = match self {
+                Self::Instance(inst) if !without_args => Some(inst.args),
+                _ => None,
+            };
+            return closure_like_path(tcx, self.def_id(), args);
+        }
+
         match self {
             Self::Instance(inst) => {
                 let def_id = inst.def_id();
@@ -66,6 +272,66 @@ impl<'tcx> FunctionInstance<'tcx> {
     }
 }
 
+/// Impl and trait an associated function belongs to, see [`FunctionInstance::assoc_info`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AssocInfo {
+    /// `Self` type of the impl, with the instance's generic args substituted
+    pub(crate) impl_self_ty: Option<String>,
+    /// Path of the implemented trait, `None` for inherent impls
+    pub(crate) trait_path: Option<String>,
+}
+
+/// Whether `path` is in the output directory of a build script
+///
+/// Checks `OUT_DIR` of the crate being compiled, and the `build/<package>-<hash>/out`
+/// layout of Cargo's target directory for generated files of other crates.
+fn is_in_build_output(path: &std::path::Path) -> bool {
+    if let Some(out_dir) = std::env::var_os("OUT_DIR")
+        && path.starts_with(out_dir)
+    {
+        return true;
+    }
+    let components: Vec<_> = path.components().map(|component| component.as_os_str()).collect();
+    components
+        .windows(3)
+        .any(|window| window[0] == "build" && window[2] == "out")
+}
+
+/// Render a closure or coroutine as `<enclosing path>::{closure@file.rs:line}`
+///
+/// `def_path_str` only yields `{closure#N}`, which does not tell apart closures
+/// defined in the same function. With `args`, the enclosing function is rendered with
+/// its part of them, e.g. `demo::map::<u8>::{closure@lib.rs:3}`.
+fn closure_like_path<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId, args: Option<ty::GenericArgsRef<'tcx>>) -> String {
+    let parent = tcx.parent(def_id);
+    let parent_args = args.and_then(|args| {
+        let generics = tcx.generics_of(parent);
+        (args.len() >= generics.count()).then(|| args.truncate_to(tcx, generics))
+    });
+    let parent_path = if tcx.is_closure_like(parent) {
+        closure_like_path(tcx, parent, parent_args)
+    } else {
+        match parent_args {
+            Some(parent_args) if !parent_args.is_empty() => tcx.def_path_str_with_args(parent, parent_args),
+            _ => tcx.def_path_str(parent),
+        }
+    };
+
+    let kind = if tcx.is_coroutine(def_id) {
+        "coroutine"
+    } else {
+        "closure"
+    };
+    let loc = tcx.sess.source_map().lookup_char_pos(tcx.def_span(def_id).lo());
+    let file_name = loc.file.name.prefer_local().to_string();
+    let file_name = std::path::Path::new(&file_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(file_name);
+
+    format!("{parent_path}::{{{kind}@{file_name}:{}}}", loc.line)
+}
+
 /// collect all function instances in local crate, including generic instances
 pub fn collect_local_instances(tcx: ty::TyCtxt<'_>) -> Vec<FunctionInstance<'_>> {
     let mut instances = Vec::new();
@@ -74,7 +340,7 @@ pub fn collect_local_instances(tcx: ty::TyCtxt<'_>) -> Vec<FunctionInstance<'_>>
         if let ty::TyKind::FnDef(def_id, args) = ty.kind() {
             let instance = ty::Instance::try_resolve(tcx, TypingEnv::post_analysis(tcx, *def_id), *def_id, args);
             if let Ok(Some(instance)) = instance {
-                instances.push(FunctionInstance::new_instance(instance));
+                instances.push(FunctionInstance::new_instance(instance).canonicalize(tcx));
             }
         }
     }
@@ -112,7 +378,6 @@ where
 
     // Traverse local crate: filter functions and associated functions via HIR body owners
     {
-        use rustc_hir::def::DefKind;
         for owner in tcx.hir_body_owners() {
             let def_id = owner.to_def_id();
             match tcx.def_kind(def_id) {
@@ -130,7 +395,6 @@ where
 
     // Traverse external crates: get exportable functions/associated functions via exported_symbols
     {
-        use rustc_hir::def::DefKind;
         for &crate_num in tcx.crates(()) {
             let mut exported_symbols = tcx.exported_generic_symbols(crate_num).to_vec();
             exported_symbols.extend_from_slice(tcx.exported_non_generic_symbols(crate_num));
@@ -153,3 +417,18 @@ where
 
     results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_is_in_build_output() {
+        assert!(is_in_build_output(Path::new(
+            "/work/target/debug/build/demo-1a2b3c/out/proto.rs"
+        )));
+        assert!(!is_in_build_output(Path::new("/work/src/build/out.rs")));
+        assert!(!is_in_build_output(Path::new("src/main.rs")));
+    }
+}
@@ -0,0 +1,279 @@
+use rustc_middle::ty::{self, TyCtxt, TypeVisitableExt};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+
+use super::fmt::write_output;
+use super::function::FunctionInstance;
+use super::types::CallGraph;
+
+/// Label LLVM gives the pseudo node for calls from and to unknown functions
+const LLVM_EXTERNAL_NODE: &str = "external node";
+
+/// Parse the Graphviz call graph written by `opt -passes=dot-callgraph`
+///
+/// Returns the `(caller_label, callee_label)` pairs, where labels are the (mangled) symbol
+/// names of the functions. Edges from or to LLVM's external pseudo node are dropped.
+pub(crate) fn import_llvm_callgraph(dot_content: &str) -> HashSet<(String, String)> {
+    let mut labels: HashMap<&str, String> = HashMap::new();
+    let mut edges: Vec<(&str, &str)> = Vec::new();
+
+    for line in dot_content.lines() {
+        let line = line.trim().trim_end_matches(';').trim();
+        if let Some((from, to)) = line.split_once("->") {
+            // `Node0x1:s0 -> Node0x2[color=...]`: drop record ports and edge attributes
+            let to = to.split('[').next().unwrap_or_default();
+            edges.push((node_id(from), node_id(to)));
+        } else if let Some((id, attrs)) = line.split_once('[')
+            && let Some(label) = parse_label(attrs)
+        {
+            labels.insert(node_id(id), label);
+        }
+    }
+
+    edges
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let caller = labels.get(from)?;
+            let callee = labels.get(to)?;
+            let is_function = |label: &str| !label.is_empty() && label != LLVM_EXTERNAL_NODE;
+            (is_function(caller) && is_function(callee)).then(|| (caller.clone(), callee.clone()))
+        })
+        .collect()
+}
+
+fn node_id(id: &str) -> &str {
+    let id = id.trim();
+    id.split_once(':').map_or(id, |(id, _port)| id).trim_matches('"')
+}
+
+/// First field of a record label, e.g. `main` for `label="{main|...}"`
+fn parse_label(attrs: &str) -> Option<String> {
+    let start = attrs.find("label=\"")? + "label=\"".len();
+    let mut label = String::new();
+    let mut chars = attrs[start..].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                // Line breaks end the first line of the label
+                'l' | 'n' | 'r' => break,
+                escaped => label.push(escaped),
+            },
+            '"' | '|' => break,
+            '{' | '}' => {}
+            c => label.push(c),
+        }
+    }
+    Some(label.trim().to_string())
+}
+
+/// Symbol name of a function as it appears in LLVM IR, if it is codegened
+fn symbol_name<'tcx>(tcx: TyCtxt<'tcx>, func: FunctionInstance<'tcx>) -> Option<String> {
+    let instance = func.instance()?;
+    if instance.args.has_param()
+        || matches!(
+            instance.def,
+            ty::InstanceKind::Virtual(..) | ty::InstanceKind::Intrinsic(_)
+        )
+    {
+        return None;
+    }
+    Some(tcx.symbol_name(instance).name.to_string())
+}
+
+/// Edge differences between our call graph and LLVM's, in symbol names
+#[derive(Debug, Default)]
+pub(crate) struct LlvmComparison {
+    pub(crate) our_edges: usize,
+    pub(crate) llvm_edges: usize,
+    pub(crate) common_edges: usize,
+    /// Our call sites without a symbol on either side, e.g. generic or virtual callees
+    pub(crate) unmapped_edges: usize,
+    /// LLVM edges whose caller is not a caller in our graph, e.g. other crates in the module
+    pub(crate) llvm_edges_outside_graph: usize,
+    /// Possibly over-approximated, or inlined away before LLVM saw them
+    pub(crate) only_in_ours: BTreeSet<(String, String)>,
+    /// Possibly missed calls
+    pub(crate) only_in_llvm: BTreeSet<(String, String)>,
+}
+
+impl LlvmComparison {
+    fn format(&self, crate_name: &str) -> String {
+        let mut result = String::new();
+        result.push_str(&format!("LLVM Call Graph Comparison for {crate_name}:\n"));
+        result.push_str("==========================================\n\n");
+        result.push_str(&format!("Our edges: {}\n", self.our_edges));
+        result.push_str(&format!("LLVM edges: {}\n", self.llvm_edges));
+        result.push_str(&format!("Common edges: {}\n", self.common_edges));
+        result.push_str(&format!("Unmapped edges: {}\n", self.unmapped_edges));
+        result.push_str(&format!(
+            "LLVM edges outside our graph: {}\n",
+            self.llvm_edges_outside_graph
+        ));
+        for (title, edges) in [
+            ("Only in ours (possibly over-approximate)", &self.only_in_ours),
+            ("Only in LLVM (possibly missed)", &self.only_in_llvm),
+        ] {
+            result.push_str(&format!("\n{title}: {}\n", edges.len()));
+            for (caller, callee) in edges {
+                result.push_str(&format!("  {caller} -> {callee}\n"));
+            }
+        }
+        result
+    }
+
+    fn format_as_json(&self, crate_name: &str) -> String {
+        let edges_json = |edges: &BTreeSet<(String, String)>| {
+            edges
+                .iter()
+                .map(|(caller, callee)| json!({ "caller": caller, "callee": callee }))
+                .collect::<Vec<_>>()
+        };
+        let result = json!({
+            "crate_name": crate_name,
+            "our_edges": self.our_edges,
+            "llvm_edges": self.llvm_edges,
+            "common_edges": self.common_edges,
+            "unmapped_edges": self.unmapped_edges,
+            "llvm_edges_outside_graph": self.llvm_edges_outside_graph,
+            "only_in_ours": edges_json(&self.only_in_ours),
+            "only_in_llvm": edges_json(&self.only_in_llvm),
+        });
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    /// Compare the output call sites with the edges of an imported LLVM call graph
+    pub(crate) fn compare_with_llvm(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        llvm_edges: &HashSet<(String, String)>,
+        options: &crate::args::CGArgs,
+    ) -> LlvmComparison {
+        let mut comparison = LlvmComparison {
+            llvm_edges: llvm_edges.len(),
+            ..Default::default()
+        };
+
+        let mut ours: HashSet<(String, String)> = HashSet::new();
+        for call_site in self.output_call_sites(tcx, options) {
+            match (
+                symbol_name(tcx, call_site.caller()),
+                symbol_name(tcx, call_site.callee()),
+            ) {
+                (Some(caller), Some(callee)) => {
+                    ours.insert((caller, callee));
+                }
+                _ => comparison.unmapped_edges += 1,
+            }
+        }
+        comparison.our_edges = ours.len();
+
+        let our_callers: HashSet<&str> = ours.iter().map(|(caller, _)| caller.as_str()).collect();
+        for edge in llvm_edges {
+            if ours.contains(edge) {
+                comparison.common_edges += 1;
+            } else if our_callers.contains(edge.0.as_str()) {
+                comparison.only_in_llvm.insert(edge.clone());
+            } else {
+                comparison.llvm_edges_outside_graph += 1;
+            }
+        }
+        comparison.only_in_ours = ours.into_iter().filter(|edge| !llvm_edges.contains(edge)).collect();
+        comparison
+    }
+}
+
+/// Import the LLVM call graph at `dot_path` and write the edge differences
+pub(crate) fn output_llvm_comparison<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    dot_path: &Path,
+    options: &crate::args::CGArgs,
+) {
+    let dot_content = match std::fs::read_to_string(dot_path) {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::error!("Failed to read LLVM call graph {}: {}", dot_path.display(), e);
+            return;
+        }
+    };
+    let llvm_edges = import_llvm_callgraph(&dot_content);
+    tracing::debug!("Imported {} edges from {}", llvm_edges.len(), dot_path.display());
+    let comparison = crate::timer::measure("compare_llvm", || {
+        call_graph.compare_with_llvm(tcx, &llvm_edges, options)
+    });
+
+    let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    for (extension, output) in [
+        ("txt", comparison.format(&crate_name)),
+        ("json", comparison.format_as_json(&crate_name)),
+    ] {
+        let output_path =
+            output_dir.join(options.output_file_name(&format!("{crate_name}-llvm-comparison.{extension}")));
+        match write_output(&output_path, options.compress, |file| write!(file, "{output}")) {
+            Ok(path) => tracing::info!("LLVM comparison written to {}", path.display()),
+            Err(e) => tracing::error!("Failed to write LLVM comparison: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LLVM_DOT: &str = r#"digraph "Call graph: test.ll" {
+	label="Call graph: test.ll";
+
+	Node0x1 [shape=record,label="{external node}"];
+	Node0x1 -> Node0x2;
+	Node0x1 -> Node0x3;
+	Node0x2 [shape=record,label="{_ZN4test4main17h0123456789abcdefE}"];
+	Node0x2 -> Node0x3;
+	Node0x2 -> Node0x4[color=blue];
+	Node0x3 [shape=record,label="{_ZN4test6helper17hfedcba9876543210E}"];
+	Node0x3 -> Node0x1;
+	Node0x4 [shape=record,label="{_ZN4core3fmt9Arguments6new_v117h00E|\{extra\}}"];
+}
+"#;
+
+    #[test]
+    fn test_import_llvm_callgraph() {
+        let edges = import_llvm_callgraph(LLVM_DOT);
+        let expected: HashSet<(String, String)> = [
+            (
+                "_ZN4test4main17h0123456789abcdefE",
+                "_ZN4test6helper17hfedcba9876543210E",
+            ),
+            (
+                "_ZN4test4main17h0123456789abcdefE",
+                "_ZN4core3fmt9Arguments6new_v117h00E",
+            ),
+        ]
+        .into_iter()
+        .map(|(caller, callee)| (caller.to_string(), callee.to_string()))
+        .collect();
+        assert_eq!(edges, expected);
+    }
+
+    #[test]
+    fn test_parse_label_escapes() {
+        assert_eq!(
+            parse_label(r#"shape=record,label="{a\<b\>}"];"#),
+            Some("a<b>".to_string())
+        );
+        assert_eq!(parse_label(r#"label="{name\l}""#), Some("name".to_string()));
+        assert_eq!(parse_label("shape=record"), None);
+    }
+
+    #[test]
+    fn test_import_empty_graph() {
+        assert!(import_llvm_callgraph("digraph \"Call graph\" {\n}\n").is_empty());
+    }
+}
@@ -1,34 +1,191 @@
 mod analysis;
+mod collector;
 mod controlflow;
+mod fail_on;
+mod feature_combo;
 mod fmt;
 mod function;
+mod import;
+mod model;
 mod origin;
+mod path_utils;
 mod public_exposure;
+mod query;
 mod resolution;
+mod since;
+mod stats;
 mod summary;
+mod trait_impls;
 mod types;
 mod utils;
+mod visibility;
 
 use analysis::perform_mono_analysis;
+pub(crate) use fail_on::FailCheck;
 use fmt::{output_call_graph_result, output_callers_result};
-use function::FunctionInstance;
+pub(crate) use fmt::{output_failure_marker, reset_crate_index};
+pub(crate) use since::{changed_files, diff_dir};
 use types::CallGraph;
 
+pub use collector::{CollectorFactory, MirEdgeCollector};
+pub use feature_combo::{FeatureComboDiff, compare_feature_outputs};
+pub use function::FunctionInstance;
+pub use function::GENERATED_FN_PATTERNS;
+pub use model::{CallSiteModel, GRAPH_MODEL_VERSION, GraphModel};
+pub use query::{GraphQuery, PathQuery, QueryReport, run_query};
+pub(crate) use trait_impls::output_trait_impls;
+pub use types::{CallSortOrder, CallerSortOrder, DeduplicationStrategy, MirLevel, OutputFormat};
+
+/// Result of analyzing a crate, turned into a process exit code by the `--exit-on-*` and `--fail-on` flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisOutcome {
+    /// No requested exit condition was met
+    Clean,
+    /// `--exit-on-callers`: `--find-callers` found callers
+    CallersFound,
+    /// `--exit-on-cycles`: functions of the crate call each other in a cycle
+    CyclesFound,
+    /// `--exit-on-unsafe-calls`: functions of the crate call an `unsafe fn`
+    UnsafeCallsFound,
+    /// `--fail-on`: one of the checks was triggered
+    ChecksFailed,
+    /// `--strict-errors`: the analysis recorded errors, see `analysis_errors` in the statistics
+    ErrorsFound,
+}
+
+impl AnalysisOutcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Clean => 0,
+            Self::CallersFound => 1,
+            Self::CyclesFound => 2,
+            Self::UnsafeCallsFound => 3,
+            Self::ChecksFailed => 4,
+            Self::ErrorsFound => 5,
+        }
+    }
+}
+
 // Main entry point for callgraph analysis
-pub fn analyze_crate<'tcx>(tcx: rustc_middle::ty::TyCtxt<'tcx>, args: &crate::args::CGArgs) -> CallGraph<'tcx> {
+pub fn analyze_crate<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    args: &crate::args::CGArgs,
+    is_build_script: bool,
+    compile_target: Option<String>,
+    feature_combo: Option<Vec<String>>,
+    collectors: &mut [Box<dyn MirEdgeCollector<'tcx> + 'tcx>],
+) -> AnalysisOutcome {
+    let started = std::time::Instant::now();
+    // With `--stream-callers`, direct callers of the `--find-callers` targets are written as they are found
+    let mut caller_stream = fmt::CallerStream::open(tcx, args);
+
     // Collect all generic instances in the crate
-    let instances: Vec<FunctionInstance<'tcx>> =
+    let mut instances: Vec<FunctionInstance<'tcx>> =
         crate::timer::measure("0collect_local_instances", || function::collect_local_instances(tcx));
 
+    // Drop compiler-generated seeds; they can still be reached as callees
+    if args.skip_synthetic {
+        let before = instances.len();
+        instances.retain(|instance| !instance.is_synthetic(tcx));
+        tracing::debug!("Skipped {} synthetic seed instances", before - instances.len());
+    }
+
+    // Only seed with functions under the requested path prefix
+    if let Some(prefix) = &args.only_prefix {
+        let before = instances.len();
+        instances.retain(|instance| utils::matches_path_prefix(tcx, *instance, prefix));
+        tracing::debug!(
+            "Kept {} of {} seed instances under prefix {}",
+            instances.len(),
+            before,
+            prefix
+        );
+    }
+
+    // Only seed with changed functions and their callers
+    if let Some(git_ref) = &args.since {
+        match since::changed_files(since::diff_dir(args), git_ref) {
+            Some(files) => {
+                instances = since::changed_seeds(tcx, instances, &files);
+                tracing::debug!("Seeding with {} instances changed since {}", instances.len(), git_ref);
+            }
+            None => tracing::warn!("Could not diff against {}, falling back to full analysis", git_ref),
+        }
+    }
+
     // Perform monomorphization analysis
-    let call_graph: CallGraph<'tcx> =
-        crate::timer::measure("1perform_mono_analysis", || perform_mono_analysis(tcx, instances, args));
+    let mut call_graph: CallGraph<'tcx> = crate::timer::measure("1perform_mono_analysis", || {
+        perform_mono_analysis(tcx, instances, args, collectors, caller_stream.as_mut())
+    });
+    call_graph.is_build_script = is_build_script;
+    call_graph.is_proc_macro = utils::is_proc_macro_crate(tcx);
+    call_graph.compile_target = compile_target;
+    call_graph.feature_combo = feature_combo;
+
+    // Only keep calls into the requested dependency versions
+    if !args.crate_version_filters.is_empty() {
+        call_graph.retain_crate_versions(tcx, &args.crate_version_filters);
+    }
+
+    // Only keep what the requested functions can reach
+    if !args.reachable_from.is_empty() {
+        crate::timer::measure("retain_reachable_from", || {
+            call_graph.retain_reachable_from(tcx, &args.reachable_from, args.no_generic_stripping)
+        });
+    }
+
+    if args.entry_distances {
+        let entry_points = stats::entry_points(tcx);
+        call_graph.entry_distances = Some(crate::timer::measure("compute_entry_distances", || {
+            call_graph.compute_entry_distances(tcx, &entry_points)
+        }));
+    }
+
+    // Layer the graph by call depth from the entry point
+    if let Some(entry_point) = &args.entry_point {
+        let entry_functions =
+            call_graph.functions_matching_paths(tcx, std::slice::from_ref(entry_point), args.no_generic_stripping);
+        call_graph.entry_depths = Some(crate::timer::measure("compute_entry_depths", || {
+            call_graph.compute_entry_distances(tcx, &entry_functions)
+        }));
+    }
+
+    // Deepest call chain from the entry points, for stack depth analysis
+    if args.max_depth_stat || args.stack_usage_limit.is_some() {
+        let entry_points = stats::entry_points(tcx);
+        let (max_depth, chain) = crate::timer::measure("compute_max_call_depth", || {
+            stats::compute_max_call_depth(&call_graph, &entry_points)
+        });
+        let chain_paths: Vec<_> = chain.iter().map(|func| call_graph.function_path(tcx, *func)).collect();
+        tracing::info!("Maximum call depth: {} ({})", max_depth, chain_paths.join(" -> "));
+        if let Some(limit) = args.stack_usage_limit
+            && max_depth > limit
+        {
+            tracing::warn!(
+                "Maximum call depth {} exceeds --stack-usage-limit {}: {}",
+                max_depth,
+                limit,
+                chain_paths.join(" -> ")
+            );
+        }
+        if args.max_depth_stat {
+            call_graph.deepest_call_chain = Some(chain);
+        }
+    }
 
     // Handle find_callers_of
+    let mut callers_found = false;
     crate::timer::measure("2output_find_callers_results", || {
         for target_path in &args.find_callers {
             tracing::debug!("Finding callers of function: {}", target_path);
-            let callers_with_constraints = call_graph.find_callers_by_path(tcx, target_path);
+            let mut callers_with_constraints =
+                call_graph.find_callers_by_path(tcx, target_path, args.find_callers_depth, args.no_generic_stripping);
+            // Package edges of the least constrained path, see `--min-pkg-edges`/`--max-pkg-edges`
+            callers_with_constraints.retain(|path_info| {
+                args.min_path_package_num.is_none_or(|min| path_info.package_num >= min)
+                    && args.max_path_package_num.is_none_or(|max| path_info.package_num <= max)
+            });
+            callers_found |= !callers_with_constraints.is_empty();
             crate::timer::measure("output_callers_result", || {
                 output_callers_result(
                     &call_graph,
@@ -42,14 +199,100 @@ pub fn analyze_crate<'tcx>(tcx: rustc_middle::ty::TyCtxt<'tcx>, args: &crate::ar
         }
     });
 
+    if args.find_panic_paths {
+        let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE);
+        let panic_callers = crate::timer::measure("find_panic_callers", || call_graph.find_panic_callers(tcx));
+        output_callers_result(
+            &call_graph,
+            tcx,
+            "core::panicking | std::panicking",
+            panic_callers,
+            args,
+            &format!("{crate_name}-panic-paths"),
+        );
+    }
+
+    if let Some(target) = &args.all_paths_to {
+        fmt::output_all_paths(&call_graph, tcx, target, args);
+    }
+
+    if args.folded_output {
+        fmt::output_folded_stacks(&call_graph, tcx, args);
+    }
+
     crate::timer::measure("output_call_graph_result", || {
         output_call_graph_result(&call_graph, tcx, args)
     });
 
+    if args.summarize_by_visibility {
+        visibility::output_visibility_report(&call_graph, tcx, args);
+    }
+
+    if let Some(dot_path) = &args.compare_llvm {
+        import::output_llvm_comparison(&call_graph, tcx, dot_path, args);
+    }
+
     // Perform public exposure analysis
     crate::timer::measure("public_exposure_analysis", || {
         public_exposure::analyze_public_exposure(&call_graph, tcx, args);
     });
 
-    call_graph
+    for collector in collectors.iter_mut() {
+        collector.finish(tcx);
+    }
+
+    let mut checks_failed = false;
+    if !args.fail_on.is_empty() {
+        let results = crate::timer::measure("evaluate_fail_checks", || {
+            fail_on::evaluate_fail_checks(&call_graph, tcx, &args.fail_on, args.no_generic_stripping)
+        });
+        checks_failed = results.iter().any(|result| result.triggered);
+        fail_on::output_fail_checks(&results, tcx, args);
+    }
+
+    fmt::append_crate_index(&call_graph, tcx, args, started.elapsed());
+
+    exit_outcome(&call_graph, tcx, args, callers_found, checks_failed)
+}
+
+/// The first `--exit-on-*` or `--fail-on` condition met by the analyzed crate
+fn exit_outcome<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    args: &crate::args::CGArgs,
+    callers_found: bool,
+    checks_failed: bool,
+) -> AnalysisOutcome {
+    if args.exit_on_callers && callers_found {
+        tracing::error!("Callers found for {}", args.find_callers.join(", "));
+        return AnalysisOutcome::CallersFound;
+    }
+    if args.exit_on_cycles
+        && let Some(cycle) = call_graph.find_local_cycle()
+    {
+        let cycle: Vec<_> = cycle.iter().map(|func| call_graph.function_path(tcx, *func)).collect();
+        tracing::error!("Call cycle found: {} -> {}", cycle.join(" -> "), cycle[0]);
+        return AnalysisOutcome::CyclesFound;
+    }
+    if args.exit_on_unsafe_calls {
+        let unsafe_calls = call_graph.local_unsafe_calls(tcx);
+        if !unsafe_calls.is_empty() {
+            for call_site in &unsafe_calls {
+                tracing::error!(
+                    "Unsafe call: {} -> {}",
+                    call_graph.function_path(tcx, call_site.caller()),
+                    call_graph.function_path(tcx, call_site.callee())
+                );
+            }
+            return AnalysisOutcome::UnsafeCallsFound;
+        }
+    }
+    if checks_failed {
+        return AnalysisOutcome::ChecksFailed;
+    }
+    if args.strict_errors && !call_graph.errors.is_empty() {
+        tracing::error!("{} analysis errors", call_graph.errors.len());
+        return AnalysisOutcome::ErrorsFound;
+    }
+    AnalysisOutcome::Clean
 }
@@ -0,0 +1,331 @@
+//! Owned, serializable model of a call graph
+//!
+//! Unlike `CallGraph`, the model does not borrow from `TyCtxt`, so it can be written to
+//! disk in a compact binary form (`--binary-output`) and loaded again without rustc.
+
+use anyhow::Context;
+use rustc_middle::ty::TyCtxt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::CallGraph;
+use super::fmt::read_output;
+
+/// Version byte prefixed to binary outputs, bump when the model changes
+pub const GRAPH_MODEL_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphModel {
+    pub crate_name: String,
+    pub compile_target: Option<String>,
+    pub call_sites: Vec<CallSiteModel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallSiteModel {
+    pub caller: String,
+    pub caller_path: String,
+    pub callee: String,
+    pub callee_path: String,
+    pub constraint_depth: usize,
+    pub loop_depth: usize,
+    pub call_kind: String,
+    pub resolution: String,
+    pub call_location: Option<String>,
+}
+
+impl GraphModel {
+    /// Serialize as the version byte followed by the postcard encoding
+    pub fn to_binary(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = vec![GRAPH_MODEL_VERSION];
+        bytes.extend(postcard::to_stdvec(self)?);
+        Ok(bytes)
+    }
+
+    /// Deserialize a binary output, rejecting unknown model versions
+    pub fn from_binary(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some((&version, payload)) = bytes.split_first() else {
+            anyhow::bail!("empty call graph file");
+        };
+        if version != GRAPH_MODEL_VERSION {
+            anyhow::bail!("unsupported call graph model version {version}, expected {GRAPH_MODEL_VERSION}");
+        }
+        Ok(postcard::from_bytes(payload)?)
+    }
+
+    /// Build the model from the JSON call graph written by `--json-output`
+    ///
+    /// Accepts the top-level array of `callgraph.json` as well as the objects of the
+    /// per-crate and `--feature-combo` outputs, which wrap it in a `call_graph` field,
+    /// and the `nodes`/`edges` layout of `--numeric-ids`.
+    /// The JSON does not record how a callee was called, so `call_kind` is `unknown`.
+    pub fn from_callgraph_json(value: &Value) -> anyhow::Result<Self> {
+        if value.get("nodes").is_some() {
+            return Self::from_numeric_json(value);
+        }
+        let (crate_name, entries) = match value {
+            Value::Array(entries) => (None, entries),
+            Value::Object(object) => match object.get("call_graph") {
+                Some(Value::Array(entries)) => {
+                    (object.get("crate_name").or_else(|| object.get("source_crate")), entries)
+                }
+                _ => anyhow::bail!("JSON object without a `call_graph` array"),
+            },
+            _ => anyhow::bail!("expected a JSON call graph array or object"),
+        };
+
+        let string = |value: &Value, field: &str| -> anyhow::Result<String> {
+            value[field]
+                .as_str()
+                .map(str::to_string)
+                .with_context(|| format!("missing string field `{field}` in {value}"))
+        };
+        let number = |value: &Value, field: &str| value[field].as_u64().unwrap_or(0) as usize;
+
+        let mut call_sites = Vec::new();
+        for entry in entries {
+            let caller = &entry["caller"];
+            for callee in entry["callee"].as_array().into_iter().flatten() {
+                call_sites.push(CallSiteModel {
+                    caller: string(caller, "name")?,
+                    caller_path: string(caller, "path")?,
+                    callee: string(callee, "name")?,
+                    callee_path: string(callee, "path")?,
+                    constraint_depth: number(callee, "constraint_depth"),
+                    loop_depth: number(callee, "loop_depth"),
+                    call_kind: "unknown".to_string(),
+                    resolution: callee["resolution"].as_str().unwrap_or("unknown").to_string(),
+                    call_location: callee["call_location"].as_str().map(str::to_string),
+                });
+            }
+        }
+
+        Ok(GraphModel {
+            crate_name: crate_name.and_then(Value::as_str).unwrap_or_default().to_string(),
+            compile_target: entries
+                .first()
+                .and_then(|entry| entry["compile_target"].as_str())
+                .map(str::to_string),
+            call_sites,
+        })
+    }
+
+    /// Parse the `--numeric-ids` layout of `callgraph.json`, with edges between node ids
+    fn from_numeric_json(value: &Value) -> anyhow::Result<Self> {
+        let mut nodes = HashMap::new();
+        for node in value["nodes"].as_array().context("`nodes` should be an array")? {
+            let id = node["id"]
+                .as_u64()
+                .with_context(|| format!("missing node id in {node}"))?;
+            let field = |field: &str| node[field].as_str().unwrap_or_default().to_string();
+            nodes.insert(id, (field("name"), field("path")));
+        }
+
+        let mut call_sites = Vec::new();
+        for edge in value["edges"].as_array().context("`edges` should be an array")? {
+            let node = |field: &str| {
+                edge[field]
+                    .as_u64()
+                    .and_then(|id| nodes.get(&id))
+                    .with_context(|| format!("edge `{field}` is not a node id in {edge}"))
+            };
+            let (caller, caller_path) = node("from")?;
+            let (callee, callee_path) = node("to")?;
+            call_sites.push(CallSiteModel {
+                caller: caller.clone(),
+                caller_path: caller_path.clone(),
+                callee: callee.clone(),
+                callee_path: callee_path.clone(),
+                constraint_depth: edge["constraint_count"].as_u64().unwrap_or(0) as usize,
+                loop_depth: 0,
+                call_kind: "unknown".to_string(),
+                resolution: "unknown".to_string(),
+                call_location: None,
+            });
+        }
+
+        Ok(GraphModel {
+            crate_name: value["crate_name"].as_str().unwrap_or_default().to_string(),
+            compile_target: value["compile_target"].as_str().map(str::to_string),
+            call_sites,
+        })
+    }
+
+    /// Load a `--binary-output` file, a `--json-output` call graph or the output of `load --format json`
+    ///
+    /// `--compress`ed `.gz` files are decompressed first.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let bytes = read_output(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+        // Binary outputs start with the version byte, never with JSON's `[` or `{`
+        match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'[' | b'{') => {
+                let value: Value = serde_json::from_slice(&bytes)?;
+                if value.get("call_sites").is_some() {
+                    Ok(serde_json::from_value(value)?)
+                } else {
+                    Self::from_callgraph_json(&value)
+                }
+            }
+            _ => Self::from_binary(&bytes),
+        }
+    }
+
+    /// Render like the default text output
+    pub fn to_text(&self) -> String {
+        let mut calls_by_caller: BTreeMap<&str, Vec<&CallSiteModel>> = BTreeMap::new();
+        for call_site in &self.call_sites {
+            calls_by_caller.entry(&call_site.caller).or_default().push(call_site);
+        }
+
+        let mut result = String::new();
+        result.push_str("Call Graph:\n");
+        result.push_str("===========\n\n");
+        for (caller, calls) in calls_by_caller {
+            result.push_str(&format!("Function: {caller}\n"));
+            for call in calls {
+                result.push_str(&format!(
+                    "  -> {} [constraint: {}]\n",
+                    call.callee, call.constraint_depth
+                ));
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    /// Build the owned model of the call sites included in outputs
+    pub(crate) fn to_model(&self, tcx: TyCtxt<'tcx>, options: &crate::args::CGArgs) -> GraphModel {
+        let mut call_sites: Vec<CallSiteModel> = self
+            .output_call_sites(tcx, options)
+            .into_iter()
+            .map(|call_site| {
+                let (caller, callee) = (
+                    self.edge_source(call_site, options),
+                    self.edge_target(call_site, options),
+                );
+                CallSiteModel {
+                    caller: self.function_path(tcx, caller).to_string(),
+                    caller_path: tcx.def_path_str(caller.def_id()),
+                    callee: self.function_path(tcx, callee).to_string(),
+                    callee_path: tcx.def_path_str(callee.def_id()),
+                    constraint_depth: call_site.constraint_count(),
+                    loop_depth: call_site.loop_depth(),
+                    call_kind: format!("{:?}", call_site.call_kind()),
+                    resolution: call_site.resolution().as_str().to_string(),
+                    call_location: call_site.call_location().map(|location| location.to_string()),
+                }
+            })
+            .collect();
+        call_sites.sort_by(|a, b| (&a.caller, &a.callee).cmp(&(&b.caller, &b.callee)));
+
+        GraphModel {
+            crate_name: tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string(),
+            compile_target: self.compile_target.clone(),
+            call_sites,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GraphModel {
+        GraphModel {
+            crate_name: "demo".to_string(),
+            compile_target: None,
+            call_sites: vec![CallSiteModel {
+                caller: "main".to_string(),
+                caller_path: "main".to_string(),
+                callee: "helper::<u8>".to_string(),
+                callee_path: "helper".to_string(),
+                constraint_depth: 1,
+                loop_depth: 0,
+                call_kind: "Direct".to_string(),
+                resolution: "exact".to_string(),
+                call_location: Some("src/main.rs:3:5".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let model = sample();
+        let bytes = model.to_binary().unwrap();
+        assert_eq!(bytes[0], GRAPH_MODEL_VERSION);
+        assert_eq!(GraphModel::from_binary(&bytes).unwrap(), model);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut bytes = sample().to_binary().unwrap();
+        bytes[0] = GRAPH_MODEL_VERSION + 1;
+        assert!(GraphModel::from_binary(&bytes).is_err());
+        assert!(GraphModel::from_binary(&[]).is_err());
+    }
+
+    #[test]
+    fn loads_callgraph_json() {
+        let json = serde_json::json!([{
+            "caller": { "name": "main", "path": "main", "constraint_depth": 1 },
+            "callee": [{
+                "name": "helper::<u8>",
+                "path": "helper",
+                "constraint_depth": 1,
+                "loop_depth": 0,
+                "resolution": "exact",
+                "call_location": "src/main.rs:3:5"
+            }]
+        }]);
+        let model = GraphModel::from_callgraph_json(&json).unwrap();
+        let expected = sample().call_sites[0].clone();
+        assert_eq!(
+            model.call_sites,
+            vec![CallSiteModel {
+                call_kind: "unknown".to_string(),
+                ..expected
+            }]
+        );
+
+        let wrapped = serde_json::json!({ "crate_name": "demo", "features": [], "call_graph": json });
+        let model = GraphModel::from_callgraph_json(&wrapped).unwrap();
+        assert_eq!(model.crate_name, "demo");
+        assert_eq!(model.call_sites.len(), 1);
+        assert!(GraphModel::from_callgraph_json(&serde_json::json!({ "crate_name": "demo" })).is_err());
+    }
+
+    #[test]
+    fn loads_numeric_id_json() {
+        let json = serde_json::json!({
+            "crate_name": "demo",
+            "nodes": [
+                { "id": 0, "name": "helper::<u8>", "path": "helper", "crate": "demo", "version": "0.1.0", "path_hash": "01" },
+                { "id": 1, "name": "main", "path": "main", "crate": "demo", "version": "0.1.0", "path_hash": "02" }
+            ],
+            "edges": [{ "from": 1, "to": 0, "constraint_count": 1 }]
+        });
+        let model = GraphModel::from_callgraph_json(&json).unwrap();
+        assert_eq!(model.crate_name, "demo");
+        assert_eq!(model.to_text(), sample().to_text());
+
+        let dangling = serde_json::json!({ "nodes": [], "edges": [{ "from": 0, "to": 1 }] });
+        assert!(GraphModel::from_callgraph_json(&dangling).is_err());
+    }
+
+    #[test]
+    fn text_matches_call_graph_layout() {
+        assert_eq!(
+            sample().to_text(),
+            "Call Graph:\n===========\n\nFunction: main\n  -> helper::<u8> [constraint: 1]\n\n"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+/// Turn a function path, file path or crate name into a portable file name component
+///
+/// Path separators (`::`, `/`, `\`), generic brackets, drive letter colons and the other
+/// characters Windows rejects in file names are replaced with `-`, consecutive `-` are
+/// collapsed and leading/trailing `-` are removed. The result only depends on `s`, and
+/// sanitizing an already sanitized name returns it unchanged.
+pub(crate) fn sanitize_path_for_filename(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = match c {
+            ':' | '<' | '>' | '/' | '\\' | '"' | '|' | '?' | '*' => '-',
+            c => c,
+        };
+        if c == '-' && result.ends_with('-') {
+            continue;
+        }
+        result.push(c);
+    }
+    result.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_windows_paths() {
+        assert_eq!(sanitize_path_for_filename(r"C:\foo\bar"), "C-foo-bar");
+        assert_eq!(sanitize_path_for_filename(r"C:\\foo\\bar.rs"), "C-foo-bar.rs");
+    }
+
+    #[test]
+    fn test_sanitize_unix_paths() {
+        assert_eq!(sanitize_path_for_filename("/usr/lib/foo.rs"), "usr-lib-foo.rs");
+        assert_eq!(sanitize_path_for_filename("src//main.rs"), "src-main.rs");
+    }
+
+    #[test]
+    fn test_sanitize_rust_paths() {
+        assert_eq!(sanitize_path_for_filename("std::vec::Vec::new"), "std-vec-Vec-new");
+        assert_eq!(sanitize_path_for_filename("Vec<HashMap<K,V>>"), "Vec-HashMap-K,V");
+        assert_eq!(
+            sanitize_path_for_filename("<Electronics as Product>::price"),
+            "Electronics as Product-price"
+        );
+        assert_eq!(sanitize_path_for_filename("my_crate"), "my_crate");
+    }
+
+    #[test]
+    fn test_sanitize_is_stable() {
+        for input in [r"C:\foo\bar", "/usr/lib", "Vec<HashMap<K,V>>", "a::<b>::c", "--x--"] {
+            let sanitized = sanitize_path_for_filename(input);
+            assert_eq!(sanitize_path_for_filename(input), sanitized);
+            assert_eq!(sanitize_path_for_filename(&sanitized), sanitized);
+            assert!(!sanitized.contains("--"), "{sanitized}");
+        }
+    }
+}
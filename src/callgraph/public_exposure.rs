@@ -1,10 +1,10 @@
 use crate::args::CGArgs;
+use crate::callgraph::fmt::write_output;
 use crate::callgraph::function::FunctionInstance;
 use crate::callgraph::types::CallGraph;
 use rustc_middle::ty::TyCtxt;
 use serde_json::json;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::{self, File};
 use std::io::Write;
 
 pub fn analyze_public_exposure<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'tcx>, args: &CGArgs) {
@@ -32,16 +32,26 @@ pub fn analyze_public_exposure<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'
         let callee = call_site.callee();
 
         if has_targets {
-            if target_patterns
-                .iter()
-                .any(|p| crate::callgraph::utils::matches_function_path(tcx, callee, p, args.without_args))
-            {
+            if target_patterns.iter().any(|p| {
+                crate::callgraph::utils::matches_function_path(
+                    tcx,
+                    callee,
+                    p,
+                    args.without_args,
+                    args.no_generic_stripping,
+                )
+            }) {
                 targets.insert(callee);
             }
-            if target_patterns
-                .iter()
-                .any(|p| crate::callgraph::utils::matches_function_path(tcx, caller, p, args.without_args))
-            {
+            if target_patterns.iter().any(|p| {
+                crate::callgraph::utils::matches_function_path(
+                    tcx,
+                    caller,
+                    p,
+                    args.without_args,
+                    args.no_generic_stripping,
+                )
+            }) {
                 targets.insert(caller);
             }
         }
@@ -71,10 +81,9 @@ pub fn analyze_public_exposure<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'
 
     if has_targets {
         for &f in &discovered {
-            if target_patterns
-                .iter()
-                .any(|p| crate::callgraph::utils::matches_function_path(tcx, f, p, args.without_args))
-            {
+            if target_patterns.iter().any(|p| {
+                crate::callgraph::utils::matches_function_path(tcx, f, p, args.without_args, args.no_generic_stripping)
+            }) {
                 targets.insert(f);
             }
         }
@@ -232,23 +241,12 @@ pub fn analyze_public_exposure<'tcx>(call_graph: &CallGraph<'tcx>, tcx: TyCtxt<'
         .clone()
         .unwrap_or_else(|| std::path::PathBuf::from("./target"));
 
-    if !output_dir.exists() {
-        let _ = fs::create_dir_all(&output_dir);
-    }
-
     let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
-    let output_path = output_dir.join(format!("{}-public-exposure.json", crate_name));
-
-    match File::create(&output_path) {
-        Ok(mut file) => {
-            if let Err(e) = write!(file, "{}", serde_json::to_string_pretty(&result).unwrap()) {
-                tracing::error!("Failed to write public exposure results to file: {}", e);
-            } else {
-                tracing::info!("Public exposure results written to {}", output_path.display());
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to create public exposure output file: {}", e);
-        }
+    let output_path = output_dir.join(args.output_file_name(&format!("{}-public-exposure.json", crate_name)));
+    let output = serde_json::to_string_pretty(&result).unwrap();
+
+    match write_output(&output_path, args.compress, |file| write!(file, "{output}")) {
+        Ok(path) => tracing::info!("Public exposure results written to {}", path.display()),
+        Err(e) => tracing::error!("Failed to write public exposure results to file: {}", e),
     }
 }
@@ -0,0 +1,597 @@
+//! Offline queries on a saved call graph, see `cargo cg4rs query`
+
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use super::model::GraphModel;
+use super::utils::matches_path_strings;
+use crate::args::QueryArgs;
+
+/// A `--query` of the form `A -> B` (direct call) or `A ->* B` (direct or indirect call)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathQuery {
+    pub source: String,
+    pub target: String,
+    pub transitive: bool,
+}
+
+impl PathQuery {
+    /// Parse `A -> B` or `A ->* B`, where `A` and `B` are paths matched as by `--find-callers`
+    ///
+    /// The query is split on its first arrow outside of `<...>` and `(...)`, so either side
+    /// may contain arrows of its own, e.g. in `apply::<fn() -> u8> ->* helper`.
+    pub fn parse(query: &str) -> anyhow::Result<Self> {
+        let Some((source, rest)) = split_top_level_arrow(query) else {
+            anyhow::bail!("invalid query `{query}`, expected `A -> B` or `A ->* B`");
+        };
+        let (target, transitive) = match rest.strip_prefix('*') {
+            Some(target) => (target, true),
+            None => (rest, false),
+        };
+        let (source, target) = (source.trim(), target.trim());
+        if source.is_empty() || target.is_empty() {
+            anyhow::bail!("invalid query `{query}`, both sides of the arrow need a function path");
+        }
+        Ok(PathQuery {
+            source: source.to_string(),
+            target: target.to_string(),
+            transitive,
+        })
+    }
+}
+
+/// Split `query` around its first `->` that is not nested in generic arguments or a signature
+fn split_top_level_arrow(query: &str) -> Option<(&str, &str)> {
+    let bytes = query.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'>') => {
+                if depth == 0 {
+                    return Some((&query[..i], &query[i + 2..]));
+                }
+                // The `>` of a nested arrow does not close a `<`
+                i += 1;
+            }
+            b'<' | b'(' => depth += 1,
+            b'>' | b')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Adjacency lists of a loaded call graph, with functions identified by their full name
+pub struct GraphQuery {
+    /// Full name and def path of each function
+    functions: Vec<(String, String)>,
+    callees: Vec<BTreeSet<usize>>,
+    callers: Vec<BTreeSet<usize>>,
+}
+
+impl GraphQuery {
+    pub fn new(model: &GraphModel) -> Self {
+        let mut query = GraphQuery {
+            functions: Vec::new(),
+            callees: Vec::new(),
+            callers: Vec::new(),
+        };
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        for call_site in &model.call_sites {
+            let caller = query.function_id(&mut ids, &call_site.caller, &call_site.caller_path);
+            let callee = query.function_id(&mut ids, &call_site.callee, &call_site.callee_path);
+            query.callees[caller].insert(callee);
+            query.callers[callee].insert(caller);
+        }
+        query
+    }
+
+    fn function_id(&mut self, ids: &mut HashMap<String, usize>, name: &str, path: &str) -> usize {
+        *ids.entry(name.to_string()).or_insert_with(|| {
+            self.functions.push((name.to_string(), path.to_string()));
+            self.callees.push(BTreeSet::new());
+            self.callers.push(BTreeSet::new());
+            self.functions.len() - 1
+        })
+    }
+
+    /// Functions whose full name or def path matches `target`
+    fn matching(&self, target: &str) -> Vec<usize> {
+        (0..self.functions.len())
+            .filter(|&id| {
+                let (name, path) = &self.functions[id];
+                matches_path_strings(name, path, target, false, false)
+            })
+            .collect()
+    }
+
+    /// Functions whose name, the last segment of their def path, contains `name` ignoring case
+    ///
+    /// Returns the full name and def path of each match, sorted by full name.
+    pub fn find_functions_by_name(&self, name: &str) -> Vec<(String, String)> {
+        let name = name.to_lowercase();
+        let mut result: Vec<(String, String)> = self
+            .functions
+            .iter()
+            .filter(|(_, path)| {
+                path.rsplit("::")
+                    .next()
+                    .is_some_and(|segment| segment.to_lowercase().contains(&name))
+            })
+            .cloned()
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Full names of the functions whose def path matches `path`, sorted
+    ///
+    /// Paths are matched as by `--find-callers`, e.g. `helper` or `demo::helper` match all instances
+    /// of `demo::helper`.
+    pub fn find_functions_by_path(&self, path: &str) -> Vec<String> {
+        let mut result: Vec<String> = self
+            .functions
+            .iter()
+            .filter(|(_, def_path)| matches_path_strings(def_path, def_path, path, false, false))
+            .map(|(name, _)| name.clone())
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Breadth-first search from `sources` along `edges`, with the predecessor of each reached function
+    fn search(&self, sources: &[usize], edges: &[BTreeSet<usize>]) -> BTreeMap<usize, (usize, Option<usize>)> {
+        let mut reached: BTreeMap<usize, (usize, Option<usize>)> =
+            sources.iter().map(|&source| (source, (0, None))).collect();
+        let mut queue: VecDeque<usize> = sources.iter().copied().collect();
+        while let Some(func) = queue.pop_front() {
+            let distance = reached[&func].0;
+            for &next in &edges[func] {
+                if !reached.contains_key(&next) {
+                    reached.insert(next, (distance + 1, Some(func)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Functions reachable from the matches of `target` along `edges`, by distance then name
+    fn transitive(&self, target: &str, edges: &[BTreeSet<usize>]) -> Vec<(String, usize)> {
+        let mut result: Vec<(String, usize)> = self
+            .search(&self.matching(target), edges)
+            .into_iter()
+            .filter(|(_, (distance, _))| *distance > 0)
+            .map(|(func, (distance, _))| (self.functions[func].0.clone(), distance))
+            .collect();
+        result.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0)));
+        result
+    }
+
+    /// All direct and indirect callers of `target`, with their distance in calls
+    pub fn find_callers(&self, target: &str) -> Vec<(String, usize)> {
+        self.transitive(target, &self.callers)
+    }
+
+    /// All functions directly or indirectly called by `target`, with their distance in calls
+    pub fn find_callees(&self, target: &str) -> Vec<(String, usize)> {
+        self.transitive(target, &self.callees)
+    }
+
+    /// A shortest call chain from a function matching `from` to one matching `to`
+    pub fn find_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let reached = self.search(&self.matching(from), &self.callees);
+        let mut current = self
+            .matching(to)
+            .into_iter()
+            .filter_map(|func| reached.get(&func).map(|(distance, _)| (*distance, func)))
+            .min()?
+            .1;
+
+        let mut path = vec![self.functions[current].0.clone()];
+        while let Some(previous) = reached[&current].1 {
+            path.push(self.functions[previous].0.clone());
+            current = previous;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// A witness call chain answering `query`, or `None` when no matching function calls a matching one
+    ///
+    /// The chain is a shortest one and has at least one call, so `A ->* A` only holds for recursive
+    /// functions. Direct queries only follow a single call.
+    pub fn check(&self, query: &PathQuery) -> Option<Vec<String>> {
+        // Functions called by a source, with the source calling them
+        let mut first_calls: BTreeMap<usize, usize> = BTreeMap::new();
+        for source in self.matching(&query.source) {
+            for &callee in &self.callees[source] {
+                first_calls.entry(callee).or_insert(source);
+            }
+        }
+        let first_callees: Vec<usize> = first_calls.keys().copied().collect();
+        let reached = if query.transitive {
+            self.search(&first_callees, &self.callees)
+        } else {
+            first_callees.iter().map(|&callee| (callee, (0, None))).collect()
+        };
+
+        let (_, mut current) = self
+            .matching(&query.target)
+            .into_iter()
+            .filter_map(|func| reached.get(&func).map(|(distance, _)| (*distance, func)))
+            .min()?;
+        let mut path = vec![self.functions[current].0.clone()];
+        while let Some(previous) = reached[&current].1 {
+            path.push(self.functions[previous].0.clone());
+            current = previous;
+        }
+        path.push(self.functions[first_calls[&current]].0.clone());
+        path.reverse();
+        Some(path)
+    }
+
+    /// Strongly connected components that contain a cycle, each as its sorted function names
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let count = self.functions.len();
+
+        // First pass: order functions by the completion of a depth-first search
+        let mut visited = vec![false; count];
+        let mut order = Vec::with_capacity(count);
+        for start in 0..count {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack = vec![(start, self.callees[start].iter())];
+            while let Some((func, callees)) = stack.last_mut() {
+                if let Some(&callee) = callees.next() {
+                    if !visited[callee] {
+                        visited[callee] = true;
+                        stack.push((callee, self.callees[callee].iter()));
+                    }
+                } else {
+                    order.push(*func);
+                    stack.pop();
+                }
+            }
+        }
+
+        // Second pass: collect components along the callers, in reverse completion order
+        let mut assigned = vec![false; count];
+        let mut cycles = Vec::new();
+        for &start in order.iter().rev() {
+            if assigned[start] {
+                continue;
+            }
+            assigned[start] = true;
+            let mut members = vec![start];
+            let mut next = 0;
+            while let Some(&func) = members.get(next) {
+                next += 1;
+                for &caller in &self.callers[func] {
+                    if !assigned[caller] {
+                        assigned[caller] = true;
+                        members.push(caller);
+                    }
+                }
+            }
+            if members.len() > 1 || self.callees[start].contains(&start) {
+                let mut names: Vec<String> = members.iter().map(|&func| self.functions[func].0.clone()).collect();
+                names.sort();
+                cycles.push(names);
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+}
+
+/// The answers of `cargo cg4rs query`
+pub struct QueryReport {
+    /// Text report or JSON, as printed to stdout
+    pub output: String,
+    /// Whether `--query` holds, `None` without `--query`
+    pub query_holds: Option<bool>,
+}
+
+/// Answer the questions of `cargo cg4rs query`, as a text report or JSON
+pub fn run_query(args: &QueryArgs) -> anyhow::Result<QueryReport> {
+    let path_query = args.query.as_deref().map(PathQuery::parse).transpose()?;
+    let model = GraphModel::load(&args.graph)?;
+    let query = GraphQuery::new(&model);
+
+    let callers: Vec<(&String, Vec<(String, usize)>)> = args
+        .find_callers
+        .iter()
+        .map(|target| (target, query.find_callers(target)))
+        .collect();
+    let callees: Vec<(&String, Vec<(String, usize)>)> = args
+        .find_callees
+        .iter()
+        .map(|target| (target, query.find_callees(target)))
+        .collect();
+    let path = match (&args.path_from, &args.path_to) {
+        (Some(from), Some(to)) => Some((from, to, query.find_path(from, to))),
+        _ => None,
+    };
+    let cycles = args.cycles.then(|| query.cycles());
+    let checked = path_query.map(|path_query| {
+        let witness = query.check(&path_query);
+        (path_query, witness)
+    });
+    let query_holds = checked.as_ref().map(|(_, witness)| witness.is_some());
+
+    if args.json {
+        let functions_json = |functions: &[(String, usize)]| {
+            functions
+                .iter()
+                .map(|(name, distance)| json!({ "name": name, "distance": distance }))
+                .collect::<Vec<_>>()
+        };
+        let mut result = json!({ "crate_name": model.crate_name });
+        if !callers.is_empty() {
+            result["callers"] = json!(
+                callers
+                    .iter()
+                    .map(|(target, found)| json!({ "target": target, "callers": functions_json(found) }))
+                    .collect::<Vec<_>>()
+            );
+        }
+        if !callees.is_empty() {
+            result["callees"] = json!(
+                callees
+                    .iter()
+                    .map(|(target, found)| json!({ "target": target, "callees": functions_json(found) }))
+                    .collect::<Vec<_>>()
+            );
+        }
+        if let Some((from, to, path)) = &path {
+            result["path"] = json!({ "from": from, "to": to, "path": path });
+        }
+        if let Some(cycles) = &cycles {
+            result["cycles"] = json!(cycles);
+        }
+        if let Some((path_query, witness)) = &checked {
+            result["query"] = json!({
+                "source": path_query.source,
+                "target": path_query.target,
+                "transitive": path_query.transitive,
+                "holds": witness.is_some(),
+                "witness": witness,
+            });
+        }
+        return Ok(QueryReport {
+            output: serde_json::to_string_pretty(&result)?,
+            query_holds,
+        });
+    }
+
+    let mut result = String::new();
+    for (title, answers) in [("Callers", &callers), ("Callees", &callees)] {
+        for (target, found) in answers {
+            result.push_str(&format!("{title} of {target}: {}\n", found.len()));
+            for (name, distance) in found {
+                result.push_str(&format!("  {name} [distance: {distance}]\n"));
+            }
+            result.push('\n');
+        }
+    }
+    if let Some((from, to, path)) = &path {
+        match path {
+            Some(path) => result.push_str(&format!("Path from {from} to {to}:\n  {}\n\n", path.join(" -> "))),
+            None => result.push_str(&format!("No path from {from} to {to}\n\n")),
+        }
+    }
+    if let Some(cycles) = &cycles {
+        result.push_str(&format!("Cycles: {}\n", cycles.len()));
+        for cycle in cycles {
+            result.push_str(&format!("  {}\n", cycle.join(", ")));
+        }
+    }
+    if let Some((path_query, witness)) = &checked {
+        let arrow = if path_query.transitive { "->*" } else { "->" };
+        let question = format!("{} {arrow} {}", path_query.source, path_query.target);
+        match witness {
+            Some(path) => result.push_str(&format!("{question}: yes\n  {}\n", path.join(" -> "))),
+            None => result.push_str(&format!("{question}: no\n")),
+        }
+    }
+    Ok(QueryReport {
+        output: result,
+        query_holds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::CallSiteModel;
+
+    fn model(edges: &[(&str, &str)]) -> GraphModel {
+        GraphModel {
+            crate_name: "demo".to_string(),
+            compile_target: None,
+            call_sites: edges
+                .iter()
+                .map(|(caller, callee)| CallSiteModel {
+                    caller: caller.to_string(),
+                    caller_path: caller.split("::<").next().unwrap().to_string(),
+                    callee: callee.to_string(),
+                    callee_path: callee.split("::<").next().unwrap().to_string(),
+                    constraint_depth: 0,
+                    loop_depth: 0,
+                    call_kind: "Direct".to_string(),
+                    resolution: "exact".to_string(),
+                    call_location: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn sample() -> GraphQuery {
+        GraphQuery::new(&model(&[
+            ("demo::main", "demo::run"),
+            ("demo::main", "demo::helper::<u8>"),
+            ("demo::run", "demo::helper::<u32>"),
+            ("demo::run", "demo::ping"),
+            ("demo::ping", "demo::pong"),
+            ("demo::pong", "demo::ping"),
+            ("demo::countdown", "demo::countdown"),
+        ]))
+    }
+
+    #[test]
+    fn test_find_callers_matches_all_instances() {
+        assert_eq!(
+            sample().find_callers("demo::helper"),
+            vec![("demo::main".to_string(), 1), ("demo::run".to_string(), 1)]
+        );
+        assert_eq!(
+            sample().find_callers("demo::pong"),
+            vec![
+                ("demo::ping".to_string(), 1),
+                ("demo::run".to_string(), 2),
+                ("demo::main".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_callees() {
+        let callees: Vec<String> = sample()
+            .find_callees("demo::run")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(callees, vec!["demo::helper::<u32>", "demo::ping", "demo::pong"]);
+        assert!(sample().find_callees("demo::missing").is_empty());
+    }
+
+    #[test]
+    fn test_find_functions_by_name() {
+        let helpers = vec![
+            ("demo::helper::<u32>".to_string(), "demo::helper".to_string()),
+            ("demo::helper::<u8>".to_string(), "demo::helper".to_string()),
+        ];
+        assert_eq!(sample().find_functions_by_name("helper"), helpers);
+        assert_eq!(sample().find_functions_by_name("HELP"), helpers);
+        // Only the function name is matched, not the crate or module
+        assert!(sample().find_functions_by_name("demo").is_empty());
+        assert!(sample().find_functions_by_name("missing").is_empty());
+    }
+
+    #[test]
+    fn test_find_functions_by_path() {
+        assert_eq!(
+            sample().find_functions_by_path("demo::helper"),
+            vec!["demo::helper::<u32>", "demo::helper::<u8>"]
+        );
+        assert_eq!(sample().find_functions_by_path("demo::ping"), vec!["demo::ping"]);
+        assert!(sample().find_functions_by_path("demo::missing").is_empty());
+    }
+
+    #[test]
+    fn test_find_path() {
+        assert_eq!(
+            sample().find_path("demo::main", "demo::pong"),
+            Some(vec![
+                "demo::main".to_string(),
+                "demo::run".to_string(),
+                "demo::ping".to_string(),
+                "demo::pong".to_string()
+            ])
+        );
+        assert_eq!(sample().find_path("demo::pong", "demo::main"), None);
+    }
+
+    #[test]
+    fn test_parse_path_query() {
+        assert_eq!(
+            PathQuery::parse("demo::main -> demo::helper").unwrap(),
+            PathQuery {
+                source: "demo::main".to_string(),
+                target: "demo::helper".to_string(),
+                transitive: false
+            }
+        );
+        assert_eq!(
+            PathQuery::parse("main->*pong").unwrap(),
+            PathQuery {
+                source: "main".to_string(),
+                target: "pong".to_string(),
+                transitive: true
+            }
+        );
+        // Arrows nested in generic arguments do not separate the two sides
+        assert_eq!(
+            PathQuery::parse("demo::apply::<fn() -> u8> ->* demo::helper").unwrap(),
+            PathQuery {
+                source: "demo::apply::<fn() -> u8>".to_string(),
+                target: "demo::helper".to_string(),
+                transitive: true
+            }
+        );
+        assert_eq!(
+            PathQuery::parse("main ->* apply::<fn() -> u8>").unwrap(),
+            PathQuery {
+                source: "main".to_string(),
+                target: "apply::<fn() -> u8>".to_string(),
+                transitive: true
+            }
+        );
+        assert_eq!(
+            PathQuery::parse("call::<fn(u8) -> u8> -> apply::<fn() -> u8>").unwrap(),
+            PathQuery {
+                source: "call::<fn(u8) -> u8>".to_string(),
+                target: "apply::<fn() -> u8>".to_string(),
+                transitive: false
+            }
+        );
+        assert!(PathQuery::parse("demo::main").is_err());
+        assert!(PathQuery::parse("demo::main ->* ").is_err());
+    }
+
+    #[test]
+    fn test_check_direct_and_transitive() {
+        let query = |text: &str| sample().check(&PathQuery::parse(text).unwrap());
+        assert_eq!(
+            query("demo::main -> demo::helper"),
+            Some(vec!["demo::main".to_string(), "demo::helper::<u8>".to_string()])
+        );
+        assert_eq!(query("demo::main -> demo::pong"), None);
+        assert_eq!(
+            query("demo::main ->* demo::pong"),
+            Some(vec![
+                "demo::main".to_string(),
+                "demo::run".to_string(),
+                "demo::ping".to_string(),
+                "demo::pong".to_string()
+            ])
+        );
+        assert_eq!(query("demo::pong ->* demo::main"), None);
+        // At least one call is needed, so only recursive functions reach themselves
+        assert_eq!(
+            query("demo::ping ->* demo::ping"),
+            Some(vec![
+                "demo::ping".to_string(),
+                "demo::pong".to_string(),
+                "demo::ping".to_string()
+            ])
+        );
+        assert_eq!(query("demo::run ->* demo::run"), None);
+    }
+
+    #[test]
+    fn test_cycles() {
+        assert_eq!(
+            sample().cycles(),
+            vec![
+                vec!["demo::countdown".to_string()],
+                vec!["demo::ping".to_string(), "demo::pong".to_string()]
+            ]
+        );
+    }
+}
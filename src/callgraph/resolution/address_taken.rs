@@ -1,4 +1,5 @@
 use super::super::function::{FunctionInstance, iterate_all_functions};
+use super::super::utils::optimized_mir_if_available;
 use lazy_static::lazy_static;
 use rustc_hir::{def, def_id::DefId};
 use rustc_middle::{
@@ -16,7 +17,8 @@ struct AddressTakenCollector<'tcx> {
 
 impl<'tcx> Visitor<'tcx> for AddressTakenCollector<'tcx> {
     fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: mir::Location) {
-        if let TerminatorKind::Call { func, args, .. } = &terminator.kind {
+        if let TerminatorKind::Call { func, args, .. } | TerminatorKind::TailCall { func, args, .. } = &terminator.kind
+        {
             match func {
                 mir::Operand::Constant(c) => {
                     if !matches!(c.ty().kind(), ty::TyKind::FnDef(..)) {
@@ -58,8 +60,7 @@ pub(crate) fn collect_address_taken_functions<'tcx>(tcx: TyCtxt<'tcx>) -> HashSe
         tcx,
         |_| true,
         |def_id| {
-            if tcx.is_mir_available(def_id) {
-                let body = tcx.optimized_mir(def_id);
+            if let Some(body) = optimized_mir_if_available(tcx, def_id) {
                 collector.visit_body(body);
             }
             None::<FunctionInstance>
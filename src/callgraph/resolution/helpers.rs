@@ -2,7 +2,7 @@ use super::super::function::FunctionInstance;
 use rustc_hir::def_id::DefId;
 use rustc_middle::{
     mir,
-    ty::{self, Instance, TyCtxt, TypeFoldable, TypingEnv},
+    ty::{self, Instance, Ty, TyCtxt, TypeFoldable, TypeFolder, TypeSuperFoldable, TypeVisitableExt, TypingEnv},
 };
 
 /// Monomorphize a value in the context of an instance
@@ -18,6 +18,78 @@ where
     instance.try_instantiate_mir_and_normalize_erasing_regions(tcx, typing_env, ty::EarlyBinder::bind(value))
 }
 
+/// Replace opaque types (`impl Trait`) in `value` by their concrete hidden types
+///
+/// Normalizing in a post-analysis `TypingEnv` usually reveals opaque types already, but
+/// types that failed to normalize or come from built MIR may still contain them, and
+/// calls on an unrevealed opaque (e.g. `next` on a returned `impl Iterator`) cannot be
+/// resolved to the concrete impl.
+pub(crate) fn reveal_opaque_types<'tcx, T>(tcx: TyCtxt<'tcx>, value: T) -> T
+where
+    T: TypeFoldable<TyCtxt<'tcx>>,
+{
+    value.fold_with(&mut OpaqueRevealer {
+        tcx,
+        expanding: Vec::new(),
+    })
+}
+
+struct OpaqueRevealer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// Opaque types currently being revealed, to stop on (invalid) recursive opaques
+    expanding: Vec<DefId>,
+}
+
+impl<'tcx> TypeFolder<TyCtxt<'tcx>> for OpaqueRevealer<'tcx> {
+    fn cx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn fold_ty(&mut self, ty: Ty<'tcx>) -> Ty<'tcx> {
+        if !ty.has_opaque_types() {
+            return ty;
+        }
+        if let ty::Alias(ty::Opaque, alias) = *ty.kind() {
+            if self.expanding.contains(&alias.def_id) {
+                return ty;
+            }
+            self.expanding.push(alias.def_id);
+            let hidden = self.tcx.type_of(alias.def_id).instantiate(self.tcx, alias.args);
+            let revealed = hidden.fold_with(self);
+            self.expanding.pop();
+            return revealed;
+        }
+        ty.super_fold_with(self)
+    }
+}
+
+/// Look through the shims rustc resolves `Fn`/`FnMut`/`FnOnce` calls to
+///
+/// Calling `FnOnce::call_once` on a `Fn`/`FnMut` closure resolves to a `ClosureOnceShim`,
+/// and calling a fn item through any of the traits to a `FnPtrShim`. Both are keyed by the
+/// trait method, which has no MIR body, so the closure or fn item they forward to is
+/// returned instead. Other instances are returned unchanged.
+pub(crate) fn resolve_fn_trait_shim<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    typing_env: TypingEnv<'tcx>,
+    instance: Instance<'tcx>,
+) -> Instance<'tcx> {
+    match instance.def {
+        ty::InstanceKind::ClosureOnceShim { .. } => match *instance.args.type_at(0).kind() {
+            ty::Closure(closure_def_id, closure_args) => Instance::new_raw(closure_def_id, closure_args),
+            _ => instance,
+        },
+        ty::InstanceKind::FnPtrShim(_, fn_ty) => match *fn_ty.kind() {
+            ty::FnDef(def_id, args) => match Instance::try_resolve(tcx, typing_env, def_id, args) {
+                Ok(Some(target)) => target,
+                _ => instance,
+            },
+            _ => instance,
+        },
+        _ => instance,
+    }
+}
+
 /// Trivially resolve a DefId to a FunctionInstance
 ///
 /// # Arguments
@@ -17,4 +17,7 @@ pub(crate) use dyn_trait::{
     peel_dyn_from_receiver,
 };
 pub(crate) use fnptr::candidates_for_fnptr_sig;
-pub(crate) use helpers::{fallback_callable_def_id_from_ty, monomorphize, operand_fn_def, trivial_resolve};
+pub(crate) use helpers::{
+    fallback_callable_def_id_from_ty, monomorphize, operand_fn_def, resolve_fn_trait_shim, reveal_opaque_types,
+    trivial_resolve,
+};
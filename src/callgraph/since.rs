@@ -0,0 +1,151 @@
+use rustc_hir::def::Res;
+use rustc_hir::def_id::DefId;
+use rustc_hir::intravisit::{self, Visitor};
+use rustc_hir::{Expr, ExprKind};
+use rustc_middle::ty::{TyCtxt, TypeckResults};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::function::FunctionInstance;
+
+/// Directory `--since` diffs in: the manifest directory, or the current one
+pub(crate) fn diff_dir(args: &crate::args::CGArgs) -> &Path {
+    args.manifest_path
+        .as_deref()
+        .and_then(Path::parent)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// Files changed since `git_ref`, canonicalized
+///
+/// Runs `git diff --name-only --relative <git_ref>` in `dir`. Returns `None` when git
+/// is not available or the command fails, so callers can fall back to a full analysis.
+pub(crate) fn changed_files(dir: &Path, git_ref: &str) -> Option<HashSet<PathBuf>> {
+    let output = match Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--name-only", "--relative", git_ref])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) => {
+            tracing::warn!("Failed to run git: {}", e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        tracing::warn!(
+            "git diff against {} failed: {}",
+            git_ref,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    // Deleted files cannot be canonicalized, but also contain no functions anymore
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| dir.join(line).canonicalize().ok())
+            .collect(),
+    )
+}
+
+/// Whether `def_id` is defined in one of `files`
+fn defined_in(tcx: TyCtxt<'_>, def_id: DefId, files: &HashSet<PathBuf>) -> bool {
+    let loc = tcx.sess.source_map().lookup_char_pos(tcx.def_span(def_id).lo());
+    let file = PathBuf::from(loc.file.name.prefer_local().to_string());
+    file.canonicalize().is_ok_and(|file| files.contains(&file))
+}
+
+/// Seeds for an impact analysis: instances defined in `files` and their transitive local callers
+///
+/// Callers are found from the type-checked HIR, so calls through trait methods match
+/// changed impls via the trait item they implement.
+pub(crate) fn changed_seeds<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    instances: Vec<FunctionInstance<'tcx>>,
+    files: &HashSet<PathBuf>,
+) -> Vec<FunctionInstance<'tcx>> {
+    let mut impacted: HashSet<DefId> = instances
+        .iter()
+        .map(|instance| instance.def_id())
+        .filter(|&def_id| defined_in(tcx, def_id, files))
+        .collect();
+    tracing::info!("{} functions changed", impacted.len());
+
+    // Also count changed impl methods as changes of the trait methods they implement
+    let mut queue: VecDeque<DefId> = impacted.iter().copied().collect();
+    queue.extend(
+        impacted
+            .iter()
+            .filter_map(|&def_id| tcx.opt_associated_item(def_id)?.trait_item_def_id),
+    );
+
+    let callers_of = local_callers(tcx);
+    while let Some(callee) = queue.pop_front() {
+        for &caller in callers_of.get(&callee).into_iter().flatten() {
+            if impacted.insert(caller) {
+                queue.push_back(caller);
+            }
+        }
+    }
+
+    instances
+        .into_iter()
+        .filter(|instance| impacted.contains(&instance.def_id()))
+        .collect()
+}
+
+/// Callee -> local callers, from the call expressions of every local body
+fn local_callers(tcx: TyCtxt<'_>) -> HashMap<DefId, Vec<DefId>> {
+    let mut callers_of: HashMap<DefId, Vec<DefId>> = HashMap::new();
+    for owner in tcx.hir_body_owners() {
+        let mut visitor = CallVisitor {
+            typeck_results: tcx.typeck(owner),
+            callees: HashSet::new(),
+        };
+        visitor.visit_expr(tcx.hir_body_owned_by(owner).value);
+        for callee in visitor.callees {
+            callers_of.entry(callee).or_default().push(owner.to_def_id());
+        }
+        // Closures are called through `Fn*` traits, attribute them to their enclosing function
+        if tcx.is_closure_like(owner.to_def_id()) {
+            callers_of
+                .entry(owner.to_def_id())
+                .or_default()
+                .push(tcx.typeck_root_def_id(owner.to_def_id()));
+        }
+    }
+    callers_of
+}
+
+/// Collects the statically known callees of a body
+struct CallVisitor<'tcx> {
+    typeck_results: &'tcx TypeckResults<'tcx>,
+    callees: HashSet<DefId>,
+}
+
+impl<'tcx> Visitor<'tcx> for CallVisitor<'tcx> {
+    fn visit_expr(&mut self, expr: &'tcx Expr<'tcx>) {
+        match expr.kind {
+            ExprKind::Call(func, _) => {
+                if let ExprKind::Path(qpath) = &func.kind
+                    && let Res::Def(_, def_id) = self.typeck_results.qpath_res(qpath, func.hir_id)
+                {
+                    self.callees.insert(def_id);
+                }
+            }
+            ExprKind::MethodCall(..) => {
+                if let Some(def_id) = self.typeck_results.type_dependent_def_id(expr.hir_id) {
+                    self.callees.insert(def_id);
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, expr);
+    }
+}
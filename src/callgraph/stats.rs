@@ -0,0 +1,218 @@
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{TyCtxt, TypeVisitableExt};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use super::function::{FunctionInstance, collect_local_instances};
+use super::types::CallGraph;
+
+/// Local functions that are reachable from outside the crate
+///
+/// These are the public functions of the crate plus the binary entry point (`main`), if any.
+pub(crate) fn entry_points(tcx: TyCtxt<'_>) -> Vec<FunctionInstance<'_>> {
+    let entry_fn = tcx.entry_fn(()).map(|(def_id, _)| def_id);
+    collect_local_instances(tcx)
+        .into_iter()
+        .filter(|func| tcx.visibility(func.def_id()).is_public() || Some(func.def_id()) == entry_fn)
+        .collect()
+}
+
+/// Number of distinct monomorphizations of each generic function in the graph
+///
+/// Instances are grouped by their base `DefId`. Instances whose args still contain
+/// generic parameters (e.g. generic seeds) are not counted.
+pub(crate) fn compute_instantiation_counts(call_graph: &CallGraph<'_>) -> HashMap<DefId, usize> {
+    let mut instantiations: HashMap<DefId, HashSet<FunctionInstance<'_>>> = HashMap::new();
+    for call_site in &call_graph.call_sites {
+        for func in [call_site.caller(), call_site.callee()] {
+            if let Some(instance) = func.instance()
+                && !instance.args.is_empty()
+                && !instance.args.has_param()
+            {
+                instantiations.entry(func.def_id()).or_default().insert(func);
+            }
+        }
+    }
+
+    instantiations
+        .into_iter()
+        .map(|(def_id, instances)| (def_id, instances.len()))
+        .collect()
+}
+
+/// Number of call sites from one crate into another, keyed by `(caller_crate, callee_crate)`
+///
+/// Calls within a crate are not counted, so crates only calling themselves do not appear.
+pub(crate) fn build_crate_graph<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    options: &crate::args::CGArgs,
+) -> HashMap<(String, String), usize> {
+    let mut crate_graph = HashMap::new();
+    for call_site in call_graph.output_call_sites(tcx, options) {
+        let caller_crate = call_site.caller().def_id().krate;
+        let callee_crate = call_site.callee().def_id().krate;
+        if caller_crate != callee_crate {
+            let key = (
+                tcx.crate_name(caller_crate).to_string(),
+                tcx.crate_name(callee_crate).to_string(),
+            );
+            *crate_graph.entry(key).or_default() += 1;
+        }
+    }
+    crate_graph
+}
+
+/// Maximum call depth from any of `roots`, and the functions along the deepest chain
+///
+/// Each function on the chain is one stack frame, so the chain `main -> a -> b` has
+/// depth 3. Calls back into a function already on the current DFS path (recursion) are
+/// skipped, so cycles contribute each of their functions once. Ties are broken by the
+/// function's debug representation to keep the reported chain stable.
+pub(crate) fn compute_max_call_depth<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    roots: &[FunctionInstance<'tcx>],
+) -> (usize, Vec<FunctionInstance<'tcx>>) {
+    let mut callees_of: HashMap<FunctionInstance<'tcx>, Vec<FunctionInstance<'tcx>>> = HashMap::new();
+    for call_site in &call_graph.call_sites {
+        callees_of
+            .entry(call_site.caller())
+            .or_default()
+            .push(call_site.callee());
+    }
+    for callees in callees_of.values_mut() {
+        callees.sort_by_cached_key(|callee| format!("{callee:?}"));
+        callees.dedup();
+    }
+    let callees = |func: FunctionInstance<'tcx>| callees_of.get(&func).map(Vec::as_slice).unwrap_or_default();
+
+    // Depth of the deepest chain starting at each finished function, and the next function on it
+    let mut deepest: HashMap<FunctionInstance<'tcx>, (usize, Option<FunctionInstance<'tcx>>)> = HashMap::new();
+    let mut on_stack: HashSet<FunctionInstance<'tcx>> = HashSet::new();
+    for &root in roots {
+        if deepest.contains_key(&root) {
+            continue;
+        }
+        // Iterative DFS, each frame holds the index of the next callee to visit
+        let mut stack: Vec<(FunctionInstance<'tcx>, usize)> = vec![(root, 0)];
+        on_stack.insert(root);
+        while let Some((func, next)) = stack.last_mut() {
+            if let Some(&callee) = callees(*func).get(*next) {
+                *next += 1;
+                if !deepest.contains_key(&callee) && on_stack.insert(callee) {
+                    stack.push((callee, 0));
+                }
+                continue;
+            }
+            let func = *func;
+            stack.pop();
+            on_stack.remove(&func);
+            let best = callees(func)
+                .iter()
+                .filter_map(|callee| deepest.get(callee).map(|(depth, _)| (*depth, *callee)))
+                .reduce(|best, candidate| if candidate.0 > best.0 { candidate } else { best });
+            deepest.insert(
+                func,
+                match best {
+                    Some((depth, callee)) => (depth + 1, Some(callee)),
+                    None => (1, None),
+                },
+            );
+        }
+    }
+
+    let mut start = None;
+    for root in roots {
+        if let Some(&(depth, _)) = deepest.get(root)
+            && start.is_none_or(|(best, _)| depth > best)
+        {
+            start = Some((depth, *root));
+        }
+    }
+    let mut chain = Vec::new();
+    let mut current = start.map(|(_, root)| root);
+    while let Some(func) = current {
+        chain.push(func);
+        current = deepest.get(&func).and_then(|(_, next)| *next);
+    }
+    (chain.len(), chain)
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    /// `(path, instantiation count)` of generic functions, most instantiated first
+    pub(crate) fn sorted_instantiation_counts(&self, tcx: TyCtxt<'tcx>) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = compute_instantiation_counts(self)
+            .into_iter()
+            .map(|(def_id, count)| (tcx.def_path_str(def_id), count))
+            .collect();
+        counts.sort_by(|(a_path, a_count), (b_path, b_count)| b_count.cmp(a_count).then_with(|| a_path.cmp(b_path)));
+        counts
+    }
+
+    /// Shortest number of call edges from any entry point to each reachable function
+    ///
+    /// Entry points have distance 0. Functions not reachable from any entry point are absent.
+    pub(crate) fn compute_entry_distances(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        entry_points: &[FunctionInstance<'tcx>],
+    ) -> HashMap<FunctionInstance<'tcx>, usize> {
+        let mut callees_of: HashMap<FunctionInstance<'tcx>, Vec<FunctionInstance<'tcx>>> = HashMap::new();
+        for call_site in &self.call_sites {
+            callees_of
+                .entry(call_site.caller())
+                .or_default()
+                .push(call_site.callee());
+        }
+
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        for &entry in entry_points {
+            if distances.insert(entry, 0).is_none() {
+                queue.push_back(entry);
+            }
+        }
+
+        while let Some(func) = queue.pop_front() {
+            let distance = distances[&func];
+            for &callee in callees_of.get(&func).into_iter().flatten() {
+                if let Entry::Vacant(entry) = distances.entry(callee) {
+                    entry.insert(distance + 1);
+                    queue.push_back(callee);
+                }
+            }
+        }
+
+        if let Some((func, distance)) = distances.iter().max_by_key(|(_, distance)| **distance) {
+            tracing::debug!(
+                "Farthest function from entry points: {} ({} hops)",
+                self.function_path(tcx, *func),
+                distance
+            );
+        }
+
+        distances
+    }
+
+    /// Distance of `func` from the nearest entry point, `None` unless `--entry-distances` is set
+    /// or `func` is unreachable from every entry point
+    pub(crate) fn entry_distance(&self, func: FunctionInstance<'tcx>) -> Option<usize> {
+        self.entry_distances.as_ref()?.get(&func).copied()
+    }
+
+    /// Minimum call depth of `func` from the `--entry-point` function, `None` unless it is set
+    /// or `func` is unreachable from it
+    pub(crate) fn depth_from_entry(&self, func: FunctionInstance<'tcx>) -> Option<usize> {
+        self.entry_depths.as_ref()?.get(&func).copied()
+    }
+
+    /// Number of functions at each entry distance
+    pub(crate) fn distance_histogram(&self) -> Option<BTreeMap<usize, usize>> {
+        let distances = self.entry_distances.as_ref()?;
+        let mut histogram = BTreeMap::new();
+        for &distance in distances.values() {
+            *histogram.entry(distance).or_default() += 1;
+        }
+        Some(histogram)
+    }
+}
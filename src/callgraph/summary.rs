@@ -5,6 +5,8 @@ use rustc_middle::{
 };
 use std::collections::{HashSet, VecDeque};
 
+use super::utils::optimized_mir_if_available;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum ReturnSummary<'tcx> {
     Param(usize),
@@ -25,11 +27,9 @@ pub(crate) fn summarize_callable_return<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId)
         return summary;
     }
 
-    if !tcx.is_mir_available(def_id) {
+    let Some(body) = optimized_mir_if_available(tcx, def_id) else {
         return ReturnSummary::Unknown;
-    }
-
-    let body = tcx.optimized_mir(def_id);
+    };
     let predecessors = body.basic_blocks.predecessors();
     let mut summaries = HashSet::new();
 
@@ -37,6 +37,10 @@ pub(crate) fn summarize_callable_return<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId)
         let Some(terminator) = body.basic_blocks[bb].terminator.as_ref() else {
             continue;
         };
+        // A tail call returns whatever the tail-called function returns
+        if matches!(terminator.kind, mir::TerminatorKind::TailCall { .. }) {
+            return ReturnSummary::Unknown;
+        }
         if !matches!(terminator.kind, mir::TerminatorKind::Return) {
             continue;
         }
@@ -0,0 +1,119 @@
+//! Listing of all impls of a trait, for `cargo cg4rs impls <trait_path>`
+
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::{self, TyCtxt};
+use serde_json::json;
+use std::io::Write;
+
+use super::fmt::write_output;
+use super::path_utils::sanitize_path_for_filename;
+use super::types::OutputFormat;
+use super::utils::matches_def_path;
+
+/// One impl block of a trait and the methods it defines
+struct TraitImpl {
+    trait_path: String,
+    self_ty: String,
+    krate: String,
+    methods: Vec<String>,
+}
+
+/// Collect the impls of every trait whose path matches `trait_path`
+///
+/// Traits are searched in the local crate and all its dependencies. Only methods
+/// defined in the impl block are listed, not inherited default methods.
+fn collect_trait_impls(tcx: TyCtxt<'_>, trait_path: &str) -> Vec<TraitImpl> {
+    let traits: Vec<DefId> = std::iter::once(LOCAL_CRATE)
+        .chain(tcx.crates(()).iter().copied())
+        .flat_map(|krate| tcx.traits(krate).iter().copied())
+        .filter(|&trait_def_id| matches_def_path(tcx, trait_def_id, trait_path))
+        .collect();
+
+    let mut impls = Vec::new();
+    for trait_def_id in traits {
+        for impl_def_id in tcx.all_impls(trait_def_id) {
+            let mut methods: Vec<String> = tcx
+                .associated_item_def_ids(impl_def_id)
+                .iter()
+                .filter(|&&item_def_id| matches!(tcx.associated_item(item_def_id).kind, ty::AssocKind::Fn { .. }))
+                .map(|&item_def_id| tcx.def_path_str(item_def_id))
+                .collect();
+            methods.sort();
+
+            impls.push(TraitImpl {
+                trait_path: tcx.def_path_str(trait_def_id),
+                self_ty: tcx.type_of(impl_def_id).instantiate_identity().to_string(),
+                krate: tcx.crate_name(impl_def_id.krate).to_string(),
+                methods,
+            });
+        }
+    }
+
+    impls.sort_by(|a, b| (&a.trait_path, &a.self_ty).cmp(&(&b.trait_path, &b.self_ty)));
+    impls
+}
+
+fn format_trait_impls(trait_path: &str, impls: &[TraitImpl]) -> String {
+    let mut result = String::new();
+    result.push_str(&format!("Impls of trait: {} ({} found)\n", trait_path, impls.len()));
+    result.push_str("===========\n\n");
+    for imp in impls {
+        result.push_str(&format!(
+            "impl {} for {} [crate: {}]\n",
+            imp.trait_path, imp.self_ty, imp.krate
+        ));
+        for method in &imp.methods {
+            result.push_str(&format!("  - {method}\n"));
+        }
+        result.push('\n');
+    }
+    result
+}
+
+fn format_trait_impls_as_json(trait_path: &str, impls: &[TraitImpl]) -> String {
+    let impls = impls
+        .iter()
+        .map(|imp| {
+            json!({
+                "trait": imp.trait_path,
+                "self_ty": imp.self_ty,
+                "crate": imp.krate,
+                "methods": imp.methods
+            })
+        })
+        .collect::<Vec<_>>();
+    let result = json!({
+        "target": trait_path,
+        "total_impls": impls.len(),
+        "impls": impls
+    });
+    serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Write the impls of `trait_path` to `<crate>-impls-<trait_path>.{txt,json}`, with the name sanitized
+pub(crate) fn output_trait_impls(tcx: TyCtxt<'_>, trait_path: &str, options: &crate::args::CGArgs) {
+    let impls = crate::timer::measure("collect_trait_impls", || collect_trait_impls(tcx, trait_path));
+    tracing::info!("Found {} impls of trait {}", impls.len(), trait_path);
+
+    let file_stem = sanitize_path_for_filename(&format!("{}-impls-{trait_path}", tcx.crate_name(LOCAL_CRATE)));
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+    let (output_path, output) = if options.effective_output_formats().contains(&OutputFormat::Json) {
+        (
+            output_dir.join(options.output_file_name(&format!("{file_stem}.json"))),
+            format_trait_impls_as_json(trait_path, &impls),
+        )
+    } else {
+        (
+            output_dir.join(options.output_file_name(&format!("{file_stem}.txt"))),
+            format_trait_impls(trait_path, &impls),
+        )
+    };
+
+    match write_output(&output_path, options.compress, |file| write!(file, "{output}")) {
+        Ok(path) => tracing::info!("Trait impls written to {}", path.display()),
+        Err(e) => tracing::error!("Failed to write trait impls: {}", e),
+    }
+}
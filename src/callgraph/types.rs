@@ -1,5 +1,11 @@
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
 
+use rustc_hir::def_id::DefId;
+
+use super::controlflow::ConstraintKindCounts;
 use super::function::FunctionInstance;
 
 pub(crate) struct CallGraph<'tcx> {
@@ -7,8 +13,52 @@ pub(crate) struct CallGraph<'tcx> {
     pub(crate) call_sites: Vec<CallSite<'tcx>>,
     pub(crate) without_args: bool,
     pub(crate) total_functions: usize,
+    pub(crate) mono_stats: MonoStats,
+    /// Functions skipped because their MIR exceeded `--max-mir-size`, with their basic block count
+    pub(crate) skipped_large_functions: Vec<(FunctionInstance<'tcx>, usize)>,
+    /// Functions of local impls of foreign traits or types left out, `None` unless `--no-extern-impls` is set
+    pub(crate) skipped_extern_impls: Option<usize>,
+    /// Functions from macro expansions or build script outputs left out, `None` unless `--exclude-generated` is set
+    pub(crate) excluded_generated: Option<usize>,
+    /// Functions of crates deeper than `--deps-depth` left out, `None` unless the depths are known
+    pub(crate) pruned_by_deps_depth: Option<usize>,
+    /// Whether analysis stopped early because `--max-edges` or `--max-nodes` was reached
+    pub(crate) truncated: bool,
+    /// Recoverable errors of the analysis, reported as `analysis_errors`, see `--strict-errors`
+    pub(crate) errors: Vec<AnalysisError<'tcx>>,
+    /// Whether call sites were restricted to the crate versions of `--crate-version-filter`
+    pub(crate) version_filter_applied: bool,
+    /// Whether the analyzed crate is a build script (`build.rs`)
+    pub(crate) is_build_script: bool,
+    /// Whether the analyzed crate is a proc-macro crate
+    pub(crate) is_proc_macro: bool,
+    /// Target triple passed with `--target`, `None` when compiling for the host
+    pub(crate) compile_target: Option<String>,
+    /// Features enabled in the current `--feature-combo` run, `None` outside feature-combination runs
+    pub(crate) feature_combo: Option<Vec<String>>,
+    /// Lazily computed `may_panic` flags per callee, `None` unless `--annotate-panics` is set
+    pub(crate) panic_cache: Option<RefCell<HashMap<FunctionInstance<'tcx>, bool>>>,
+    /// Lazily rendered display paths per function, with `without_args` as set at construction
+    pub(crate) path_cache: RefCell<HashMap<FunctionInstance<'tcx>, Rc<str>>>,
+    /// Shortest hop distance from an entry point per function, `None` unless `--entry-distances` is set
+    pub(crate) entry_distances: Option<HashMap<FunctionInstance<'tcx>, usize>>,
+    /// Minimum call depth from the `--entry-point` function, `None` unless it is set
+    pub(crate) entry_depths: Option<HashMap<FunctionInstance<'tcx>, usize>>,
+    /// Deepest call chain from the entry points, `None` unless `--max-depth-stat` is set
+    pub(crate) deepest_call_chain: Option<Vec<FunctionInstance<'tcx>>>,
+    /// Reverse adjacency used by `--find-callers`, built once after analysis
+    pub(crate) callee_to_callers: OnceCell<CallEdges<'tcx>>,
+    /// Forward adjacency used by `--reachable-from`, built once after analysis
+    pub(crate) caller_to_callees: OnceCell<CallEdges<'tcx>>,
 }
 
+/// Function -> neighbor -> (constraints, package_num, call_kind, generic_args_len, constraint_kinds)
+/// of the least constrained call site between them, neighbors are callers or callees
+pub(crate) type CallEdges<'tcx> = HashMap<
+    FunctionInstance<'tcx>,
+    HashMap<FunctionInstance<'tcx>, (usize, usize, CallKind, usize, ConstraintKindCounts)>,
+>;
+
 impl<'tcx> CallGraph<'tcx> {
     pub(crate) fn new(all_generic_instances: Vec<FunctionInstance<'tcx>>, without_args: bool) -> Self {
         Self {
@@ -16,19 +66,241 @@ impl<'tcx> CallGraph<'tcx> {
             call_sites: Vec::new(),
             without_args,
             total_functions: 0,
+            mono_stats: MonoStats::default(),
+            skipped_large_functions: Vec::new(),
+            truncated: false,
+            errors: Vec::new(),
+            skipped_extern_impls: None,
+            excluded_generated: None,
+            pruned_by_deps_depth: None,
+            version_filter_applied: false,
+            is_build_script: false,
+            is_proc_macro: false,
+            compile_target: None,
+            feature_combo: None,
+            panic_cache: None,
+            path_cache: RefCell::default(),
+            entry_distances: None,
+            entry_depths: None,
+            deepest_call_chain: None,
+            callee_to_callers: OnceCell::new(),
+            caller_to_callees: OnceCell::new(),
         }
     }
 }
 
+/// A recoverable error of the analysis; the affected calls or functions are missing from the graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AnalysisError<'tcx> {
+    /// The type of a callee in `func` could not be normalized for the generic args of `func`
+    MonomorphizationFailed {
+        func: FunctionInstance<'tcx>,
+        error: String,
+    },
+    /// `Instance::try_resolve` failed for the call of `callee` in `func`
+    InstanceResolutionFailed {
+        func: FunctionInstance<'tcx>,
+        callee: DefId,
+    },
+    /// `optimized_mir` of the function panicked, so its calls were not collected
+    MirUnavailable { def_id: DefId },
+    /// The driver timeout was close before the calls of `func` were collected
+    TimeoutExceeded { func: FunctionInstance<'tcx> },
+}
+
+impl AnalysisError<'_> {
+    /// Name of the variant, as used in the `kind` field of `analysis_errors`
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::MonomorphizationFailed { .. } => "monomorphization_failed",
+            Self::InstanceResolutionFailed { .. } => "instance_resolution_failed",
+            Self::MirUnavailable { .. } => "mir_unavailable",
+            Self::TimeoutExceeded { .. } => "timeout_exceeded",
+        }
+    }
+}
+
+/// Counters describing how callee monomorphization and resolution went
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct MonoStats {
+    /// Callee types that were monomorphized successfully
+    pub(crate) ok: usize,
+    /// Callee types that failed to normalize in the caller's context
+    pub(crate) normalization_failures: usize,
+    /// `Instance::try_resolve` returned an error
+    pub(crate) resolve_failures: usize,
+    /// Instances that could only be resolved via `trivial_resolve`
+    pub(crate) trivial_resolve_fallbacks: usize,
+    /// Callees that ended up as non-instances or were dropped
+    pub(crate) unresolved: usize,
+    /// Fnptr and dyn calls resolved to signature-matched or trait impl candidates
+    ///
+    /// Counted while expanding, before deduplication, so a call in a function analyzed as
+    /// several instances counts once per instance.
+    pub(crate) candidate_call_sites: usize,
+}
+
+impl MonoStats {
+    pub(crate) fn merge(&mut self, other: &MonoStats) {
+        self.ok += other.ok;
+        self.normalization_failures += other.normalization_failures;
+        self.resolve_failures += other.resolve_failures;
+        self.trivial_resolve_fallbacks += other.trivial_resolve_fallbacks;
+        self.unresolved += other.unresolved;
+        self.candidate_call_sites += other.candidate_call_sites;
+    }
+
+    pub(crate) fn failures(&self) -> usize {
+        self.normalization_failures + self.resolve_failures + self.unresolved
+    }
+}
+
+impl std::fmt::Display for MonoStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Monomorphization: {} ok, {} normalization failures, {} resolve failures, {} trivial-resolve fallbacks, {} unresolved",
+            self.ok,
+            self.normalization_failures,
+            self.resolve_failures,
+            self.trivial_resolve_fallbacks,
+            self.unresolved
+        )
+    }
+}
+
+/// How duplicate call sites are collapsed before output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DeduplicationStrategy {
+    /// Keep the call site with the fewest constraints per (caller, callee) pair
+    #[default]
+    #[value(name = "constraint")]
+    ByConstraint,
+    /// Keep the call site with the fewest constraints per (caller, source line)
+    #[value(name = "location")]
+    ByLocation,
+}
+
+/// Order of the calls listed under each caller, selected with `--sort`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CallSortOrder {
+    /// Alphabetically by callee, then by constraint count
+    #[default]
+    Name,
+    /// By the MIR basic block of the call, roughly the order in which the calls execute
+    BbOrder,
+}
+
+/// Order of the callers in `--find-callers` reports, selected with `--sort-callers-by`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CallerSortOrder {
+    /// Alphabetically by caller, then by path constraints
+    #[default]
+    Name,
+    /// Least constrained paths first
+    Constraints,
+    /// Nearest callers first, by hop count from the target
+    Distance,
+}
+
+/// Output format of the call graph, selected with `--format` (repeatable)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Readable text, `<crate>-callgraph.txt`
+    Text,
+    /// JSON, `callgraph.json` plus `<crate>-callgraph-stats.json`
+    Json,
+    /// Graphviz, `<crate>-callgraph.dot`
+    Dot,
+    /// One row per call site, `<crate>-callgraph.csv`
+    Csv,
+    /// Mermaid flowchart, `<crate>-callgraph.mmd`
+    Mermaid,
+    /// Cytoscape.js elements JSON, `<crate>-callgraph.cytoscape.json`
+    Cytoscape,
+}
+
+/// Which MIR of a function call sites are collected from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MirLevel {
+    /// MIR before optimizations: keeps every source-level call, but generics are less resolved
+    Built,
+    /// `optimized_mir`: resolves more calls, but calls removed or merged by MIR inlining are lost
+    #[default]
+    Optimized,
+}
+
+/// Source location of a call site
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallLocation {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl std::fmt::Display for CallLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
+    }
+}
+
+/// How confidently the callee of a call site was resolved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResolutionSource {
+    /// Resolved to a concrete instance, or traced back to the actual function
+    #[default]
+    Exact,
+    /// One of several candidates matched by signature or trait impls (fnptr and dyn calls)
+    FnptrCandidate,
+    /// Only resolved via `trivial_resolve`
+    Trivial,
+    /// Fell back to a non-instance function
+    Unresolved,
+}
+
+impl ResolutionSource {
+    /// Name used in JSON output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::FnptrCandidate => "fnptr_candidate",
+            Self::Trivial => "trivial",
+            Self::Unresolved => "unresolved",
+        }
+    }
+
+    pub(crate) const ALL: [Self; 4] = [Self::Exact, Self::FnptrCandidate, Self::Trivial, Self::Unresolved];
+}
+
 /// Represents a call site in the code
 #[derive(Debug, Clone)]
 pub struct CallSite<'tcx> {
     caller: FunctionInstance<'tcx>,
     callee: FunctionInstance<'tcx>,
     constraint_cnt: usize,
+    loop_depth: usize,
     call_kind: CallKind,
+    /// Only populated with `--with-locations`
+    call_location: Option<CallLocation>,
+    /// Kinds of the constraints guarding this call site within the caller
+    constraint_kinds: ConstraintKindCounts,
+    resolution: ResolutionSource,
+    /// Calling convention of the called function type, e.g. `Rust` or `C`
+    abi: &'static str,
+    /// Number of arguments passed, `None` for drops
+    arg_count: Option<usize>,
+    /// Index of the MIR basic block of the call in the caller's body
+    basic_block: Option<usize>,
 }
 
+/// ABIs that use Rust's own calling convention, see [`CallSite::is_foreign_abi`]
+const RUST_ABIS: &[&str] = &["Rust", "rust-call", "rust-cold", "rust-intrinsic"];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CallKind {
     Direct,
@@ -39,12 +311,24 @@ pub enum CallKind {
 
 impl<'tcx> CallSite<'tcx> {
     /// Create a new CallSite
-    pub fn new(caller: FunctionInstance<'tcx>, callee: FunctionInstance<'tcx>, constraint_count: usize) -> Self {
+    pub fn new(
+        caller: FunctionInstance<'tcx>,
+        callee: FunctionInstance<'tcx>,
+        constraint_count: usize,
+        loop_depth: usize,
+    ) -> Self {
         Self {
             caller,
             callee,
             constraint_cnt: constraint_count,
+            loop_depth,
             call_kind: CallKind::Direct,
+            call_location: None,
+            constraint_kinds: ConstraintKindCounts::default(),
+            resolution: ResolutionSource::Exact,
+            abi: "Rust",
+            arg_count: None,
+            basic_block: None,
         }
     }
 
@@ -53,13 +337,53 @@ impl<'tcx> CallSite<'tcx> {
         caller: FunctionInstance<'tcx>,
         callee: FunctionInstance<'tcx>,
         constraint_count: usize,
+        loop_depth: usize,
         call_kind: CallKind,
     ) -> Self {
         Self {
             caller,
             callee,
             constraint_cnt: constraint_count,
+            loop_depth,
             call_kind,
+            call_location: None,
+            constraint_kinds: ConstraintKindCounts::default(),
+            resolution: ResolutionSource::Exact,
+            abi: "Rust",
+            arg_count: None,
+            basic_block: None,
+        }
+    }
+
+    /// Set how the callee was resolved
+    pub(crate) fn with_resolution(self, resolution: ResolutionSource) -> Self {
+        Self { resolution, ..self }
+    }
+
+    /// Canonicalize both the caller and the callee, see [`FunctionInstance::canonicalize`]
+    pub(crate) fn canonicalize(self, tcx: rustc_middle::ty::TyCtxt<'tcx>) -> Self {
+        Self {
+            caller: self.caller.canonicalize(tcx),
+            callee: self.callee.canonicalize(tcx),
+            ..self
+        }
+    }
+
+    /// The direct call `A -> C` replacing this call `A -> B` and the call `next` of `B -> C`
+    ///
+    /// Keeps the caller-side location of `self` and the callee-side attributes of `next`;
+    /// constraints and loop depths of both calls add up. See `--merge-inline`.
+    pub(crate) fn merged_through(&self, next: &CallSite<'tcx>) -> Self {
+        Self {
+            callee: next.callee,
+            constraint_cnt: self.constraint_cnt + next.constraint_cnt,
+            loop_depth: self.loop_depth + next.loop_depth,
+            call_kind: next.call_kind,
+            constraint_kinds: self.constraint_kinds.sum(next.constraint_kinds),
+            resolution: next.resolution,
+            abi: next.abi,
+            arg_count: next.arg_count,
+            ..self.clone()
         }
     }
 
@@ -78,6 +402,11 @@ impl<'tcx> CallSite<'tcx> {
         self.constraint_cnt
     }
 
+    /// Get the loop nesting depth of this call site, a heuristic for call frequency
+    pub fn loop_depth(&self) -> usize {
+        self.loop_depth
+    }
+
     pub fn package_num(&self) -> usize {
         if self.caller.def_id().krate == self.callee.def_id().krate {
             0
@@ -89,6 +418,64 @@ impl<'tcx> CallSite<'tcx> {
     pub fn call_kind(&self) -> CallKind {
         self.call_kind
     }
+
+    /// Get the source location of this call site, if recorded
+    pub fn call_location(&self) -> Option<&CallLocation> {
+        self.call_location.as_ref()
+    }
+
+    pub(crate) fn set_call_location(&mut self, location: CallLocation) {
+        self.call_location = Some(location);
+    }
+
+    /// Get the kinds of the constraints guarding this call site
+    pub fn constraint_kinds(&self) -> ConstraintKindCounts {
+        self.constraint_kinds
+    }
+
+    pub(crate) fn set_constraint_kinds(&mut self, constraint_kinds: ConstraintKindCounts) {
+        self.constraint_kinds = constraint_kinds;
+    }
+
+    /// Get how confidently the callee was resolved
+    pub fn resolution(&self) -> ResolutionSource {
+        self.resolution
+    }
+
+    /// Get the ABI of the called function type
+    pub fn abi(&self) -> &'static str {
+        self.abi
+    }
+
+    pub(crate) fn set_abi(&mut self, abi: &'static str) {
+        self.abi = abi;
+    }
+
+    /// Get the number of arguments passed at this call site
+    ///
+    /// This is the count visible in MIR: variadic calls count every passed argument and
+    /// closure calls through `Fn*` traits count the closure and the argument tuple.
+    pub fn arg_count(&self) -> Option<usize> {
+        self.arg_count
+    }
+
+    pub(crate) fn set_arg_count(&mut self, arg_count: usize) {
+        self.arg_count = Some(arg_count);
+    }
+
+    /// Index of the MIR basic block of the call in the caller's body
+    pub fn basic_block(&self) -> Option<usize> {
+        self.basic_block
+    }
+
+    pub(crate) fn set_basic_block(&mut self, basic_block: usize) {
+        self.basic_block = Some(basic_block);
+    }
+
+    /// Whether the call crosses into a non-Rust calling convention, e.g. an `extern "C"` function
+    pub fn is_foreign_abi(&self) -> bool {
+        !RUST_ABIS.contains(&self.abi)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -96,9 +483,12 @@ pub(crate) struct PathInfo<'tcx> {
     pub(crate) caller: FunctionInstance<'tcx>,
     pub(crate) call_path: Vec<FunctionInstance<'tcx>>,
     pub(crate) constraints: usize,
+    pub(crate) constraint_kinds: ConstraintKindCounts,
     pub(crate) package_num: usize,
     pub(crate) package_num_unique: usize,
     pub(crate) path_len: usize,
+    /// Fewest calls between the caller and a target, `path_len` counts the least constrained path
+    pub(crate) hop_count: usize,
     pub(crate) dyn_edges: usize,
     pub(crate) fnptr_edges: usize,
     pub(crate) generic_args_len_sum: usize,
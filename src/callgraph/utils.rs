@@ -1,11 +1,269 @@
-use rustc_hir::def_id::{CrateNum, DefId};
-use rustc_middle::ty::TyCtxt;
+use lazy_static::lazy_static;
+use rustc_hir::{
+    def::DefKind,
+    def_id::{CrateNum, DefId},
+};
+use rustc_middle::{mir, ty::TyCtxt};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::Mutex;
 
-use crate::callgraph::{function::FunctionInstance, types::PathInfo};
+use crate::callgraph::{controlflow::ConstraintKindCounts, function::FunctionInstance, types::PathInfo};
 
-use super::types::CallGraph;
+use super::stats;
+use super::types::{CallEdges, CallGraph, CallSite, DeduplicationStrategy};
+
+/// Functions `--all-paths-to` may visit before giving up, bounding the exponential search
+const MAX_PATH_SEARCH_STEPS: usize = 1_000_000;
+
+/// Which paths a [`PathSearch`] records
+enum PathEnd<'a, 'tcx> {
+    /// Paths reaching one of these functions, searched backwards from a target for `--all-paths-to`
+    EntryPoints(&'a HashSet<FunctionInstance<'tcx>>),
+    /// Paths that cannot be extended, searched forwards from a root for `--folded-output`
+    Leaves,
+}
+
+/// State of the depth-first search of `--all-paths-to` and `--folded-output`
+struct PathSearch<'a, 'tcx> {
+    edges: &'a CallEdges<'tcx>,
+    end: PathEnd<'a, 'tcx>,
+    max_paths: usize,
+    max_path_len: usize,
+    steps_left: usize,
+    /// Current path, in search order
+    path: Vec<FunctionInstance<'tcx>>,
+    /// Recorded paths, always in call order
+    paths: Vec<Vec<FunctionInstance<'tcx>>>,
+    truncated: bool,
+}
+
+impl<'a, 'tcx> PathSearch<'a, 'tcx> {
+    fn new(edges: &'a CallEdges<'tcx>, end: PathEnd<'a, 'tcx>, max_paths: usize, max_path_len: usize) -> Self {
+        PathSearch {
+            edges,
+            end,
+            max_paths,
+            max_path_len,
+            steps_left: MAX_PATH_SEARCH_STEPS,
+            path: Vec::new(),
+            paths: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    fn visit(&mut self, func: FunctionInstance<'tcx>) {
+        if self.truncated {
+            return;
+        }
+        if self.steps_left == 0 {
+            tracing::warn!("Call path search budget exhausted, paths are incomplete");
+            self.truncated = true;
+            return;
+        }
+        self.steps_left -= 1;
+
+        self.path.push(func);
+        let mut next: Vec<FunctionInstance<'tcx>> = if self.path.len() <= self.max_path_len {
+            self.edges
+                .get(&func)
+                .into_iter()
+                .flat_map(|neighbors| neighbors.keys().copied())
+                .filter(|neighbor| !self.path.contains(neighbor))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        next.sort_by_key(|neighbor| format!("{neighbor:?}"));
+
+        let ends_here = match self.end {
+            PathEnd::EntryPoints(entry_points) => entry_points.contains(&func),
+            PathEnd::Leaves => next.is_empty(),
+        };
+        if ends_here {
+            if self.paths.len() == self.max_paths {
+                self.truncated = true;
+                self.path.pop();
+                return;
+            }
+            self.paths.push(match self.end {
+                PathEnd::EntryPoints(_) => self.path.iter().rev().copied().collect(),
+                PathEnd::Leaves => self.path.clone(),
+            });
+        }
+
+        // Paths keep going through entry points, e.g. `main` calling a public function
+        for neighbor in next {
+            self.visit(neighbor);
+        }
+        self.path.pop();
+    }
+}
+
+/// Direction in which call edges are followed, see [`CallGraph::shortest_paths`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeDirection {
+    /// From callees to their callers, for `--find-callers`
+    Callers,
+    /// From callers to their callees, for `--reachable-from`
+    Callees,
+}
+
+/// Best path metrics to a function: (constraints, package_num, package_num_unique, path_len,
+/// dyn_edges, fnptr_edges, generic_args_len_sum, constraint_kinds)
+type PathDist = (usize, usize, usize, usize, usize, usize, usize, ConstraintKindCounts);
+
+/// A Dijkstra frontier entry of [`CallGraph::shortest_paths`]
+#[derive(Clone)]
+struct PathState<'tcx> {
+    cost: usize,
+    node: FunctionInstance<'tcx>,
+    package_sum: usize,
+    package_unique: HashSet<CrateNum>,
+    depth: usize,
+    dyn_edges: usize,
+    fnptr_edges: usize,
+    generic_args_len_sum: usize,
+    constraint_kinds: ConstraintKindCounts,
+}
+
+impl<'tcx> Eq for PathState<'tcx> {}
+
+impl<'tcx> PartialEq for PathState<'tcx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl<'tcx> Ord for PathState<'tcx> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is max-heap, reverse comparison for min-heap behavior
+        other.cost.cmp(&self.cost)
+    }
+}
+
+impl<'tcx> PartialOrd for PathState<'tcx> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+lazy_static! {
+    /// Functions whose `optimized_mir` panicked, with the panic message
+    static ref MIR_FAILURES: Mutex<HashMap<DefId, String>> = Mutex::new(HashMap::new());
+}
+
+/// Get the optimized MIR of a function, or `None` if it cannot be queried safely
+///
+/// `is_mir_available` alone is not enough: it also holds for const-eval-only bodies
+/// (consts, statics, anonymous constants), and `optimized_mir` ICEs on those as well
+/// as on foreign items. All `optimized_mir` accesses should go through this check so
+/// that a single pathological function cannot abort the whole analysis. Panics of
+/// `optimized_mir` on other exotic defs are caught, and the function is skipped from
+/// then on and listed by [`mir_failures`].
+pub(crate) fn optimized_mir_if_available<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Option<&'tcx mir::Body<'tcx>> {
+    if !tcx.is_mir_available(def_id) {
+        return None;
+    }
+
+    if tcx.is_foreign_item(def_id) {
+        tracing::warn!("Skip foreign item with MIR: {:?}", def_id);
+        return None;
+    }
+
+    match tcx.def_kind(def_id) {
+        DefKind::Fn | DefKind::AssocFn | DefKind::Closure | DefKind::SyntheticCoroutineBody | DefKind::Ctor(..) => {
+            if MIR_FAILURES.lock().unwrap().contains_key(&def_id) {
+                return None;
+            }
+            match panic::catch_unwind(AssertUnwindSafe(|| {
+                #[cfg(feature = "fault-injection")]
+                inject_mir_panic(tcx, def_id);
+                tcx.optimized_mir(def_id)
+            })) {
+                Ok(body) => Some(body),
+                Err(payload) => {
+                    let message = crate::driver::panic_message(payload.as_ref());
+                    tracing::warn!("Skip {:?}, optimized_mir panicked: {}", def_id, message);
+                    MIR_FAILURES.lock().unwrap().insert(def_id, message);
+                    None
+                }
+            }
+        }
+        other => {
+            tracing::warn!("Skip {:?} without optimized MIR: {:?}", other, def_id);
+            None
+        }
+    }
+}
+
+/// Def path of a function whose `optimized_mir` access panics with the `fault-injection` feature
+///
+/// Lets the regression tests exercise the recovery in [`optimized_mir_if_available`].
+#[cfg(feature = "fault-injection")]
+const INJECT_MIR_PANIC_ENV: &str = "CG4RS_INJECT_MIR_PANIC";
+
+#[cfg(feature = "fault-injection")]
+fn inject_mir_panic(tcx: TyCtxt<'_>, def_id: DefId) {
+    static TARGET: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    if let Some(target) = TARGET.get_or_init(|| std::env::var(INJECT_MIR_PANIC_ENV).ok())
+        && *target == tcx.def_path_str(def_id)
+    {
+        panic!("injected by {INJECT_MIR_PANIC_ENV}");
+    }
+}
+
+/// Functions skipped because `optimized_mir` panicked, see [`mir_failures`]
+pub(crate) fn mir_failure_def_ids() -> Vec<DefId> {
+    MIR_FAILURES.lock().unwrap().keys().copied().collect()
+}
+
+/// Def paths of the functions skipped because `optimized_mir` panicked, with the panic message
+pub(crate) fn mir_failures(tcx: TyCtxt<'_>) -> Vec<(String, String)> {
+    let mut failures: Vec<(String, String)> = MIR_FAILURES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(def_id, message)| (tcx.def_path_str(*def_id), message.clone()))
+        .collect();
+    failures.sort();
+    failures
+}
+
+/// Snapshot the pre-optimization MIR of all local functions for `--mir-level built`
+///
+/// The analysis runs in `after_analysis`, and by then borrow checking has stolen `mir_built`
+/// (into `mir_promoted`) and `mir_promoted` (into `mir_drops_elaborated_and_const_checked`) for
+/// every body, so neither can be read any more. The MIR after drop elaboration is the last
+/// stage before optimizations: it has the same calls as `mir_built`, plus the explicit `Drop`
+/// terminators that the analysis handles for `optimized_mir` as well, and none of the inlining
+/// or simplification that `--mir-level built` is meant to avoid.
+///
+/// It is in turn stolen by `optimized_mir`, so the snapshot must be taken before any local
+/// `optimized_mir` access. Functions whose MIR is already stolen are left out and fall back
+/// to `optimized_mir`.
+pub(crate) fn collect_built_mir<'tcx>(tcx: TyCtxt<'tcx>) -> HashMap<DefId, &'tcx mir::Body<'tcx>> {
+    let mut bodies = HashMap::new();
+    for local_def_id in tcx.hir_body_owners() {
+        if !matches!(
+            tcx.def_kind(local_def_id),
+            DefKind::Fn | DefKind::AssocFn | DefKind::Closure | DefKind::SyntheticCoroutineBody
+        ) {
+            continue;
+        }
+
+        let steal = tcx.mir_drops_elaborated_and_const_checked(local_def_id);
+        if steal.is_stolen() {
+            tracing::warn!("Built MIR of {:?} already stolen, using optimized MIR", local_def_id);
+            continue;
+        }
+        let body: &'tcx mir::Body<'tcx> = tcx.arena.alloc(steal.borrow().clone());
+        bodies.insert(local_def_id.to_def_id(), body);
+    }
+    bodies
+}
 
 // Get version information for a specific DefId from TyCtxt
 pub(crate) fn get_crate_version<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> String {
@@ -41,17 +299,19 @@ pub(crate) fn get_crate_version<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Strin
 }
 
 /// Strip generic parameters (::<...>) from a function path
-pub fn strip_generics_from_path(path: &str) -> String {
+///
+/// Works on `char`s only, so arbitrary (including non-ASCII) input never panics.
+/// A `>` that is part of `->` (e.g. in `fn() -> T` arguments) does not close a generic section.
+pub fn strip_generic_args(path: &str) -> String {
     let mut result = String::new();
     let mut in_generic = false;
     let mut angle_bracket_count = 0;
+    let mut prev: Option<char> = None;
 
-    let chars: Vec<(usize, char)> = path.char_indices().collect();
-
-    for (i, c) in chars {
+    for (i, c) in path.char_indices() {
         if c == '<' {
             // Check for "::<" pattern
-            let is_start_generic = !in_generic && i >= 2 && &path[i - 2..i] == "::";
+            let is_start_generic = !in_generic && path[..i].ends_with("::");
 
             if is_start_generic {
                 in_generic = true;
@@ -67,13 +327,16 @@ pub fn strip_generics_from_path(path: &str) -> String {
                 result.push(c);
             }
         } else if c == '>' && in_generic {
-            angle_bracket_count -= 1;
-            if angle_bracket_count == 0 {
-                in_generic = false;
+            if prev != Some('-') {
+                angle_bracket_count -= 1;
+                if angle_bracket_count == 0 {
+                    in_generic = false;
+                }
             }
         } else if !in_generic {
             result.push(c);
         }
+        prev = Some(c);
     }
     result
 }
@@ -92,7 +355,7 @@ fn normalize_path_for_match(path: &str, without_args: bool) -> String {
     } else {
         trimmed
     };
-    strip_generics_from_path(no_args)
+    strip_generic_args(no_args)
 }
 
 fn segment_match(candidate: &str, target: &str) -> bool {
@@ -116,12 +379,95 @@ fn segment_match(candidate: &str, target: &str) -> bool {
     false
 }
 
+/// Whether the local crate is a proc-macro crate, whose functions run inside the compiler
+pub(crate) fn is_proc_macro_crate(tcx: TyCtxt<'_>) -> bool {
+    tcx.crate_types().contains(&rustc_session::config::CrateType::ProcMacro)
+}
+
+/// Whether `def_id` is a function or method declared `unsafe fn`
+pub(crate) fn is_unsafe_fn(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        && tcx.fn_sig(def_id).skip_binder().safety().is_unsafe()
+}
+
+/// FNV-1a hash of a string, stable across toolchains and runs unlike `DefaultHasher`
+pub(crate) fn stable_hash(s: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in s.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Check if the generic-stripped base path of a function is `prefix` or lies under it, for `--only-prefix`
+///
+/// Only whole segments match, so `foo::bar` does not match `foo::barbaz`. Methods of trait impls
+/// print as `<Type as Trait>::method`, so associated functions (and the closures in them) also
+/// match on the path of the module that encloses their impl or trait.
+pub(crate) fn matches_path_prefix(tcx: TyCtxt<'_>, func: FunctionInstance<'_>, prefix: &str) -> bool {
+    let under_prefix = |path: &str| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+    };
+    if under_prefix(&strip_generic_args(&tcx.def_path_str(func.def_id()))) {
+        return true;
+    }
+    let def_id = tcx.typeck_root_def_id(func.def_id());
+    tcx.opt_associated_item(def_id).is_some() && under_prefix(&enclosing_module_path(tcx, def_id))
+}
+
+/// Path of the module that encloses `def_id`, printed like function paths
+fn enclosing_module_path(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    let mut module = tcx.parent(def_id);
+    while tcx.def_kind(module) != DefKind::Mod {
+        module = tcx.parent(module);
+    }
+    if !module.is_crate_root() {
+        tcx.def_path_str(module)
+    } else if module.is_local() {
+        String::new()
+    } else {
+        tcx.crate_name(module.krate).to_string()
+    }
+}
+
+/// Check if the generic-stripped path of any item (e.g. a trait) matches the target path
+pub(crate) fn matches_def_path(tcx: TyCtxt<'_>, def_id: DefId, target_path: &str) -> bool {
+    let clean_target = normalize_path_for_match(target_path, true);
+    !clean_target.is_empty()
+        && segment_match(
+            &normalize_path_for_match(&tcx.def_path_str(def_id), true),
+            &clean_target,
+        )
+}
+
+/// Whether `def_id` is part of the panic machinery, e.g. `core::panicking::panic_fmt`
+///
+/// Besides the printed path, the raw def path is checked, since re-exports such as
+/// `std::rt::panic_fmt` may be printed instead of the defining module.
+pub(crate) fn is_panic_source(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let path = tcx.def_path_str(def_id);
+    if path.contains("core::panicking") || path.contains("std::panicking") {
+        return true;
+    }
+    matches!(tcx.crate_name(def_id.krate).as_str(), "core" | "std")
+        && tcx
+            .def_path(def_id)
+            .to_string_no_crate_verbose()
+            .starts_with("::panicking::")
+}
+
 /// Check if a function matches the target path description
+///
+/// With `no_generic_stripping`, the target is matched as a plain substring of the
+/// full or base path, without stripping `::<...>` segments from either side.
 pub fn matches_function_path<'tcx>(
     tcx: TyCtxt<'tcx>,
     func: FunctionInstance<'tcx>,
     target_path: &str,
     without_args: bool,
+    no_generic_stripping: bool,
 ) -> bool {
     let full_func_path = func.full_path(tcx, without_args);
 
@@ -130,6 +476,28 @@ pub fn matches_function_path<'tcx>(
         FunctionInstance::NonInstance(def_id) => tcx.def_path_str(def_id),
     };
 
+    matches_path_strings(
+        &full_func_path,
+        &base_path,
+        target_path,
+        without_args,
+        no_generic_stripping,
+    )
+}
+
+/// [`matches_function_path`] on an already rendered full path and def path of a function
+pub(crate) fn matches_path_strings(
+    full_func_path: &str,
+    base_path: &str,
+    target_path: &str,
+    without_args: bool,
+    no_generic_stripping: bool,
+) -> bool {
+    if no_generic_stripping {
+        let target = target_path.trim();
+        return !target.is_empty() && (full_func_path.contains(target) || base_path.contains(target));
+    }
+
     if target_path.contains('<') && (base_path == target_path || full_func_path == target_path) {
         return true;
     }
@@ -139,8 +507,8 @@ pub fn matches_function_path<'tcx>(
         return false;
     }
 
-    let clean_base_path = normalize_path_for_match(&base_path, true);
-    let clean_full_path = normalize_path_for_match(&full_func_path, without_args);
+    let clean_base_path = normalize_path_for_match(base_path, true);
+    let clean_full_path = normalize_path_for_match(full_func_path, without_args);
 
     tracing::trace!("clean_target: {}", clean_target);
     tracing::trace!("clean_base_path: {}", clean_base_path);
@@ -154,49 +522,136 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_strip_generics_from_path() {
-        assert_eq!(
-            strip_generics_from_path("std::vec::Vec::<T>::new"),
-            "std::vec::Vec::new"
-        );
+    fn test_strip_generic_args() {
+        assert_eq!(strip_generic_args("std::vec::Vec::<T>::new"), "std::vec::Vec::new");
         assert_eq!(
-            strip_generics_from_path("my_crate::foo::<i32, f64>::bar"),
+            strip_generic_args("my_crate::foo::<i32, f64>::bar"),
             "my_crate::foo::bar"
         );
         assert_eq!(
-            strip_generics_from_path("std::option::Option::<std::string::String>::None"),
+            strip_generic_args("std::option::Option::<std::string::String>::None"),
             "std::option::Option::None"
         );
         // Nested generics
-        assert_eq!(strip_generics_from_path("my::func::<Vec::<i32>>"), "my::func");
+        assert_eq!(strip_generic_args("my::func::<Vec::<i32>>"), "my::func");
         // No generics
-        assert_eq!(strip_generics_from_path("simple::function"), "simple::function");
+        assert_eq!(strip_generic_args("simple::function"), "simple::function");
         // Edge case: :: not before <
-        assert_eq!(strip_generics_from_path("val < 5"), "val < 5");
+        assert_eq!(strip_generic_args("val < 5"), "val < 5");
         // Edge case: starts with generic? (Unlikely in Rust path but good to test)
-        assert_eq!(strip_generics_from_path("::<T>"), "");
+        assert_eq!(strip_generic_args("::<T>"), "");
+    }
+
+    #[test]
+    fn test_strip_generic_args_edge_cases() {
+        // Multi-byte characters right before "<" must not split a char boundary
+        assert_eq!(strip_generic_args("€<"), "€<");
+        assert_eq!(strip_generic_args("a€::<T>::b"), "a€::b");
+        assert_eq!(strip_generic_args("模块::函数::<类型>"), "模块::函数");
+        // Function pointer arguments contain "->"
+        assert_eq!(strip_generic_args("call::<fn(u8) -> u8>::run"), "call::run");
+        assert_eq!(strip_generic_args("map::<Box<dyn Fn() -> Vec::<u8>>>"), "map");
+        // Unbalanced brackets
+        assert_eq!(strip_generic_args("foo::<T"), "foo");
+        assert_eq!(strip_generic_args("foo>::bar"), "foo>::bar");
+        assert_eq!(strip_generic_args("foo::<T>>::bar"), "foo>::bar");
+        // Trait impl paths keep their `<Type as Trait>` prefix
+        assert_eq!(strip_generic_args("<Foo as Bar>::baz::<u8>"), "<Foo as Bar>::baz");
+        assert_eq!(strip_generic_args(""), "");
+        assert_eq!(strip_generic_args("::"), "::");
+    }
+
+    /// Feed pseudo-random unicode strings built from path-like fragments and
+    /// assert the cleaner never panics and only ever removes characters
+    #[test]
+    fn test_strip_generic_args_randomized() {
+        const FRAGMENTS: &[&str] = &[
+            ":",
+            "::",
+            "<",
+            ">",
+            "::<",
+            "->",
+            " ",
+            ",",
+            "a",
+            "Vec",
+            "€",
+            "é",
+            "模",
+            "🦀",
+            "\u{0}",
+            "\u{10FFFF}",
+        ];
+
+        // xorshift64, deterministic so failures are reproducible
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..20_000 {
+            let len = (next() % 24) as usize;
+            let mut input = String::new();
+            for _ in 0..len {
+                let r = next();
+                if r % 5 == 0 {
+                    // Arbitrary scalar value
+                    input.push(char::from_u32((r >> 8) as u32 % 0x11_0000).unwrap_or('\u{FFFD}'));
+                } else {
+                    input.push_str(FRAGMENTS[(r >> 8) as usize % FRAGMENTS.len()]);
+                }
+            }
+
+            let output = strip_generic_args(&input);
+            assert!(output.len() <= input.len(), "output grew for {input:?}");
+            if !input.contains("::<") {
+                assert_eq!(output, input, "input without generics should be unchanged");
+            }
+        }
     }
 }
 
 impl<'tcx> CallGraph<'tcx> {
     /// Deduplicate call sites, keeping only the one with the minimum constraint count
-    /// for each unique caller-callee pair
-    pub fn deduplicate_call_sites(&mut self) {
-        // Create a map to track the call site with minimum constraint_cnt for each caller-callee pair
-        let mut min_constraints: HashMap<(FunctionInstance<'tcx>, FunctionInstance<'tcx>), usize> = HashMap::new();
-        let mut min_indices: HashMap<(FunctionInstance<'tcx>, FunctionInstance<'tcx>), usize> = HashMap::new();
+    /// for each unique key of the given strategy
+    ///
+    /// - [`DeduplicationStrategy::ByConstraint`]: one call site per caller-callee pair
+    /// - [`DeduplicationStrategy::ByLocation`]: one call site per caller and source line,
+    ///   falling back to the caller-callee pair for call sites without a location
+    pub fn deduplicate_call_sites(&mut self, strategy: DeduplicationStrategy) {
+        type DedupKey<'tcx> = (
+            FunctionInstance<'tcx>,
+            Option<(String, usize)>,
+            Option<FunctionInstance<'tcx>>,
+        );
+        let key_of = |call_site: &CallSite<'tcx>| -> DedupKey<'tcx> {
+            match (strategy, call_site.call_location()) {
+                (DeduplicationStrategy::ByLocation, Some(location)) => {
+                    (call_site.caller(), Some((location.file.clone(), location.line)), None)
+                }
+                _ => (call_site.caller(), None, Some(call_site.callee())),
+            }
+        };
 
-        // Find minimum constraint count for each caller-callee pair
+        // Create a map to track the call site with minimum constraint_cnt for each key
+        let mut min_constraints: HashMap<DedupKey<'tcx>, usize> = HashMap::new();
+        let mut min_indices: HashMap<DedupKey<'tcx>, usize> = HashMap::new();
+
+        // Find minimum constraint count for each key
         for (index, call_site) in self.call_sites.iter().enumerate() {
-            let key = (call_site.caller(), call_site.callee());
+            let key = key_of(call_site);
 
             if let Some(existing_cnt) = min_constraints.get(&key) {
                 if call_site.constraint_count() < *existing_cnt {
-                    min_constraints.insert(key, call_site.constraint_count());
+                    min_constraints.insert(key.clone(), call_site.constraint_count());
                     min_indices.insert(key, index);
                 }
             } else {
-                min_constraints.insert(key, call_site.constraint_count());
+                min_constraints.insert(key.clone(), call_site.constraint_count());
                 min_indices.insert(key, index);
             }
         }
@@ -222,115 +677,129 @@ impl<'tcx> CallGraph<'tcx> {
         self.call_sites = deduplicated_call_sites.into_iter().collect();
     }
 
-    /// Find functions that match a predicate and then find all their callers
-    fn find_callers_by_predicate<F>(
-        &self,
-        tcx: TyCtxt<'tcx>,
-        target_description: &str,
-        predicate: F,
-    ) -> Vec<PathInfo<'tcx>>
-    where
-        F: Fn(FunctionInstance<'tcx>, TyCtxt<'tcx>) -> bool,
-    {
-        // First find functions that match the predicate
-        let target_functions: Vec<FunctionInstance<'tcx>> = self
-            .call_sites
-            .iter()
-            .map(|call_site| call_site.callee())
-            .filter(|&func| predicate(func, tcx))
-            .collect();
-
-        if target_functions.is_empty() {
-            tracing::warn!("No function found matching {}", target_description);
-            return Vec::new();
+    /// Whether `callee` may panic, see [`FunctionInstance::may_panic`]
+    ///
+    /// Returns `None` unless panic annotation is enabled. Results are cached per callee.
+    pub(crate) fn may_panic(&self, tcx: TyCtxt<'tcx>, callee: FunctionInstance<'tcx>) -> Option<bool> {
+        let cache = self.panic_cache.as_ref()?;
+        if let Some(may_panic) = cache.borrow().get(&callee) {
+            return Some(*may_panic);
         }
+        let may_panic = callee.may_panic(tcx);
+        cache.borrow_mut().insert(callee, may_panic);
+        Some(may_panic)
+    }
 
-        tracing::debug!("Found {} functions matching", target_functions.len());
-
-        // Create mapping from callee to callers with edge attributes
-        // (constraints, package_num, call_kind, generic_args_len)
-        let mut callee_to_callers: HashMap<
-            FunctionInstance<'tcx>,
-            HashMap<FunctionInstance<'tcx>, (usize, usize, crate::callgraph::types::CallKind, usize)>,
-        > = HashMap::new();
-
-        for call_site in &self.call_sites {
-            let caller = call_site.caller();
-            let callee = call_site.callee();
-            let constraints = call_site.constraint_count();
-            let package_num = call_site.package_num();
-
-            let call_kind = call_site.call_kind();
-            let generic_len = callee.instance().map(|inst| inst.args.len()).unwrap_or(0);
-            callee_to_callers
-                .entry(callee)
-                .or_default()
-                .entry(caller)
-                .and_modify(|(c, p, k, g)| {
-                    if constraints < *c {
-                        *c = constraints;
-                        *p = package_num;
-                        *k = call_kind;
-                        *g = generic_len;
-                    }
-                })
-                .or_insert((constraints, package_num, call_kind, generic_len));
+    /// Display path of `func`, see [`FunctionInstance::full_path`]
+    ///
+    /// Rendering a path is expensive, and outputs and caller searches ask for the same
+    /// functions many times, so each path is rendered once and cached. The cache shares
+    /// the rendered string, so looking a path up again does not allocate.
+    pub(crate) fn function_path(&self, tcx: TyCtxt<'tcx>, func: FunctionInstance<'tcx>) -> Rc<str> {
+        if let Some(path) = self.path_cache.borrow().get(&func) {
+            return Rc::clone(path);
         }
+        let path: Rc<str> = func.full_path(tcx, self.without_args).into();
+        self.path_cache.borrow_mut().insert(func, Rc::clone(&path));
+        path
+    }
 
-        // Use Dijkstra algorithm to find shortest constrained paths to all direct/indirect callers
-        #[derive(Clone)]
-        struct State<'tcx> {
-            cost: usize,
-            node: FunctionInstance<'tcx>,
-            package_sum: usize,
-            package_unique: HashSet<CrateNum>,
-            depth: usize,
-            dyn_edges: usize,
-            fnptr_edges: usize,
-            generic_args_len_sum: usize,
-        }
+    /// [`matches_function_path`] with the cached display path of `func`
+    fn matches_path(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        func: FunctionInstance<'tcx>,
+        target_path: &str,
+        no_generic_stripping: bool,
+    ) -> bool {
+        matches_path_strings(
+            &self.function_path(tcx, func),
+            &tcx.def_path_str(func.def_id()),
+            target_path,
+            self.without_args,
+            no_generic_stripping,
+        )
+    }
 
-        impl<'tcx> Eq for State<'tcx> {}
+    /// Adjacency in `direction` with the attributes of the least constrained edge
+    ///
+    /// Built on first use and shared by all `--find-callers` targets and `--reachable-from`.
+    fn call_edges(&self, direction: EdgeDirection) -> &CallEdges<'tcx> {
+        let cell = match direction {
+            EdgeDirection::Callers => &self.callee_to_callers,
+            EdgeDirection::Callees => &self.caller_to_callees,
+        };
+        cell.get_or_init(|| {
+            let mut edges: CallEdges<'tcx> = HashMap::new();
 
-        impl<'tcx> PartialEq for State<'tcx> {
-            fn eq(&self, other: &Self) -> bool {
-                self.cost == other.cost
-            }
-        }
+            for call_site in &self.call_sites {
+                let caller = call_site.caller();
+                let callee = call_site.callee();
+                let (from, to) = match direction {
+                    EdgeDirection::Callers => (callee, caller),
+                    EdgeDirection::Callees => (caller, callee),
+                };
+                let constraints = call_site.constraint_count();
+                let package_num = call_site.package_num();
 
-        impl<'tcx> Ord for State<'tcx> {
-            fn cmp(&self, other: &Self) -> Ordering {
-                // BinaryHeap is max-heap, reverse comparison for min-heap behavior
-                other.cost.cmp(&self.cost)
+                let call_kind = call_site.call_kind();
+                let generic_len = callee.instance().map(|inst| inst.args.len()).unwrap_or(0);
+                let constraint_kinds = call_site.constraint_kinds();
+                edges
+                    .entry(from)
+                    .or_default()
+                    .entry(to)
+                    .and_modify(|(c, p, k, g, ck)| {
+                        if constraints < *c {
+                            *c = constraints;
+                            *p = package_num;
+                            *k = call_kind;
+                            *g = generic_len;
+                            *ck = constraint_kinds;
+                        }
+                    })
+                    .or_insert((constraints, package_num, call_kind, generic_len, constraint_kinds));
             }
-        }
 
-        impl<'tcx> PartialOrd for State<'tcx> {
-            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-                Some(self.cmp(other))
-            }
-        }
+            edges
+        })
+    }
 
-        let mut dist: HashMap<FunctionInstance<'tcx>, (usize, usize, usize, usize, usize, usize, usize)> =
-            HashMap::new();
+    /// Least constrained paths from `sources` to every function reachable in `direction`
+    ///
+    /// Returns the best path metrics per reached function (including the sources) and the
+    /// next hop from each reached function back towards the sources.
+    fn shortest_paths(
+        &self,
+        sources: &[FunctionInstance<'tcx>],
+        direction: EdgeDirection,
+    ) -> (
+        HashMap<FunctionInstance<'tcx>, PathDist>,
+        HashMap<FunctionInstance<'tcx>, FunctionInstance<'tcx>>,
+    ) {
+        let edges = self.call_edges(direction);
+
+        // Use Dijkstra algorithm to find shortest constrained paths to all direct/indirect neighbors
+        let mut dist: HashMap<FunctionInstance<'tcx>, PathDist> = HashMap::new();
         let mut next_hop: HashMap<FunctionInstance<'tcx>, FunctionInstance<'tcx>> = HashMap::new();
-        let mut heap: BinaryHeap<State<'tcx>> = BinaryHeap::new();
+        let mut heap: BinaryHeap<PathState<'tcx>> = BinaryHeap::new();
 
-        for target in &target_functions {
-            dist.insert(*target, (0, 0, 0, 0, 0, 0, 0));
-            heap.push(State {
+        for source in sources {
+            dist.insert(*source, (0, 0, 0, 0, 0, 0, 0, ConstraintKindCounts::default()));
+            heap.push(PathState {
                 cost: 0,
-                node: *target,
+                node: *source,
                 package_sum: 0,
                 package_unique: HashSet::new(),
                 depth: 0,
                 dyn_edges: 0,
                 fnptr_edges: 0,
                 generic_args_len_sum: 0,
+                constraint_kinds: ConstraintKindCounts::default(),
             });
         }
 
-        while let Some(State {
+        while let Some(PathState {
             cost: cur_cost,
             node: cur_node,
             package_sum: cur_pkg,
@@ -339,29 +808,30 @@ impl<'tcx> CallGraph<'tcx> {
             dyn_edges: cur_dyn,
             fnptr_edges: cur_fnptr,
             generic_args_len_sum: cur_genlen,
+            constraint_kinds: cur_kinds,
         }) = heap.pop()
         {
             // skip if the current cost is worse than the best known
             // FIXME: non-negative weights, we can use visited set to skip
             // but current version is more general
-            if let Some((best, _, _, _, _, _, _)) = dist.get(&cur_node) {
+            if let Some((best, ..)) = dist.get(&cur_node) {
                 if cur_cost > *best {
                     continue;
                 }
             }
 
-            // Find all caller
-            if let Some(callers) = callee_to_callers.get(&cur_node) {
-                for (caller, (edge_cost, edge_pkg, edge_kind, edge_genlen)) in callers {
+            // Find all neighbors in the requested direction
+            if let Some(neighbors) = edges.get(&cur_node) {
+                for (neighbor, (edge_cost, edge_pkg, edge_kind, edge_genlen, edge_kinds)) in neighbors {
                     let next_cost = cur_cost + edge_cost;
 
-                    match dist.get(caller) {
-                        Some((best, _, _, _, _, _, _)) if next_cost >= *best => {}
+                    match dist.get(neighbor) {
+                        Some((best, ..)) if next_cost >= *best => {}
                         _ => {
                             let next_pkg = cur_pkg + edge_pkg;
                             let next_depth = cur_depth + 1;
                             let mut next_package_unique = cur_pkg_unique.clone();
-                            next_package_unique.insert(caller.def_id().krate);
+                            next_package_unique.insert(neighbor.def_id().krate);
                             let next_dyn = cur_dyn
                                 + if matches!(edge_kind, crate::callgraph::types::CallKind::DynTrait) {
                                     1
@@ -375,9 +845,10 @@ impl<'tcx> CallGraph<'tcx> {
                                     0
                                 };
                             let next_genlen = cur_genlen + edge_genlen;
+                            let next_kinds = cur_kinds.sum(*edge_kinds);
                             // Update the best path if a shorter one is found
                             dist.insert(
-                                *caller,
+                                *neighbor,
                                 (
                                     next_cost,
                                     next_pkg,
@@ -386,19 +857,21 @@ impl<'tcx> CallGraph<'tcx> {
                                     next_dyn,
                                     next_fnptr,
                                     next_genlen,
+                                    next_kinds,
                                 ),
                             );
-                            next_hop.insert(*caller, cur_node);
+                            next_hop.insert(*neighbor, cur_node);
 
-                            heap.push(State {
+                            heap.push(PathState {
                                 cost: next_cost,
-                                node: *caller,
+                                node: *neighbor,
                                 package_sum: next_pkg,
                                 package_unique: next_package_unique,
                                 depth: next_depth,
                                 dyn_edges: next_dyn,
                                 fnptr_edges: next_fnptr,
                                 generic_args_len_sum: next_genlen,
+                                constraint_kinds: next_kinds,
                             });
                         }
                     }
@@ -406,23 +879,73 @@ impl<'tcx> CallGraph<'tcx> {
             }
         }
 
-        // filter out the target functions
-        let mut all_callers: HashMap<FunctionInstance<'tcx>, (usize, usize, usize, usize, usize, usize, usize)> =
-            HashMap::new();
-        for (func, (constraints, package_num, package_unique, path_len, dyn_edges, fnptr_edges, genlen_sum)) in dist {
-            if !target_functions.contains(&func) {
-                all_callers.insert(
-                    func,
-                    (
-                        constraints,
-                        package_num,
-                        package_unique,
-                        path_len,
-                        dyn_edges,
-                        fnptr_edges,
-                        genlen_sum,
-                    ),
-                );
+        (dist, next_hop)
+    }
+
+    /// Fewest calls from each function within `max_hops` calls of `sources` in `direction`
+    ///
+    /// The sources themselves are included with 0 hops.
+    fn hop_counts(
+        &self,
+        sources: &[FunctionInstance<'tcx>],
+        direction: EdgeDirection,
+        max_hops: Option<usize>,
+    ) -> HashMap<FunctionInstance<'tcx>, usize> {
+        let edges = self.call_edges(direction);
+        let mut hops: HashMap<FunctionInstance<'tcx>, usize> = sources.iter().map(|source| (*source, 0)).collect();
+        let mut queue: std::collections::VecDeque<FunctionInstance<'tcx>> = sources.iter().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let hop_count = hops[&node];
+            if max_hops.is_some_and(|max_hops| hop_count >= max_hops) {
+                continue;
+            }
+            for neighbor in edges.get(&node).into_iter().flat_map(|neighbors| neighbors.keys()) {
+                if !hops.contains_key(neighbor) {
+                    hops.insert(*neighbor, hop_count + 1);
+                    queue.push_back(*neighbor);
+                }
+            }
+        }
+        hops
+    }
+
+    /// Find functions that match a predicate and then find all their callers
+    ///
+    /// With `max_hops`, only callers within that many calls of a target are kept; `Some(0)`
+    /// returns the matched targets themselves.
+    fn find_callers_by_predicate<F>(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        target_description: &str,
+        max_hops: Option<usize>,
+        predicate: F,
+    ) -> Vec<PathInfo<'tcx>>
+    where
+        F: Fn(FunctionInstance<'tcx>, TyCtxt<'tcx>) -> bool,
+    {
+        // First find functions that match the predicate
+        let target_functions: Vec<FunctionInstance<'tcx>> = self
+            .call_sites
+            .iter()
+            .map(|call_site| call_site.callee())
+            .filter(|&func| predicate(func, tcx))
+            .collect();
+
+        if target_functions.is_empty() {
+            tracing::warn!("No function found matching {}", target_description);
+            return Vec::new();
+        }
+
+        tracing::debug!("Found {} functions matching", target_functions.len());
+
+        let (dist, next_hop) = self.shortest_paths(&target_functions, EdgeDirection::Callers);
+        let hop_counts = self.hop_counts(&target_functions, EdgeDirection::Callers, max_hops);
+
+        // filter out the target functions, and the callers beyond `max_hops`
+        let mut all_callers: HashMap<FunctionInstance<'tcx>, PathDist> = HashMap::new();
+        for (func, path_dist) in dist {
+            if hop_counts.contains_key(&func) && (max_hops == Some(0) || !target_functions.contains(&func)) {
+                all_callers.insert(func, path_dist);
             }
         }
 
@@ -432,11 +955,22 @@ impl<'tcx> CallGraph<'tcx> {
             .map(
                 |(
                     caller,
-                    (constraints, package_num, package_num_unique, path_len, dyn_edges, fnptr_edges, genlen_sum),
+                    (
+                        constraints,
+                        package_num,
+                        package_num_unique,
+                        path_len,
+                        dyn_edges,
+                        fnptr_edges,
+                        genlen_sum,
+                        constraint_kinds,
+                    ),
                 )| PathInfo {
                     call_path: std::iter::successors(Some(caller), |node| next_hop.get(node).copied()).collect(),
+                    hop_count: hop_counts[&caller],
                     caller,
                     constraints,
+                    constraint_kinds,
                     package_num,
                     package_num_unique,
                     path_len,
@@ -455,9 +989,303 @@ impl<'tcx> CallGraph<'tcx> {
     }
 
     /// Find all functions that directly or indirectly call the specified function
-    pub fn find_callers_by_path(&self, tcx: TyCtxt<'tcx>, target_path: &str) -> Vec<PathInfo<'tcx>> {
-        self.find_callers_by_predicate(tcx, &format!("path: {target_path}"), |func, tcx| {
-            matches_function_path(tcx, func, target_path, self.without_args)
+    ///
+    /// Only callers within `max_depth` calls are reported, see `--find-callers-depth`.
+    pub fn find_callers_by_path(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        target_path: &str,
+        max_depth: Option<usize>,
+        no_generic_stripping: bool,
+    ) -> Vec<PathInfo<'tcx>> {
+        self.find_callers_by_predicate(tcx, &format!("path: {target_path}"), max_depth, |func, tcx| {
+            self.matches_path(tcx, func, target_path, no_generic_stripping)
         })
     }
+
+    /// Find all callers of the panic machinery in `core::panicking` and `std::panicking`
+    pub(crate) fn find_panic_callers(&self, tcx: TyCtxt<'tcx>) -> Vec<PathInfo<'tcx>> {
+        self.find_callers_by_predicate(tcx, "panic sources", None, |func, tcx| {
+            is_panic_source(tcx, func.def_id())
+        })
+    }
+
+    /// Simple call paths from the entry points to the functions matching `target_path`
+    ///
+    /// Paths are enumerated by a depth-first search backwards from the targets that never
+    /// revisits a function of the current path. At most `max_paths` paths of at most
+    /// `max_path_len` calls are returned, each starting at an entry point; the flag is set
+    /// when the search was cut short and more paths may exist.
+    pub(crate) fn all_paths_to(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        target_path: &str,
+        max_paths: usize,
+        max_path_len: usize,
+        no_generic_stripping: bool,
+    ) -> (Vec<Vec<FunctionInstance<'tcx>>>, bool) {
+        let mut targets = self.functions_matching_paths(tcx, &[target_path.to_string()], no_generic_stripping);
+        targets.sort_by_key(|target| format!("{target:?}"));
+        let entry_points: HashSet<FunctionInstance<'tcx>> = stats::entry_points(tcx).into_iter().collect();
+
+        let mut search = PathSearch::new(
+            self.call_edges(EdgeDirection::Callers),
+            PathEnd::EntryPoints(&entry_points),
+            max_paths,
+            max_path_len,
+        );
+        for target in targets {
+            search.visit(target);
+        }
+        (search.paths, search.truncated)
+    }
+
+    /// Simple call paths from each of `roots` to a function where the path cannot be extended
+    ///
+    /// A path ends at a function without callees, at calls back into the path (recursion)
+    /// and after `max_path_len` calls. Like [`Self::all_paths_to`], at most `max_paths`
+    /// paths are returned and the flag is set when the search was cut short.
+    pub(crate) fn all_paths_from(
+        &self,
+        roots: &[FunctionInstance<'tcx>],
+        max_paths: usize,
+        max_path_len: usize,
+    ) -> (Vec<Vec<FunctionInstance<'tcx>>>, bool) {
+        let mut roots = roots.to_vec();
+        roots.sort_by_key(|root| format!("{root:?}"));
+
+        let mut search = PathSearch::new(
+            self.call_edges(EdgeDirection::Callees),
+            PathEnd::Leaves,
+            max_paths,
+            max_path_len,
+        );
+        for root in roots {
+            search.visit(root);
+        }
+        (search.paths, search.truncated)
+    }
+
+    /// Keep only the subgraph reachable from the functions matching `target_paths`
+    ///
+    /// This is the forward dual of `--find-callers`: the result contains the matched
+    /// functions, all their direct and indirect callees, and the edges among them.
+    pub(crate) fn retain_reachable_from(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        target_paths: &[String],
+        no_generic_stripping: bool,
+    ) {
+        let sources = self.functions_matching_paths(tcx, target_paths, no_generic_stripping);
+        let (reachable, _) = self.shortest_paths(&sources, EdgeDirection::Callees);
+        let before = self.call_sites.len();
+        self.call_sites.retain(|call_site| {
+            reachable.contains_key(&call_site.caller()) && reachable.contains_key(&call_site.callee())
+        });
+        tracing::debug!(
+            "Kept {} of {} call sites reachable from {} functions",
+            self.call_sites.len(),
+            before,
+            sources.len()
+        );
+
+        // The cached adjacency describes the full graph
+        self.callee_to_callers.take();
+        self.caller_to_callees.take();
+    }
+
+    /// Keep only the calls into the crates of `filters` whose version matches the requirement
+    ///
+    /// `filters` are `(crate_name, requirement)` pairs of `--crate-version-filter`. Versions
+    /// are the ones reported in the JSON output, see [`get_crate_version`].
+    pub(crate) fn retain_crate_versions(&mut self, tcx: TyCtxt<'tcx>, filters: &[(String, String)]) {
+        let requirements: Vec<(&str, semver::VersionReq)> = filters
+            .iter()
+            .filter_map(|(name, requirement)| {
+                semver::VersionReq::parse(requirement)
+                    .ok()
+                    .map(|requirement| (name.as_str(), requirement))
+            })
+            .collect();
+
+        let mut matches_by_crate: HashMap<CrateNum, bool> = HashMap::new();
+        let before = self.call_sites.len();
+        self.call_sites.retain(|call_site| {
+            let def_id = call_site.callee().def_id();
+            *matches_by_crate.entry(def_id.krate).or_insert_with(|| {
+                let crate_name = tcx.crate_name(def_id.krate);
+                let Ok(version) = semver::Version::parse(&get_crate_version(tcx, def_id)) else {
+                    return false;
+                };
+                requirements
+                    .iter()
+                    .any(|(name, requirement)| *name == crate_name.as_str() && requirement.matches(&version))
+            })
+        });
+        self.version_filter_applied = true;
+        tracing::debug!(
+            "Kept {} of {} call sites into crates matching --crate-version-filter",
+            self.call_sites.len(),
+            before
+        );
+
+        // The cached adjacency describes the full graph
+        self.callee_to_callers.take();
+        self.caller_to_callees.take();
+    }
+
+    /// Collapse calls through `#[inline(always)]` functions, for `--merge-inline`
+    ///
+    /// Every pair of calls `A -> B` and `B -> C` through such a function `B` is replaced by
+    /// a direct call `A -> C`, see [`CallSite::merged_through`], and `B` leaves the graph.
+    /// Functions without callers (e.g. entry points) or callees (leaves) and recursive
+    /// functions are kept.
+    pub(crate) fn merge_inline_calls(&mut self, tcx: TyCtxt<'tcx>) {
+        // Calls grouped by caller and callers grouped by callee, in order of first appearance
+        let mut caller_order = Vec::new();
+        let mut callee_order = Vec::new();
+        let mut calls_by_caller: HashMap<FunctionInstance<'tcx>, Vec<CallSite<'tcx>>> = HashMap::new();
+        let mut callers_by_callee: HashMap<FunctionInstance<'tcx>, HashSet<FunctionInstance<'tcx>>> = HashMap::new();
+        for call_site in self.call_sites.drain(..) {
+            callers_by_callee
+                .entry(call_site.callee())
+                .or_insert_with(|| {
+                    callee_order.push(call_site.callee());
+                    HashSet::new()
+                })
+                .insert(call_site.caller());
+            calls_by_caller
+                .entry(call_site.caller())
+                .or_insert_with(|| {
+                    caller_order.push(call_site.caller());
+                    Vec::new()
+                })
+                .push(call_site);
+        }
+
+        let inline_fns: Vec<FunctionInstance<'tcx>> = callee_order
+            .into_iter()
+            .filter(|callee| callee.is_inline_always(tcx))
+            .collect();
+
+        let mut merged = 0;
+        for inline_fn in inline_fns {
+            let callers = callers_by_callee.get(&inline_fn).cloned().unwrap_or_default();
+            let outgoing = calls_by_caller.get(&inline_fn).cloned().unwrap_or_default();
+            if callers.is_empty() || outgoing.is_empty() || callers.contains(&inline_fn) {
+                continue;
+            }
+            for caller in &callers {
+                let calls = calls_by_caller.get_mut(caller).expect("callers are indexed");
+                let (incoming, rest): (Vec<_>, Vec<_>) =
+                    calls.drain(..).partition(|call_site| call_site.callee() == inline_fn);
+                *calls = rest;
+                for call_site in &incoming {
+                    calls.extend(outgoing.iter().map(|next| call_site.merged_through(next)));
+                }
+                for next in &outgoing {
+                    callers_by_callee.entry(next.callee()).or_default().insert(*caller);
+                }
+            }
+            calls_by_caller.remove(&inline_fn);
+            callers_by_callee.remove(&inline_fn);
+            for next in &outgoing {
+                if let Some(next_callers) = callers_by_callee.get_mut(&next.callee()) {
+                    next_callers.remove(&inline_fn);
+                }
+            }
+            merged += 1;
+        }
+        tracing::info!("Merged {merged} #[inline(always)] functions into their callers");
+
+        self.call_sites = caller_order
+            .into_iter()
+            .filter_map(|caller| calls_by_caller.remove(&caller))
+            .flatten()
+            .collect();
+        self.total_functions = self.total_functions.saturating_sub(merged);
+
+        // The cached adjacency describes the unmerged graph
+        self.callee_to_callers.take();
+        self.caller_to_callees.take();
+    }
+
+    /// A cycle of calls among functions of the analyzed crate, for `--exit-on-cycles`
+    ///
+    /// Returns the functions on the cycle in call order; direct recursion is a cycle of
+    /// one function. Cycles through other crates (e.g. recursion inside `std`) are ignored.
+    pub(crate) fn find_local_cycle(&self) -> Option<Vec<FunctionInstance<'tcx>>> {
+        let edges = self.call_edges(EdgeDirection::Callees);
+        let local_callees = |func: &FunctionInstance<'tcx>| -> Vec<FunctionInstance<'tcx>> {
+            edges
+                .get(func)
+                .into_iter()
+                .flat_map(|callees| callees.keys().copied())
+                .filter(|callee| callee.def_id().is_local())
+                .collect()
+        };
+
+        // Iterative DFS, a callee on the current path closes a cycle
+        let mut finished: HashSet<FunctionInstance<'tcx>> = HashSet::new();
+        for &start in edges.keys().filter(|func| func.def_id().is_local()) {
+            if finished.contains(&start) {
+                continue;
+            }
+            let mut path = vec![start];
+            let mut stack = vec![local_callees(&start).into_iter()];
+            while let Some(callees) = stack.last_mut() {
+                match callees.next() {
+                    Some(callee) => {
+                        if let Some(position) = path.iter().position(|&func| func == callee) {
+                            return Some(path[position..].to_vec());
+                        }
+                        if !finished.contains(&callee) {
+                            path.push(callee);
+                            stack.push(local_callees(&callee).into_iter());
+                        }
+                    }
+                    None => {
+                        stack.pop();
+                        finished.extend(path.pop());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Call sites where a function of the analyzed crate calls an `unsafe fn`, for `--exit-on-unsafe-calls`
+    pub(crate) fn local_unsafe_calls(&self, tcx: TyCtxt<'tcx>) -> Vec<&CallSite<'tcx>> {
+        self.call_sites
+            .iter()
+            .filter(|call_site| {
+                call_site.caller().def_id().is_local() && is_unsafe_fn(tcx, call_site.callee().def_id())
+            })
+            .collect()
+    }
+
+    /// Functions of the graph matching any of `target_paths`, warning about unmatched paths
+    pub(crate) fn functions_matching_paths(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        target_paths: &[String],
+        no_generic_stripping: bool,
+    ) -> Vec<FunctionInstance<'tcx>> {
+        let functions: HashSet<FunctionInstance<'tcx>> = self
+            .call_sites
+            .iter()
+            .flat_map(|call_site| [call_site.caller(), call_site.callee()])
+            .collect();
+        let mut matched = Vec::new();
+        for target_path in target_paths {
+            let before = matched.len();
+            matched.extend(functions.iter().copied().filter(|&func| {
+                matches_function_path(tcx, func, target_path, self.without_args, no_generic_stripping)
+            }));
+            if matched.len() == before {
+                tracing::warn!("No function found matching path: {}", target_path);
+            }
+        }
+        matched
+    }
 }
@@ -0,0 +1,185 @@
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{TyCtxt, Visibility};
+use serde_json::json;
+use std::io::Write;
+
+use super::fmt::write_output;
+use super::types::CallGraph;
+
+/// Visibility of a callee, as seen from the rest of its crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CalleeVisibility {
+    Pub,
+    PubCrate,
+    /// Only visible in a module narrower than the crate (private, `pub(super)`, `pub(in path)`)
+    Private,
+}
+
+impl CalleeVisibility {
+    const ALL: [CalleeVisibility; 3] = [
+        CalleeVisibility::Pub,
+        CalleeVisibility::PubCrate,
+        CalleeVisibility::Private,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CalleeVisibility::Pub => "pub",
+            CalleeVisibility::PubCrate => "pub_crate",
+            CalleeVisibility::Private => "private",
+        }
+    }
+}
+
+/// Where a call goes relative to its caller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CallScope {
+    SameModule,
+    CrossModule,
+    CrossCrate,
+}
+
+impl CallScope {
+    const ALL: [CallScope; 3] = [CallScope::SameModule, CallScope::CrossModule, CallScope::CrossCrate];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            CallScope::SameModule => "same_module",
+            CallScope::CrossModule => "cross_module",
+            CallScope::CrossCrate => "cross_crate",
+        }
+    }
+}
+
+/// Call counts grouped by call scope and callee visibility
+#[derive(Debug, Default)]
+pub(crate) struct VisibilityReport {
+    counts: [[usize; 3]; 3],
+    /// Callees without a declared visibility, e.g. shims of compiler-generated items
+    unknown: usize,
+}
+
+impl VisibilityReport {
+    fn count(&self, scope: CallScope, visibility: CalleeVisibility) -> usize {
+        self.counts[scope as usize][visibility as usize]
+    }
+
+    /// `(key, count)` pairs such as `("cross_module_private_calls", 3)`
+    fn entries(&self) -> Vec<(String, usize)> {
+        CallScope::ALL
+            .into_iter()
+            .flat_map(|scope| {
+                CalleeVisibility::ALL.into_iter().map(move |visibility| {
+                    (
+                        format!("{}_{}_calls", scope.as_str(), visibility.as_str()),
+                        self.count(scope, visibility),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn format(&self, crate_name: &str) -> String {
+        let mut result = String::new();
+        result.push_str(&format!("Visibility Report for {crate_name}:\n"));
+        result.push_str("==================================\n\n");
+        for (key, count) in self.entries() {
+            result.push_str(&format!("{key}: {count}\n"));
+        }
+        result.push_str(&format!("unknown_visibility_calls: {}\n", self.unknown));
+        result
+    }
+
+    fn format_as_json(&self, crate_name: &str) -> String {
+        let mut counts = serde_json::Map::new();
+        for (key, count) in self.entries() {
+            counts.insert(key, json!(count));
+        }
+        counts.insert("unknown_visibility_calls".to_string(), json!(self.unknown));
+        let result = json!({
+            "crate_name": crate_name,
+            "calls": counts
+        });
+        serde_json::to_string_pretty(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Nearest enclosing module of `def_id` (the crate root for top-level items)
+fn parent_module(tcx: TyCtxt<'_>, def_id: DefId) -> DefId {
+    let mut current = def_id;
+    while let Some(parent) = tcx.opt_parent(current) {
+        if tcx.def_kind(parent) == DefKind::Mod {
+            return parent;
+        }
+        current = parent;
+    }
+    current
+}
+
+/// Visibility of a function, closures take the visibility of their enclosing function
+fn callee_visibility(tcx: TyCtxt<'_>, def_id: DefId) -> Option<CalleeVisibility> {
+    let def_id = tcx.typeck_root_def_id(def_id);
+    // Only query items that have a visibility, external metadata has none for e.g. closures
+    if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn | DefKind::Ctor(..)) {
+        return None;
+    }
+
+    Some(match tcx.visibility(def_id) {
+        Visibility::Public => CalleeVisibility::Pub,
+        Visibility::Restricted(module) if module.is_crate_root() && parent_module(tcx, def_id) != module => {
+            CalleeVisibility::PubCrate
+        }
+        Visibility::Restricted(_) => CalleeVisibility::Private,
+    })
+}
+
+impl<'tcx> CallGraph<'tcx> {
+    /// Count the output call sites by call scope and callee visibility
+    pub(crate) fn visibility_report(&self, tcx: TyCtxt<'tcx>, options: &crate::args::CGArgs) -> VisibilityReport {
+        let mut report = VisibilityReport::default();
+        for call_site in self.output_call_sites(tcx, options) {
+            let caller = call_site.caller().def_id();
+            let callee = call_site.callee().def_id();
+            let Some(visibility) = callee_visibility(tcx, callee) else {
+                report.unknown += 1;
+                continue;
+            };
+            let scope = if caller.krate != callee.krate {
+                CallScope::CrossCrate
+            } else if parent_module(tcx, caller) != parent_module(tcx, callee) {
+                CallScope::CrossModule
+            } else {
+                CallScope::SameModule
+            };
+            report.counts[scope as usize][visibility as usize] += 1;
+        }
+        report
+    }
+}
+
+/// Write `{crate_name}-visibility-report.txt` and `{crate_name}-visibility-report.json`
+pub(crate) fn output_visibility_report<'tcx>(
+    call_graph: &CallGraph<'tcx>,
+    tcx: TyCtxt<'tcx>,
+    options: &crate::args::CGArgs,
+) {
+    let report = crate::timer::measure("visibility_report", || call_graph.visibility_report(tcx, options));
+    let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+    let output_dir = options
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| std::path::PathBuf::from("./target"));
+
+    for (extension, output) in [
+        ("txt", report.format(&crate_name)),
+        ("json", report.format_as_json(&crate_name)),
+    ] {
+        let output_path =
+            output_dir.join(options.output_file_name(&format!("{crate_name}-visibility-report.{extension}")));
+        match write_output(&output_path, options.compress, |file| write!(file, "{output}")) {
+            Ok(path) => tracing::info!("Visibility report written to {}", path.display()),
+            Err(e) => tracing::error!("Failed to write visibility report: {}", e),
+        }
+    }
+}
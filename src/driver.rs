@@ -1,20 +1,41 @@
-use clap::Parser;
 use rustc_driver::Compilation;
 use rustc_interface::interface;
 use rustc_middle::ty::TyCtxt;
 use std::borrow::Cow;
 use std::env;
+use std::panic::{self, AssertUnwindSafe};
 use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use crate::args::{AllCliArgs, CGArgs};
 use crate::callgraph;
 use crate::timer::Timer;
 
 // The plugin for the rustc_driver
-use rustc_compat::{CrateFilter, Plugin, RustcPluginArgs, Utf8Path};
+use rustc_compat::{
+    CompilationUnit, CrateFilter, FEATURE_COMBO, Plugin, RustcPluginArgs, Utf8Path, plugin_env, report_exit_code,
+};
+
+/// Name of the driver binary, which the `rustc_compat` environment variables are derived from
+const DRIVER_NAME: &str = "cg4rs";
 
 #[derive(Default)]
-pub struct CGDriver;
+pub struct CGDriver {
+    collector_factory: Option<callgraph::CollectorFactory>,
+}
+
+impl CGDriver {
+    /// Run additional MIR collectors in the same pass that collects call sites
+    ///
+    /// `factory` is called once per analyzed crate; every returned collector sees the
+    /// statements and terminators of each analyzed function, and is finished after the crate.
+    pub fn with_collectors(factory: callgraph::CollectorFactory) -> Self {
+        Self {
+            collector_factory: Some(factory),
+        }
+    }
+}
 
 impl Plugin for CGDriver {
     type CargoArgs = Vec<String>;
@@ -25,15 +46,48 @@ impl Plugin for CGDriver {
     }
 
     fn driver_name(&self) -> Cow<'static, str> {
-        "cg4rs".into()
+        DRIVER_NAME.into()
     }
 
-    /// In the CLI, we ask Clap to parse arguments and also specify a CrateFilter.
+    /// In the CLI, we ask Clap to parse arguments, merge them with `cg4rs.toml`
+    /// and also specify a CrateFilter (all crates, or the crate containing `--file`).
     fn args(&self, _target_dir: &Utf8Path) -> RustcPluginArgs<Self::CargoArgs, Self::PluginArgs> {
-        let args = AllCliArgs::parse_from(env::args().skip(1));
-        let filter = CrateFilter::AllCrates;
+        let cli_args = AllCliArgs::expand_subcommands(env::args().skip(1));
+        let args = AllCliArgs::parse_with_config(cli_args).unwrap_or_else(|e| {
+            tracing::error!("Failed to load configuration: {:?}", e);
+            std::process::exit(1);
+        });
+        // Rows are appended by the drivers, so the index only lists the crates of this run
+        callgraph::reset_crate_index(&args.cg_args);
+        let filter = match &args.cg_args.file {
+            Some(file) if !file.is_file() => {
+                tracing::error!("File passed to --file does not exist: {}", file.display());
+                std::process::exit(1);
+            }
+            Some(file) => CrateFilter::CrateContainingFile(file.clone()),
+            None => match &args.cg_args.since {
+                // Only check the packages with changed sources; the rest have no changed functions
+                Some(git_ref) => match callgraph::changed_files(callgraph::diff_dir(&args.cg_args), git_ref) {
+                    Some(files) => CrateFilter::PackagesContainingFiles(
+                        files
+                            .into_iter()
+                            .filter(|file| file.extension().is_some_and(|extension| extension == "rs"))
+                            .collect(),
+                    ),
+                    None => CrateFilter::AllCrates,
+                },
+                None => CrateFilter::AllCrates,
+            },
+        };
         RustcPluginArgs {
             cargo_args: args.cargo_args,
+            include_build_scripts: args.cg_args.include_build_scripts,
+            analyze_proc_macros: args.cg_args.analyze_proc_macros,
+            crate_types: args.cg_args.crate_types.clone(),
+            target: args.cg_args.target.clone(),
+            deps_depth: args.cg_args.deps_depth,
+            feature_combos: args.cg_args.feature_combo_lists(),
+            all_feature_combos: args.cg_args.all_feature_combos,
             plugin_args: args.cg_args,
             filter,
         }
@@ -46,11 +100,17 @@ impl Plugin for CGDriver {
 
     /// In the driver, we use the rustc_driver API to start a compiler session
     /// for the arguments given to us by rustc_plugin.
-    fn run(self, compiler_args: Vec<String>, plugin_args: Self::PluginArgs) {
+    fn run(self, compiler_args: Vec<String>, mut plugin_args: Self::PluginArgs, unit: CompilationUnit) {
+        // Every build script is a `build_script_build` crate, so their outputs are told apart by package
+        if unit.is_build_script
+            && let Some(package) = &unit.package_name
+        {
+            plugin_args.output_prefix = Some(plugin_args.output_file_name(&format!("{package}-")));
+        }
         Timer::init(&plugin_args);
 
         crate::timer::measure("Overall_execution", || {
-            let mut callbacks = CGCallbacks::new(plugin_args);
+            let mut callbacks = CGCallbacks::new(plugin_args, unit, self.collector_factory);
             rustc_driver::run_compiler(&compiler_args, &mut callbacks);
         });
 
@@ -63,19 +123,181 @@ impl Plugin for CGDriver {
 /// The callbacks for the rustc_driver
 pub(crate) struct CGCallbacks {
     cg_args: CGArgs,
+    unit: CompilationUnit,
+    collector_factory: Option<callgraph::CollectorFactory>,
 }
 
 impl CGCallbacks {
-    pub fn new(cg_args: CGArgs) -> Self {
-        Self { cg_args }
+    pub fn new(cg_args: CGArgs, unit: CompilationUnit, collector_factory: Option<callgraph::CollectorFactory>) -> Self {
+        Self {
+            cg_args,
+            unit,
+            collector_factory,
+        }
     }
 }
 
 impl rustc_driver::Callbacks for CGCallbacks {
     fn after_analysis<'tcx>(&mut self, _compiler: &interface::Compiler, tcx: TyCtxt<'tcx>) -> Compilation {
         tracing::info!("{}", "Entering after_analysis rustc_driver callback");
-        callgraph::analyze_crate(tcx, &self.cg_args);
+        let is_build_script = self.unit.is_build_script;
+        let compile_target = self.unit.target.clone();
+        if let Some(trait_path) = &self.cg_args.list_impls {
+            callgraph::output_trait_impls(tcx, trait_path, &self.cg_args);
+            tracing::info!("{}", "Exiting after_analysis rustc_driver callback");
+            return Compilation::Continue;
+        }
+
+        let feature_combo = env::var(plugin_env(DRIVER_NAME, FEATURE_COMBO)).ok().map(|features| {
+            features
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(String::from)
+                .collect()
+        });
+        // A panic while analyzing one crate must not abort the whole cargo run,
+        // outputs of crates that already succeeded are kept
+        let crate_name = tcx.crate_name(rustc_hir::def_id::LOCAL_CRATE).to_string();
+        let mut collectors = self.collector_factory.map(|factory| factory(tcx)).unwrap_or_default();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            callgraph::analyze_crate(
+                tcx,
+                &self.cg_args,
+                is_build_script,
+                compile_target,
+                feature_combo,
+                &mut collectors,
+            )
+        }));
+        match result {
+            // Compilation goes on, so the other crates are still analyzed; cargo-cg4rs exits with the code
+            Ok(outcome) if outcome != callgraph::AnalysisOutcome::Clean => {
+                report_exit_code(DRIVER_NAME, outcome.exit_code())
+            }
+            Ok(_) => {}
+            Err(payload) => {
+                let message = panic_message(payload.as_ref());
+                tracing::error!("Analysis of crate {} failed: {}", crate_name, message);
+                callgraph::output_failure_marker(&crate_name, &message, &self.cg_args);
+                report_exit_code(DRIVER_NAME, 101);
+            }
+        }
         tracing::info!("{}", "Exiting after_analysis rustc_driver callback");
         Compilation::Continue
     }
 }
+
+/// How long a `cg4rs` driver invocation may run before it is killed with exit code 124
+pub const DRIVER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Time kept for writing the outputs of a crate once its expansion has been stopped
+const OUTPUT_MARGIN: Duration = Duration::from_secs(30);
+
+static EXPANSION_DEADLINE: OnceLock<Instant> = OnceLock::new();
+
+/// Start the clock of [`DRIVER_TIMEOUT`] for this driver invocation
+///
+/// The analysis stops expanding functions [`OUTPUT_MARGIN`] before the driver is killed,
+/// so the calls collected so far are still written out.
+pub fn start_driver_timeout() {
+    let _ = EXPANSION_DEADLINE.set(Instant::now() + DRIVER_TIMEOUT - OUTPUT_MARGIN);
+}
+
+/// Whether the analysis has to stop expanding functions for the driver timeout
+pub(crate) fn expansion_deadline_passed() -> bool {
+    #[cfg(feature = "fault-injection")]
+    if env::var_os("CG4RS_INJECT_TIMEOUT").is_some() {
+        return true;
+    }
+    EXPANSION_DEADLINE
+        .get()
+        .is_some_and(|deadline| Instant::now() >= *deadline)
+}
+
+/// Best-effort text of a panic payload
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::callgraph::{FunctionInstance, MirEdgeCollector};
+    use clap::Parser;
+    use rustc_middle::mir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CALLS_SEEN: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Counts the call terminators of every analyzed function
+    struct CountingCollector;
+
+    impl<'tcx> MirEdgeCollector<'tcx> for CountingCollector {
+        fn visit_terminator(
+            &mut self,
+            _caller: FunctionInstance<'tcx>,
+            _body: &mir::Body<'tcx>,
+            terminator: &mir::Terminator<'tcx>,
+            _location: mir::Location,
+        ) {
+            if matches!(terminator.kind, mir::TerminatorKind::Call { .. }) {
+                CALLS_SEEN.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        fn finish(&mut self, _tcx: TyCtxt<'tcx>) {
+            FINISHED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_collectors<'tcx>(_tcx: TyCtxt<'tcx>) -> Vec<Box<dyn MirEdgeCollector<'tcx> + 'tcx>> {
+        vec![Box::new(CountingCollector)]
+    }
+
+    #[test]
+    fn test_registered_collectors_see_calls_and_are_finished() {
+        let dir = env::temp_dir().join(format!("cg4rs-collector-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("main.rs");
+        std::fs::write(
+            &source,
+            "fn helper() {}\nfn main() {\n    helper();\n    helper();\n}\n",
+        )
+        .unwrap();
+        let sysroot = Command::new("rustc").args(["--print", "sysroot"]).output().unwrap();
+        let sysroot = String::from_utf8(sysroot.stdout).unwrap();
+
+        let cg_args = CGArgs::parse_from(["cg4rs", "--output-dir", dir.to_str().unwrap()]);
+        let driver = CGDriver::with_collectors(counting_collectors);
+        let mut callbacks = CGCallbacks::new(cg_args, CompilationUnit::default(), driver.collector_factory);
+        let compiler_args: Vec<String> = [
+            "rustc",
+            source.to_str().unwrap(),
+            "--edition=2021",
+            "--emit=metadata",
+            "--out-dir",
+            dir.to_str().unwrap(),
+            "--sysroot",
+            sysroot.trim(),
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        rustc_driver::run_compiler(&compiler_args, &mut callbacks);
+
+        assert!(CALLS_SEEN.load(Ordering::SeqCst) >= 2, "both calls of main are visited");
+        assert_eq!(
+            FINISHED.load(Ordering::SeqCst),
+            1,
+            "the collector is finished once per crate"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -19,7 +19,28 @@ extern crate rustc_type_ir;
 mod args;
 mod callgraph;
 mod driver;
+mod process;
 mod timer;
 
 // This is used in cg4rs.rs
-pub use driver::CGDriver;
+pub use driver::{CGDriver, DRIVER_TIMEOUT, start_driver_timeout};
+pub use timer::Timer;
+
+// Cleaning up child processes when the analysis times out
+pub use process::{get_process_descendants, terminate_child_processes};
+
+// Exposed for users extending the `--hide-generated` filter
+pub use callgraph::GENERATED_FN_PATTERNS;
+
+// Diffing the outputs of `--feature-combo` runs
+pub use callgraph::{FeatureComboDiff, compare_feature_outputs};
+
+// Custom MIR collectors run alongside call site collection, see `CGDriver::with_collectors`
+pub use callgraph::{CollectorFactory, FunctionInstance, MirEdgeCollector};
+
+// Loading `--binary-output` files
+pub use callgraph::{CallSiteModel, GRAPH_MODEL_VERSION, GraphModel};
+
+// `cargo cg4rs query` on saved call graphs
+pub use args::QueryArgs;
+pub use callgraph::{GraphQuery, PathQuery, QueryReport, run_query};
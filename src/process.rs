@@ -0,0 +1,196 @@
+//! Cleanup of the processes spawned during an analysis
+//!
+//! rustc runs on a blocking task that cannot be cancelled, and may itself have spawned
+//! child processes (e.g. linkers or build scripts). When the analysis times out, these
+//! have to be terminated explicitly, otherwise they outlive the driver.
+
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+
+/// All transitive child processes of `pid`, parents before their children
+#[cfg(unix)]
+pub fn get_process_descendants(pid: u32) -> Vec<u32> {
+    let parents = process_parents();
+    let mut descendants = Vec::new();
+    let mut queue = vec![pid];
+    while let Some(parent) = queue.pop() {
+        for &(child, _) in parents.iter().filter(|(_, ppid)| *ppid == parent) {
+            descendants.push(child);
+            queue.push(child);
+        }
+    }
+    descendants
+}
+
+/// `(pid, parent pid)` of every running process
+#[cfg(target_os = "linux")]
+fn process_parents() -> Vec<(u32, u32)> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            // The command name may contain spaces and parentheses, so parse after the last `)`
+            let (_, fields) = stat.rsplit_once(')')?;
+            let ppid = fields.split_whitespace().nth(1)?.parse().ok()?;
+            Some((pid, ppid))
+        })
+        .collect()
+}
+
+/// `(pid, parent pid)` of every running process
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_parents() -> Vec<(u32, u32)> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some((fields.next()?.parse().ok()?, fields.next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Terminate all descendants of the current process
+///
+/// Sends `SIGTERM` first and `SIGKILL` to the processes still alive after `grace_period`.
+/// Returns the number of processes that were signaled.
+#[cfg(unix)]
+pub fn terminate_child_processes(grace_period: Duration) -> usize {
+    terminate_descendants(std::process::id(), grace_period)
+}
+
+/// Terminate all descendants of `pid`, see [`terminate_child_processes`]
+#[cfg(unix)]
+fn terminate_descendants(pid: u32, grace_period: Duration) -> usize {
+    let descendants = get_process_descendants(pid);
+    if descendants.is_empty() {
+        return 0;
+    }
+    tracing::info!("Terminating {} child processes", descendants.len());
+    for &pid in &descendants {
+        signal(pid, libc::SIGTERM);
+    }
+
+    let deadline = Instant::now() + grace_period;
+    while Instant::now() < deadline && descendants.iter().any(|&pid| is_alive(pid)) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    for &pid in descendants.iter().filter(|&&pid| is_alive(pid)) {
+        tracing::warn!("Child process {pid} ignored SIGTERM, killing it");
+        signal(pid, libc::SIGKILL);
+    }
+    descendants.len()
+}
+
+/// Child processes are not tracked on this platform
+#[cfg(not(unix))]
+pub fn get_process_descendants(_pid: u32) -> Vec<u32> {
+    Vec::new()
+}
+
+/// Child processes are not tracked on this platform, so nothing is terminated
+#[cfg(not(unix))]
+pub fn terminate_child_processes(_grace_period: Duration) -> usize {
+    tracing::warn!("Terminating child processes is not supported on this platform");
+    0
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: libc::c_int) {
+    // SAFETY: `kill` has no memory safety requirements, a stale pid only makes it fail
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+        tracing::debug!(
+            "Failed to send signal {signal} to {pid}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Whether `pid` still exists and is not a zombie waiting to be reaped
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    // Reap the process if it is our direct child, so it does not linger as a zombie
+    let mut status = 0;
+    // SAFETY: `status` is a valid pointer, `WNOHANG` makes the call non-blocking
+    if unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) } == pid as libc::pid_t {
+        return false;
+    }
+    // SAFETY: signal 0 only checks whether the process exists
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::{Child, Command};
+
+    /// Spawn `sh -c script`, and wait until the shell has a child of its own
+    fn spawn_shell_with_child(script: &str) -> (Child, u32) {
+        let shell = Command::new("sh")
+            .args(["-c", script])
+            .spawn()
+            .expect("failed to spawn sh");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(&grandchild) = get_process_descendants(shell.id()).first() {
+                return (shell, grandchild);
+            }
+            assert!(Instant::now() < deadline, "the shell did not spawn its child");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_descendants_list_parents_before_children() {
+        let (mut shell, grandchild) = spawn_shell_with_child("sleep 30 & wait");
+
+        let descendants = get_process_descendants(std::process::id());
+        let position = |pid: u32| descendants.iter().position(|&descendant| descendant == pid);
+        let shell_position = position(shell.id()).expect("the shell is a child of the test");
+        let grandchild_position = position(grandchild).expect("sleep is a grandchild of the test");
+        assert!(shell_position < grandchild_position, "{descendants:?}");
+
+        terminate_descendants(shell.id(), Duration::from_secs(5));
+        shell.wait().expect("failed to wait for sh");
+    }
+
+    #[test]
+    fn test_terminate_sends_sigterm() {
+        let (mut shell, grandchild) = spawn_shell_with_child("sleep 30 & wait");
+
+        assert_eq!(terminate_descendants(shell.id(), Duration::from_secs(5)), 1);
+        assert!(!is_alive(grandchild));
+        let status = shell.wait().expect("failed to wait for sh");
+        assert!(!status.success(), "wait reports the terminated sleep: {status}");
+    }
+
+    #[test]
+    fn test_terminate_kills_processes_ignoring_sigterm() {
+        // Ignored signals stay ignored in the spawned sleep
+        let (mut shell, grandchild) = spawn_shell_with_child("trap '' TERM; sleep 30 & wait");
+
+        let started = Instant::now();
+        assert_eq!(terminate_descendants(shell.id(), Duration::from_millis(200)), 1);
+        assert!(
+            started.elapsed() >= Duration::from_millis(200),
+            "SIGKILL only follows the grace period"
+        );
+        // SIGKILL is not waited for, the shell reaps the sleep shortly after
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_alive(grandchild) {
+            assert!(Instant::now() < deadline, "sleep survived SIGKILL");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        shell.wait().expect("failed to wait for sh");
+    }
+}
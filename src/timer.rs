@@ -5,11 +5,16 @@
 //! 2. Collect execution time statistics
 //! 3. Write timer results to a file
 //! 4. Global access through a singleton pattern
+//!
+//! Timers may be started again while running, on the same thread (nested `measure` calls)
+//! or on other threads. Every stop counts as one call, and only the outermost measurement
+//! of each thread adds to the total, so nested time is not counted twice.
 use anyhow::Result;
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
 use std::time::{Duration, Instant};
 
 use itertools::Itertools;
@@ -23,7 +28,8 @@ lazy_static::lazy_static! {
 
 #[derive(Debug)]
 struct TimerData {
-    start_time: Option<Instant>,
+    /// Start instants of the running measurements per thread, innermost last
+    running: HashMap<ThreadId, Vec<Instant>>,
     elapsed: Duration,
     count: usize,
 }
@@ -31,7 +37,7 @@ struct TimerData {
 impl TimerData {
     fn new() -> Self {
         Self {
-            start_time: None,
+            running: HashMap::new(),
             elapsed: Duration::from_secs(0),
             count: 0,
         }
@@ -43,6 +49,7 @@ pub struct Timer {
     timers: Arc<Mutex<HashMap<String, TimerData>>>,
     output_file: Arc<Mutex<Option<String>>>,
     enabled: Arc<Mutex<bool>>,
+    current_phase: Arc<Mutex<Option<String>>>,
 }
 
 impl Timer {
@@ -51,14 +58,18 @@ impl Timer {
             timers: Arc::new(Mutex::new(HashMap::new())),
             output_file: Arc::new(Mutex::new(None)),
             enabled: Arc::new(Mutex::new(false)),
+            current_phase: Arc::new(Mutex::new(None)),
         }
     }
 
     pub fn init(plugin_args: &CGArgs) {
         // Only enable when timer_output is specified
         let mut enabled = TIMER.enabled.lock().unwrap();
-        if let Some(path) = plugin_args.timer_output.clone() {
+        if let Some(mut path) = plugin_args.timer_output.clone() {
             *enabled = true;
+            if let Some(file_name) = path.file_name() {
+                path.set_file_name(plugin_args.output_file_name(&file_name.to_string_lossy()));
+            }
             Timer::set_output_file(path.to_str().unwrap());
         } else {
             *enabled = false;
@@ -76,19 +87,18 @@ impl Timer {
     /// # Arguments
     /// * `name` - The name of the timer to start
     pub fn start(name: &str) {
+        // Track the phase even when timing is disabled, so a timeout can report it
+        *TIMER.current_phase.lock().unwrap() = Some(name.to_string());
         if !*TIMER.enabled.lock().unwrap() {
             return;
         }
         let mut timers = TIMER.timers.lock().unwrap();
         let timer = timers.entry(name.to_string()).or_insert_with(TimerData::new);
-
-        if timer.start_time.is_some() {
-            // Timer already running
-            tracing::warn!("Timer '{}' already started", name);
-            return;
-        }
-
-        timer.start_time = Some(Instant::now());
+        timer
+            .running
+            .entry(std::thread::current().id())
+            .or_default()
+            .push(Instant::now());
     }
 
     /// Stops a named timer and records the elapsed time
@@ -102,11 +112,16 @@ impl Timer {
         let mut timers = TIMER.timers.lock().unwrap();
 
         if let Some(timer) = timers.get_mut(name) {
-            if let Some(start_time) = timer.start_time {
+            let thread = std::thread::current().id();
+            let start_time = timer.running.get_mut(&thread).and_then(Vec::pop);
+            if let Some(start_time) = start_time {
                 let elapsed = start_time.elapsed();
-                timer.elapsed += elapsed;
+                // Nested measurements are already covered by the outermost one
+                if timer.running[&thread].is_empty() {
+                    timer.running.remove(&thread);
+                    timer.elapsed += elapsed;
+                }
                 timer.count += 1;
-                timer.start_time = None;
 
                 tracing::debug!(
                     "Timer '{}' stopped. Duration: {:?}, Total: {:?}, Count: {}",
@@ -123,6 +138,14 @@ impl Timer {
         }
     }
 
+    /// Returns the name of the most recently started timer
+    ///
+    /// This is the best-effort "current phase" of the analysis and is
+    /// primarily used to report where the driver was when it timed out.
+    pub fn current_phase() -> Option<String> {
+        TIMER.current_phase.lock().unwrap().clone()
+    }
+
     /// Records duration for a named timer without manually starting/stopping
     ///
     /// # Arguments
@@ -290,7 +313,73 @@ where
     F: FnOnce() -> T,
 {
     Timer::start(name);
-    let result = f();
-    Timer::stop(name);
-    result
+    // Stop on unwinding too, so a panicking measurement does not stay running
+    let _stop = StopOnDrop(name);
+    f()
+}
+
+struct StopOnDrop<'a>(&'a str);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        Timer::stop(self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn enable() {
+        *TIMER.enabled.lock().unwrap() = true;
+    }
+
+    fn totals(name: &str) -> (Duration, usize, bool) {
+        let timers = TIMER.timers.lock().unwrap();
+        let timer = &timers[name];
+        (timer.elapsed, timer.count, timer.running.is_empty())
+    }
+
+    #[test]
+    fn nested_measurements_are_not_counted_twice() {
+        enable();
+        let wall = Instant::now();
+        measure("test_nested", || {
+            thread::sleep(Duration::from_millis(10));
+            measure("test_nested", || thread::sleep(Duration::from_millis(10)));
+        });
+        let wall = wall.elapsed();
+
+        let (elapsed, count, idle) = totals("test_nested");
+        assert_eq!(count, 2);
+        assert!(idle);
+        assert!(elapsed >= Duration::from_millis(20), "{elapsed:?}");
+        assert!(elapsed <= wall, "{elapsed:?} > {wall:?}");
+    }
+
+    #[test]
+    fn concurrent_measurements_accumulate() {
+        enable();
+        let handles: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| measure("test_concurrent", || thread::sleep(Duration::from_millis(10)))))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let (elapsed, count, idle) = totals("test_concurrent");
+        assert_eq!(count, 4);
+        assert!(idle);
+        assert!(elapsed >= Duration::from_millis(40), "{elapsed:?}");
+    }
+
+    #[test]
+    fn panicking_measurement_is_stopped() {
+        enable();
+        let result = std::panic::catch_unwind(|| measure("test_panic", || panic!("measured panic")));
+        assert!(result.is_err());
+        assert_eq!(totals("test_panic").1, 1);
+        assert!(totals("test_panic").2);
+    }
 }
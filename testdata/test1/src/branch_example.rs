@@ -0,0 +1,23 @@
+// Branch example: one `if` followed by one `match`
+// Goal: blocks after the `if` have 1 constraint, blocks after the `match` have 2
+
+fn on_flag() -> u32 {
+    10
+}
+
+fn on_zero() -> u32 {
+    20
+}
+
+#[inline(never)]
+pub fn classify(x: u32, flag: bool) -> u32 {
+    let base = if flag { on_flag() } else { 1 };
+    match x {
+        0 => base.wrapping_add(on_zero()),
+        _ => base,
+    }
+}
+
+pub fn main() {
+    println!("Branch example: {} {}", classify(0, true), classify(3, false));
+}
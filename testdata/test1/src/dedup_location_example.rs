@@ -0,0 +1,11 @@
+// Dedup location example: one source line calls two instantiations of the same generic function
+// Goal: exercise --dedup-strategy location, which should keep a single entry for that line
+
+fn describe<T: std::fmt::Debug>(value: T) -> String {
+    format!("<{:?}>", value)
+}
+
+pub fn main() {
+    let (number, text) = (describe(1u8), describe("two"));
+    println!("Described: {} {}", number, text);
+}
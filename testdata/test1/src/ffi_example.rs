@@ -0,0 +1,19 @@
+// FFI example: one foreign call through an extern block, one Rust-defined `extern "C"` function
+// Goal: exercise the `abi` field and --ffi-only, which should keep only the non-Rust ABI calls
+
+extern "C" {
+    fn abs(input: i32) -> i32;
+}
+
+extern "C" fn c_callback(value: i32) -> i32 {
+    value + 1
+}
+
+fn rust_helper(value: i32) -> i32 {
+    value * 2
+}
+
+pub fn main() {
+    let magnitude = unsafe { abs(-3) };
+    println!("FFI example: {} {}", c_callback(magnitude), rust_helper(magnitude));
+}
@@ -0,0 +1,12 @@
+// Generic example: one generic function instantiated with three different types
+// Goal: --top-instantiations should report 3 instantiations of `describe`
+
+fn describe<T: std::fmt::Debug>(value: T) -> String {
+    format!("{value:?}")
+}
+
+pub fn main() {
+    println!("Generic example: {}", describe(1u32));
+    println!("Generic example: {}", describe("text"));
+    println!("Generic example: {}", describe(2.5f64));
+}
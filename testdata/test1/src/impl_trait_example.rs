@@ -0,0 +1,28 @@
+// impl Trait example: a function returns its concrete iterator as `impl Iterator`
+// Goal: `next` on the opaque return type should resolve to `Countdown::next`
+
+struct Countdown(u32);
+
+impl Iterator for Countdown {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.0 == 0 {
+            None
+        } else {
+            self.0 -= 1;
+            Some(self.0)
+        }
+    }
+}
+
+fn countdown(from: u32) -> impl Iterator<Item = u32> {
+    Countdown(from)
+}
+
+pub fn main() {
+    let mut remaining = countdown(3);
+    while let Some(value) = remaining.next() {
+        println!("impl Trait example: {}", value);
+    }
+}
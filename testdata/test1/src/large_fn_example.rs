@@ -0,0 +1,32 @@
+// Large function example: a macro expands into a function with many branches
+// Goal: exercise --max-mir-size, which should skip `dispatch_opcode` at a low threshold
+
+fn opcode_handler(value: u32) -> u32 {
+    value.wrapping_mul(31).wrapping_add(7)
+}
+
+macro_rules! opcode_dispatch {
+    ($name:ident, $($opcode:literal),* $(,)?) => {
+        pub fn $name(opcode: u32, value: u32) -> u32 {
+            match opcode {
+                $($opcode => opcode_handler(value.wrapping_add($opcode)),)*
+                _ => value,
+            }
+        }
+    };
+}
+
+opcode_dispatch!(
+    dispatch_opcode,
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30,
+    31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59,
+    60, 61, 62, 63,
+);
+
+pub fn main() {
+    let mut acc = 0;
+    for opcode in 0..70 {
+        acc = dispatch_opcode(opcode, acc);
+    }
+    println!("Large dispatch result: {}", acc);
+}
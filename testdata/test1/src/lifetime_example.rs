@@ -0,0 +1,39 @@
+// Lifetime example: methods of a struct with an early-bound lifetime parameter
+// Goal: the seed instance (with the impl's lifetime) and the called instance (with erased
+// lifetimes) must be treated as the same function and analyzed once
+
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Tokenizer { input, pos: 0 }
+    }
+
+    pub fn next_token(&mut self) -> Option<&'a str> {
+        let rest = self.input[self.pos..].trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let start = self.input.len() - rest.len();
+        let len = rest.find(' ').unwrap_or(rest.len());
+        self.pos = start + len;
+        Some(&self.input[start..start + len])
+    }
+}
+
+pub fn count_tokens(input: &str) -> usize {
+    let mut tokenizer = Tokenizer::new(input);
+    let mut count = 0;
+    while tokenizer.next_token().is_some() {
+        count += 1;
+    }
+    count
+}
+
+pub fn main() {
+    println!("Tokens: {}", count_tokens("a b"));
+    println!("Tokens: {}", count_tokens("c d e"));
+}
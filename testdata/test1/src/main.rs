@@ -3,12 +3,23 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::rc::Rc;
 
+mod branch_example;
 mod dyn_example;
+mod dedup_location_example;
 mod external_trait_example;
+mod ffi_example;
 mod fn_pointer_example;
 mod fn_trait_example;
+mod generic_example;
+mod impl_trait_example;
+mod large_fn_example;
+mod lifetime_example;
 mod manual_serde;
+mod panic_example;
+mod strategy_example;
+mod track_caller_example;
 mod ultra_simple_serde;
+mod visibility_example;
 // mod serde_import_only;
 
 // Generic trait - product interface
@@ -428,6 +439,39 @@ fn main() {
 
     println!("\n=== Drop Example ===");
     trigger_scope_drop();
+
+    println!("\n=== Large Function Example ===");
+    large_fn_example::main();
+
+    println!("\n=== Lifetime Example ===");
+    lifetime_example::main();
+
+    println!("\n=== Dedup Location Example ===");
+    dedup_location_example::main();
+
+    println!("\n=== Panic Example ===");
+    panic_example::main();
+
+    println!("\n=== FFI Example ===");
+    ffi_example::main();
+
+    println!("\n=== Visibility Example ===");
+    visibility_example::main();
+
+    println!("\n=== Generic Example ===");
+    generic_example::main();
+
+    println!("\n=== impl Trait Example ===");
+    impl_trait_example::main();
+
+    println!("\n=== Track Caller Example ===");
+    track_caller_example::main();
+
+    println!("\n=== Branch Example ===");
+    branch_example::main();
+
+    println!("\n=== Strategy Example ===");
+    strategy_example::main();
 }
 
 mod unsafe_test {
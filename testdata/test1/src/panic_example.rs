@@ -0,0 +1,36 @@
+// Panic example: one callee panics explicitly, the other cannot panic
+// Goal: exercise --annotate-panics, which should mark only `checked_divide` as may_panic
+
+fn checked_divide(dividend: u32, divisor: u32) -> u32 {
+    if divisor == 0 {
+        panic!("division by zero: {}", dividend);
+    }
+    dividend / divisor
+}
+
+fn wrapping_sum(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+// The call to `guarded_sink` is guarded by a bounds-check `Assert` and an `if` (`SwitchInt`)
+fn guarded_sink(value: u32) -> u32 {
+    value - 10
+}
+
+fn unreachable_branch(flag: bool) -> u32 {
+    if flag {
+        panic!("unreachable");
+    }
+    1
+}
+
+fn lookup(table: &[u32], index: usize) -> u32 {
+    let value = table[index];
+    if value > 10 { guarded_sink(value) } else { 0 }
+}
+
+pub fn main() {
+    println!("Panic example: {} {}", checked_divide(10, 2), wrapping_sum(u32::MAX, 1));
+    println!("Lookup: {}", lookup(&[1, 20, 3], 1));
+    println!("Branch: {}", unreachable_branch(false));
+}
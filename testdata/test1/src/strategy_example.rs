@@ -0,0 +1,69 @@
+// Strategy example: helpers that call their argument through Fn / FnMut / FnOnce bounds
+// Goal: each bound call resolves to the closure body or fn item passed in, not the trait method
+
+struct Item {
+    price: f64,
+    quantity: u32,
+}
+
+fn unit_price(item: &Item) -> f64 {
+    item.price
+}
+
+fn round_total(total: f64) -> f64 {
+    total.round()
+}
+
+fn value_with_strategy<F>(items: &[Item], strategy: F) -> f64
+where
+    F: Fn(&Item) -> f64,
+{
+    let mut total = 0.0;
+    for item in items {
+        total += strategy(item);
+    }
+    total
+}
+
+fn visit_items<F>(items: &[Item], mut visit: F)
+where
+    F: FnMut(&Item),
+{
+    for item in items {
+        visit(item);
+    }
+}
+
+fn finish_with<F>(total: f64, finish: F) -> f64
+where
+    F: FnOnce(f64) -> f64,
+{
+    finish(total)
+}
+
+pub fn main() {
+    let items = vec![
+        Item {
+            price: 2.5,
+            quantity: 4,
+        },
+        Item {
+            price: 1.25,
+            quantity: 2,
+        },
+    ];
+
+    let by_quantity = value_with_strategy(&items, |item| item.price * item.quantity as f64);
+    let by_unit = value_with_strategy(&items, unit_price);
+
+    let mut quantity = 0;
+    visit_items(&items, |item| quantity += item.quantity);
+
+    // A `Fn` closure and a fn item called through `FnOnce`
+    let discounted = finish_with(by_quantity, |total| total * 0.9);
+    let rounded = finish_with(by_unit, round_total);
+    println!(
+        "Strategy example: {} {} {} {}",
+        by_quantity, quantity, discounted, rounded
+    );
+}
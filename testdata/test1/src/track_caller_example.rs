@@ -0,0 +1,14 @@
+// Track caller example: a `#[track_caller]` function called directly and through a fn pointer
+// Goal: the reified function and the function itself should collapse to one callee node
+
+#[track_caller]
+fn located(value: u32) -> u32 {
+    std::panic::Location::caller().line() + value
+}
+
+pub fn main() {
+    let direct = located(1);
+    let reified: fn(u32) -> u32 = located;
+    let indirect = reified(2);
+    println!("Track caller example: {} {}", direct, indirect);
+}
@@ -0,0 +1,24 @@
+// Visibility example: one pub, one pub(crate) and one private function in a nested module
+// Goal: exercise --summarize-by-visibility, which should report all three visibility categories
+
+mod inner {
+    pub fn public_api(value: u32) -> u32 {
+        private_detail(value) + 1
+    }
+
+    pub(crate) fn crate_helper(value: u32) -> u32 {
+        value * 3
+    }
+
+    fn private_detail(value: u32) -> u32 {
+        value / 2
+    }
+}
+
+pub fn main() {
+    println!(
+        "Visibility example: {} {}",
+        inner::public_api(8),
+        inner::crate_helper(2)
+    );
+}
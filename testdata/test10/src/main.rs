@@ -0,0 +1,38 @@
+// Impls bridging to other crates: a foreign trait for a local type and a local trait for a foreign type
+// Goal: --no-extern-impls leaves out `<Celsius as Display>::fmt` and `<String as Describe>::describe`
+
+use std::fmt;
+
+struct Celsius(f64);
+
+impl Celsius {
+    fn new(degrees: f64) -> Self {
+        Celsius(degrees)
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} C", round(self.0))
+    }
+}
+
+trait Describe {
+    fn describe(&self) -> usize;
+}
+
+impl Describe for String {
+    fn describe(&self) -> usize {
+        self.len()
+    }
+}
+
+fn main() {
+    let temperature = Celsius::new(21.46);
+    let text = temperature.to_string();
+    let _length = text.describe();
+}
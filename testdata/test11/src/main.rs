@@ -0,0 +1,36 @@
+// A tiny always-inlined helper next to a regular one, both calling the same leaf
+// Goal: --merge-inline turns `main -> clamp_small -> checked_add` into `main -> checked_add`
+// while `main -> accumulate -> checked_add` stays as it is, and the always-inlined leaf
+// `flip_low_bit`, which calls nothing, stays a callee of `main`
+
+fn checked_add(a: u32, b: u32) -> u32 {
+    a.checked_add(b).unwrap_or(u32::MAX)
+}
+
+#[inline(always)]
+fn clamp_small(value: u32) -> u32 {
+    if value > 100 {
+        checked_add(100, 0)
+    } else {
+        checked_add(value, 0)
+    }
+}
+
+#[inline(always)]
+fn flip_low_bit(value: u32) -> u32 {
+    value ^ 1
+}
+
+fn accumulate(values: &[u32]) -> u32 {
+    let mut total = 0;
+    for value in values {
+        total = checked_add(total, *value);
+    }
+    total
+}
+
+fn main() {
+    let small = clamp_small(42);
+    let total = accumulate(&[flip_low_bit(small), 7, 9]);
+    println!("{total}");
+}
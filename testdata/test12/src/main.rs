@@ -0,0 +1,19 @@
+// Concrete and generic callers naming a generic type's `Default` impl through UFCS
+// Goal: `<HashMap<String, u32> as Default>::default()` resolves to the concrete
+// `HashMap` instance instead of falling back to a non-instance
+
+use std::collections::HashMap;
+
+fn empty_scores() -> HashMap<String, u32> {
+    <HashMap<String, u32> as Default>::default()
+}
+
+fn empty_list<T>() -> Vec<T> {
+    <Vec<T> as Default>::default()
+}
+
+fn main() {
+    let scores = empty_scores();
+    let names: Vec<String> = empty_list();
+    println!("{} {}", scores.len(), names.len());
+}
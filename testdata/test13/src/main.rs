@@ -0,0 +1,21 @@
+// A function pointer parameter that cannot be traced inside the function calling it
+// Goal: the call in `apply` is resolved to the two address-taken functions of its
+// signature, i.e. two fnptr-candidate edges from a single candidate call site
+
+fn flip(value: u32) -> u32 {
+    value ^ 0xff
+}
+
+fn mask(value: u32) -> u32 {
+    value & 0x0f
+}
+
+fn apply(op: fn(u32) -> u32, value: u32) -> u32 {
+    op(value)
+}
+
+fn main() {
+    let flipped = apply(flip, 21);
+    let masked = apply(mask, flipped);
+    std::hint::black_box(masked);
+}
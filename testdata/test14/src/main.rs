@@ -0,0 +1,13 @@
+// Closures nested in a generic function
+// Goal: closures are named after their enclosing function, with its generic args
+fn pair_up<T: Copy>(value: T) -> (T, T) {
+    let outer = |v: T| {
+        let inner = |w: T| (w, v);
+        inner(v)
+    };
+    outer(value)
+}
+
+fn main() {
+    std::hint::black_box(pair_up(7u8));
+}
@@ -0,0 +1,11 @@
+// Build script example: the call graph of build.rs should be analyzed with --include-build-scripts
+
+fn generated_value_helper() -> u32 {
+    6 * 7
+}
+
+fn main() {
+    let value = generated_value_helper();
+    println!("cargo:rustc-env=GENERATED_VALUE={}", value);
+    println!("cargo:rerun-if-changed=build.rs");
+}
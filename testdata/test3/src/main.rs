@@ -0,0 +1,3 @@
+fn main() {
+    println!("Generated value: {}", env!("GENERATED_VALUE"));
+}
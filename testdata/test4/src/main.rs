@@ -0,0 +1,14 @@
+// Config file example: `cg4rs.toml` next to Cargo.toml enables JSON output
+// and a default `--find-callers` target, which the command line can override
+
+fn config_helper(value: u32) -> u32 {
+    value + 1
+}
+
+fn other_helper(value: u32) -> u32 {
+    value * 2
+}
+
+fn main() {
+    println!("{}", other_helper(config_helper(1)));
+}
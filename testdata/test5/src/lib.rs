@@ -0,0 +1,12 @@
+//! No-std example: a firmware-style library for cross-compilation analysis
+//! Goal: exercise --target, e.g. `call-cg4rs --target thumbv7em-none-eabi --json-output`
+
+#![no_std]
+
+fn scale(sample: u16, gain: u16) -> u32 {
+    u32::from(sample).wrapping_mul(u32::from(gain))
+}
+
+pub fn process_samples(samples: &[u16], gain: u16) -> u32 {
+    samples.iter().fold(0u32, |acc, &sample| acc.wrapping_add(scale(sample, gain)))
+}
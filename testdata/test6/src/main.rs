@@ -0,0 +1,31 @@
+// Feature-gated calls for testing --feature-combo
+
+fn common_step(x: u32) -> u32 {
+    x + 1
+}
+
+#[cfg(feature = "fast")]
+fn fast_step(x: u32) -> u32 {
+    x << 1
+}
+
+#[cfg(feature = "verbose")]
+fn log_step(x: u32) {
+    println!("step: {x}");
+}
+
+fn run(x: u32) -> u32 {
+    let y = common_step(x);
+
+    #[cfg(feature = "fast")]
+    let y = fast_step(y);
+
+    #[cfg(feature = "verbose")]
+    log_step(y);
+
+    y
+}
+
+fn main() {
+    println!("{}", run(1));
+}
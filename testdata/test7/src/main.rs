@@ -0,0 +1,35 @@
+// Call depth example: the deepest chain is main -> stage1 -> stage2 -> stage3 -> stage4
+// Goal: --max-depth-stat reports depth 5 despite the shorter branch and the recursion
+
+fn stage4(x: u32) -> u32 {
+    x * 2
+}
+
+fn stage3(x: u32) -> u32 {
+    stage4(x) + 3
+}
+
+fn stage2(x: u32) -> u32 {
+    stage3(x) + 2
+}
+
+fn stage1(x: u32) -> u32 {
+    stage2(x) + 1
+}
+
+fn shortcut(x: u32) -> u32 {
+    stage4(x)
+}
+
+fn countdown(n: u32) -> u32 {
+    if n == 0 {
+        0
+    } else {
+        countdown(n - 1)
+    }
+}
+
+fn main() {
+    // No calls into std, whose chains would be deeper
+    let _total = stage1(1) + shortcut(2) + countdown(3);
+}
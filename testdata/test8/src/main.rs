@@ -0,0 +1,15 @@
+// Proc-macro example: `#[derive(Describe)]` comes from the local test8_derive crate
+// Goal: --analyze-proc-macros also analyzes the functions of the derive itself
+
+use test8_derive::Describe;
+
+trait Describe {
+    fn describe() -> &'static str;
+}
+
+#[derive(Describe)]
+struct Widget;
+
+fn main() {
+    let _name = Widget::describe();
+}
@@ -0,0 +1,24 @@
+use proc_macro::{TokenStream, TokenTree};
+
+/// Name of the type following the `struct`, `enum` or `union` keyword
+fn type_name(input: TokenStream) -> Option<String> {
+    let mut tokens = input.into_iter();
+    while let Some(token) = tokens.next() {
+        if let TokenTree::Ident(ident) = &token {
+            if matches!(ident.to_string().as_str(), "struct" | "enum" | "union") {
+                return tokens.next().map(|name| name.to_string());
+            }
+        }
+    }
+    None
+}
+
+fn render_impl(name: &str) -> String {
+    format!("impl Describe for {name} {{ fn describe() -> &'static str {{ \"{name}\" }} }}")
+}
+
+#[proc_macro_derive(Describe)]
+pub fn derive_describe(input: TokenStream) -> TokenStream {
+    let name = type_name(input).expect("Describe can only be derived for named types");
+    render_impl(&name).parse().expect("generated impl should parse")
+}
@@ -0,0 +1,47 @@
+// Tail call example: `become` is lowered to a `TailCall` terminator instead of a `Call`
+// Goal: tail calls appear in the call graph like ordinary calls, a function pointer
+// returned by a tail call is resolved by its signature, and a tail-called panicking
+// function is annotated by `--annotate-panics`
+
+#![allow(incomplete_features)]
+#![feature(explicit_tail_calls)]
+
+fn finish(n: u64, acc: u64) -> u64 {
+    acc + n
+}
+
+fn sum_to(n: u64, acc: u64) -> u64 {
+    if n == 0 {
+        become finish(n, acc);
+    }
+    become sum_to(n - 1, acc + n)
+}
+
+fn pick_finish() -> fn(u64, u64) -> u64 {
+    finish
+}
+
+// Returns whatever `pick_finish` returns, which the return summary cannot follow
+fn pick_via_tail() -> fn(u64, u64) -> u64 {
+    become pick_finish()
+}
+
+fn run_picked() -> u64 {
+    let picked = pick_via_tail();
+    picked(1, 2)
+}
+
+fn fail(code: u64) -> u64 {
+    panic!("failed with {code}")
+}
+
+fn fail_via_tail(code: u64) -> u64 {
+    become fail(code)
+}
+
+fn main() {
+    let total = sum_to(10, 0) + run_picked();
+    if total > 100 {
+        fail_via_tail(total);
+    }
+}
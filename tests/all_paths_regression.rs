@@ -0,0 +1,47 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+/// Paths to test7's `stage4` reported with the given extra arguments
+fn stage4_paths(extra_args: &[&str]) -> (Vec<String>, String) {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-all-paths");
+
+    let mut args = vec!["--all-paths-to", "stage4"];
+    args.extend_from_slice(extra_args);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    let report = fs::read_to_string(output_dir.join("test7-all-paths-stage4.txt")).expect("failed to read paths");
+    let mut paths: Vec<String> = report
+        .lines()
+        .filter(|line| line.starts_with("main"))
+        .map(str::to_string)
+        .collect();
+    paths.sort();
+    (paths, report)
+}
+
+#[test]
+fn all_simple_paths_from_main_are_listed() {
+    let (paths, report) = stage4_paths(&[]);
+    assert_eq!(
+        paths,
+        [
+            "main -> shortcut -> stage4",
+            "main -> stage1 -> stage2 -> stage3 -> stage4"
+        ],
+        "{report}"
+    );
+    assert!(!report.contains("stopped early"), "{report}");
+}
+
+#[test]
+fn path_length_and_count_are_bounded() {
+    let (paths, report) = stage4_paths(&["--max-path-len", "2"]);
+    assert_eq!(paths, ["main -> shortcut -> stage4"], "{report}");
+
+    let (paths, report) = stage4_paths(&["--max-paths", "1"]);
+    assert_eq!(paths.len(), 1, "{report}");
+    assert!(report.contains("stopped early"), "{report}");
+}
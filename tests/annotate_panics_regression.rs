@@ -0,0 +1,31 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn callee_may_panic(callgraph: &Value, callee: &str) -> Option<bool> {
+    callees_of(callgraph, "panic_example::main")
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some(callee))
+        .and_then(|call| call["may_panic"].as_bool())
+}
+
+#[test]
+fn callee_edges_are_annotated_with_may_panic() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-annotate-panics");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--annotate-panics"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(
+        callee_may_panic(&callgraph, "panic_example::checked_divide"),
+        Some(true),
+        "checked_divide calls panic_fmt and has a division assert"
+    );
+    assert_eq!(
+        callee_may_panic(&callgraph, "panic_example::wrapping_sum"),
+        Some(false),
+        "wrapping_sum cannot panic"
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, run_cargo_cg4rs, unique_output_dir};
+use serde_json::Value;
+
+#[test]
+fn binary_output_round_trips_through_load() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-binary-output");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--binary-output"]);
+
+    let binary_path = output_dir.join("test1-callgraph.bin");
+    let bytes = std::fs::read(&binary_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", binary_path.display()));
+    assert_eq!(
+        bytes.first(),
+        Some(&1),
+        "binary output should start with the model version"
+    );
+
+    let binary_path = binary_path.to_str().expect("binary path is not valid utf-8");
+    let output = run_cargo_cg4rs(&["load", binary_path, "--format", "json"]);
+    assert!(output.status.success(), "cargo cg4rs load should succeed");
+    let model: Value = serde_json::from_slice(&output.stdout).expect("load --format json should print JSON");
+    assert_eq!(model["crate_name"].as_str(), Some("test1"));
+    assert!(
+        model["call_sites"]
+            .as_array()
+            .expect("call_sites should be an array")
+            .iter()
+            .any(|call| call["caller_path"] == "dedup_location_example::main"
+                && call["callee_path"] == "dedup_location_example::describe"),
+        "loaded model should contain main -> describe"
+    );
+
+    let output = run_cargo_cg4rs(&["load", binary_path]);
+    assert!(output.status.success(), "cargo cg4rs load should succeed");
+    let text = String::from_utf8(output.stdout).expect("text output should be utf-8");
+    assert!(text.starts_with("Call Graph:"), "default load format should be text");
+}
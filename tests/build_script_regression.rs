@@ -0,0 +1,43 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+#[test]
+fn build_scripts_are_analyzed_and_tagged_when_requested() {
+    let manifest_path = manifest_path("testdata/test3/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-build-script");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--include-build-scripts", "--json-output", "--per-crate-output"],
+    );
+
+    let build_script = read_json(&output_dir.join("test3-build_script_build-build_script_build-callgraph.json"));
+    let helper = callees_of(&build_script["call_graph"], "main")
+        .into_iter()
+        .find(|callee| callee["path"].as_str() == Some("generated_value_helper"))
+        .expect("build script main should call generated_value_helper");
+    assert_eq!(helper["is_build_script"].as_bool(), Some(true));
+}
+
+#[test]
+fn build_scripts_are_skipped_by_default() {
+    let manifest_path = manifest_path("testdata/test3/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-build-script-default");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &[]);
+
+    assert!(output_dir.join("test3-callgraph.txt").exists());
+    assert!(
+        !fs::read_dir(&output_dir)
+            .expect("failed to read output dir")
+            .any(|entry| entry
+                .expect("failed to read dir entry")
+                .file_name()
+                .to_string_lossy()
+                .contains("build_script_build")),
+        "build scripts should not be analyzed without --include-build-scripts"
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_capturing, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn chain_of_depth_five_is_identified() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-depth");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--max-depth-stat"]);
+
+    let stats = read_json(&output_dir.join("test7-callgraph-stats.json"));
+    assert_eq!(stats["max_call_depth"].as_u64(), Some(5), "{stats:#}");
+    let chain: Vec<&str> = stats["deepest_chain"]
+        .as_array()
+        .expect("deepest_chain should be an array")
+        .iter()
+        .map(|func| func.as_str().expect("chain entries should be paths"))
+        .collect();
+    assert_eq!(chain, ["main", "stage1", "stage2", "stage3", "stage4"]);
+}
+
+#[test]
+fn max_depth_is_omitted_by_default() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-depth-default");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let stats = read_json(&output_dir.join("test7-callgraph-stats.json"));
+    assert!(stats.get("max_call_depth").is_none(), "{stats:#}");
+    assert!(stats.get("deepest_chain").is_none(), "{stats:#}");
+}
+
+#[test]
+fn stack_usage_limit_warns_when_exceeded() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+
+    let output_dir = unique_output_dir("cg4rs-stack-limit-exceeded");
+    let output = run_call_cg4rs_capturing(&manifest_path, &output_dir, &["--stack-usage-limit", "4"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Maximum call depth 5 exceeds --stack-usage-limit 4"),
+        "{stderr}"
+    );
+
+    let output_dir = unique_output_dir("cg4rs-stack-limit-ok");
+    let output = run_call_cg4rs_capturing(&manifest_path, &output_dir, &["--stack-usage-limit", "5"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("exceeds --stack-usage-limit"), "{stderr}");
+}
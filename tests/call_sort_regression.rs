@@ -0,0 +1,41 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+
+/// Callee lines of `strategy_example::main` in the text call graph of test1 run with `args`
+fn strategy_main_callees(prefix: &str, args: &[&str]) -> Vec<String> {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, args);
+    let txt_path = output_dir.join("test1-callgraph.txt");
+    let text =
+        std::fs::read_to_string(&txt_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", txt_path.display()));
+    text.lines()
+        .skip_while(|line| *line != "Function: strategy_example::main")
+        .skip(1)
+        .take_while(|line| line.starts_with("  -> "))
+        .map(str::to_string)
+        .collect()
+}
+
+fn first_call(callees: &[String], callee: &str) -> usize {
+    callees
+        .iter()
+        .position(|line| line.starts_with(&format!("  -> strategy_example::{callee}")))
+        .unwrap_or_else(|| panic!("{callee} should be called, got {callees:#?}"))
+}
+
+#[test]
+fn calls_are_sorted_by_name_by_default() {
+    let callees = strategy_main_callees("cg4rs-sort-name", &["--no-dedup"]);
+    assert!(first_call(&callees, "finish_with") < first_call(&callees, "value_with_strategy"));
+    assert!(first_call(&callees, "value_with_strategy") < first_call(&callees, "visit_items"));
+}
+
+#[test]
+fn bb_order_follows_the_mir() {
+    let callees = strategy_main_callees("cg4rs-sort-bb-order", &["--no-dedup", "--sort", "bb-order"]);
+    // value_with_strategy -> visit_items -> finish_with, as in the source
+    assert!(first_call(&callees, "value_with_strategy") < first_call(&callees, "visit_items"));
+    assert!(first_call(&callees, "visit_items") < first_call(&callees, "finish_with"));
+}
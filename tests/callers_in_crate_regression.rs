@@ -0,0 +1,42 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+fn is_std_path(path: &str) -> bool {
+    ["std::", "core::", "alloc::", "<std::", "<core::", "<alloc::"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+#[test]
+fn callers_in_crate_keeps_only_local_callers_but_all_callees() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-callers-in-crate");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--callers-in-crate"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(!entries.is_empty());
+
+    for entry in entries {
+        let caller_path = entry["caller"]["path"]
+            .as_str()
+            .expect("caller path should be a string");
+        assert!(
+            !is_std_path(caller_path),
+            "caller {caller_path} should belong to the local crate"
+        );
+    }
+
+    assert!(
+        entries.iter().any(|entry| {
+            entry["callee"]
+                .as_array()
+                .expect("callee should be an array")
+                .iter()
+                .any(|callee| callee["path"].as_str().is_some_and(is_std_path))
+        }),
+        "outbound edges into std should be kept"
+    );
+}
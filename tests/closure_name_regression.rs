@@ -0,0 +1,49 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+/// Rendered names of the callees of the callers whose name is `caller_name`
+fn callee_names(callgraph: &Value, caller_name: &str) -> Vec<String> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter(|entry| entry["caller"]["name"].as_str() == Some(caller_name))
+        .flat_map(|entry| entry["callee"].as_array().expect("callee should be an array"))
+        .map(|callee| callee["name"].as_str().expect("callee name").to_string())
+        .collect()
+}
+
+#[test]
+fn closures_keep_the_generic_args_of_their_enclosing_function() {
+    let manifest_path = manifest_path("testdata/test14/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-closure-name");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let outer = "pair_up::<u8>::{closure@main.rs:4}";
+    assert!(
+        callee_names(&callgraph, "pair_up::<u8>").contains(&outer.to_string()),
+        "{callgraph}"
+    );
+    assert!(
+        callee_names(&callgraph, outer).contains(&format!("{outer}::{{closure@main.rs:5}}")),
+        "{callgraph}"
+    );
+}
+
+#[test]
+fn closures_drop_generic_args_with_without_args() {
+    let manifest_path = manifest_path("testdata/test14/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-closure-name-without-args");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--without-args"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(
+        callee_names(&callgraph, "pair_up").contains(&"pair_up::{closure@main.rs:4}".to_string()),
+        "{callgraph}"
+    );
+}
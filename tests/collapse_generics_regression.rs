@@ -0,0 +1,56 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+
+/// Lines of the text call graph of test1 run with `args`
+fn text_call_graph(prefix: &str, args: &[&str]) -> String {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, args);
+    let txt_path = output_dir.join("test1-callgraph.txt");
+    std::fs::read_to_string(&txt_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", txt_path.display()))
+}
+
+/// Callee lines of the first block of `caller`
+fn callee_lines<'a>(text: &'a str, caller: &str) -> Vec<&'a str> {
+    text.lines()
+        .skip_while(|line| *line != format!("Function: {caller}"))
+        .skip(1)
+        .take_while(|line| line.starts_with("  -> "))
+        .collect()
+}
+
+#[test]
+fn instances_are_listed_separately_by_default() {
+    let text = text_call_graph("cg4rs-collapse-generics-default", &[]);
+    let total_value_callers = text
+        .lines()
+        .filter(|line| line.starts_with("Function: DataStore::<") && line.ends_with(">::total_value"))
+        .count();
+    assert_eq!(
+        total_value_callers, 2,
+        "DataStore<Electronics> and DataStore<Clothing> are separate callers"
+    );
+}
+
+#[test]
+fn collapse_generics_merges_instances() {
+    let text = text_call_graph("cg4rs-collapse-generics", &["--collapse-generics"]);
+    let total_value_callers = text
+        .lines()
+        .filter(|line| line.starts_with("Function: DataStore::<") && line.ends_with(">::total_value"))
+        .collect::<Vec<_>>();
+    assert_eq!(total_value_callers, ["Function: DataStore::<T>::total_value"]);
+
+    // The calls of both instances are unioned into one edge per callee
+    let callees = callee_lines(&text, "DataStore::<T>::total_value");
+    assert!(!callees.is_empty(), "total_value should have callees");
+    let mut callee_names: Vec<&str> = callees
+        .iter()
+        .map(|line| line.trim_start_matches("  -> ").split(" [").next().unwrap_or_default())
+        .collect();
+    let edges = callee_names.len();
+    callee_names.sort_unstable();
+    callee_names.dedup();
+    assert_eq!(callee_names.len(), edges, "{callees:#?}");
+}
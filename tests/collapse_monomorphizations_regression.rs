@@ -0,0 +1,53 @@
+mod common;
+
+use common::{all_callees, manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir};
+use serde_json::Value;
+
+const TOTAL_VALUE: &str = "DataStore::<T>::total_value";
+
+/// Number of caller entries (one per analyzed instance) for `path`
+fn caller_entries(callgraph: &Value, path: &str) -> usize {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter(|entry| entry["caller"]["path"].as_str() == Some(path))
+        .count()
+}
+
+#[test]
+fn each_monomorphization_is_expanded_by_default() {
+    let callgraph = read_json(&test1_json_output().join("callgraph.json"));
+    assert_eq!(
+        caller_entries(&callgraph, TOTAL_VALUE),
+        2,
+        "DataStore<Electronics> and DataStore<Clothing> are analyzed separately"
+    );
+}
+
+#[test]
+fn collapse_monomorphizations_expands_generic_bodies_once() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-collapse");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--collapse-monomorphizations"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(caller_entries(&callgraph, TOTAL_VALUE), 1);
+
+    // Both monomorphizations are still reached as callees
+    let callee_names: Vec<&str> = all_callees(&callgraph)
+        .into_iter()
+        .filter(|call| call["path"].as_str() == Some(TOTAL_VALUE))
+        .filter_map(|call| call["name"].as_str())
+        .collect();
+    assert!(
+        callee_names.iter().any(|name| name.contains("Electronics"))
+            && callee_names.iter().any(|name| name.contains("Clothing")),
+        "edges to every monomorphization should be kept, got {callee_names:?}"
+    );
+}
@@ -17,8 +17,7 @@ pub fn unique_output_dir(prefix: &str) -> PathBuf {
     std::env::temp_dir().join(format!("{prefix}-{nanos}"))
 }
 
-pub fn run_call_cg4rs_with_args(manifest_path: &Path, output_dir: &Path, extra_args: &[&str]) {
-    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+fn call_cg4rs_command(manifest_path: &Path, output_dir: &Path, extra_args: &[&str]) -> Command {
     let call_cg4rs = PathBuf::from(env!("CARGO_BIN_EXE_call-cg4rs"));
     let bins_dir = call_cg4rs
         .parent()
@@ -30,7 +29,8 @@ pub fn run_call_cg4rs_with_args(manifest_path: &Path, output_dir: &Path, extra_a
 
     fs::create_dir_all(output_dir).expect("failed to create test output dir");
 
-    let status = Command::new(&call_cg4rs)
+    let mut command = Command::new(&call_cg4rs);
+    command
         .env("PATH", prefixed_path)
         .args([
             "--manifest-path",
@@ -38,13 +38,65 @@ pub fn run_call_cg4rs_with_args(manifest_path: &Path, output_dir: &Path, extra_a
             "--output-dir",
             output_dir.to_str().expect("output dir is not valid utf-8"),
         ])
-        .args(extra_args)
+        .args(extra_args);
+    command
+}
+
+pub fn run_call_cg4rs_with_args(manifest_path: &Path, output_dir: &Path, extra_args: &[&str]) {
+    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+    let status = call_cg4rs_command(manifest_path, output_dir, extra_args)
+        .status()
+        .expect("failed to run call-cg4rs");
+
+    assert!(status.success(), "call-cg4rs should exit successfully");
+}
+
+/// Like `run_call_cg4rs_with_args`, with additional environment variables
+pub fn run_call_cg4rs_with_env(manifest_path: &Path, output_dir: &Path, extra_args: &[&str], envs: &[(&str, &str)]) {
+    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+    let status = call_cg4rs_command(manifest_path, output_dir, extra_args)
+        .envs(envs.iter().copied())
         .status()
         .expect("failed to run call-cg4rs");
 
     assert!(status.success(), "call-cg4rs should exit successfully");
 }
 
+/// Like `run_call_cg4rs_with_args`, but captures stdout and stderr
+pub fn run_call_cg4rs_capturing(manifest_path: &Path, output_dir: &Path, extra_args: &[&str]) -> std::process::Output {
+    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+    let output = call_cg4rs_command(manifest_path, output_dir, extra_args)
+        .output()
+        .expect("failed to run call-cg4rs");
+
+    assert!(output.status.success(), "call-cg4rs should exit successfully");
+    output
+}
+
+/// Like `run_call_cg4rs_with_args`, but returns the exit code instead of asserting success
+pub fn run_call_cg4rs_exit_code(manifest_path: &Path, output_dir: &Path, extra_args: &[&str]) -> Option<i32> {
+    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+    call_cg4rs_command(manifest_path, output_dir, extra_args)
+        .status()
+        .expect("failed to run call-cg4rs")
+        .code()
+}
+
+/// Like `run_call_cg4rs_exit_code`, with additional environment variables
+pub fn run_call_cg4rs_exit_code_with_env(
+    manifest_path: &Path,
+    output_dir: &Path,
+    extra_args: &[&str],
+    envs: &[(&str, &str)],
+) -> Option<i32> {
+    let _guard = CALL_CG4RS_LOCK.lock().expect("call-cg4rs lock poisoned");
+    call_cg4rs_command(manifest_path, output_dir, extra_args)
+        .envs(envs.iter().copied())
+        .status()
+        .expect("failed to run call-cg4rs")
+        .code()
+}
+
 pub fn run_call_cg4rs(manifest_path: &Path, output_dir: &Path, find_callers: &str) {
     run_call_cg4rs_with_args(
         manifest_path,
@@ -58,8 +110,77 @@ pub fn read_json(path: &Path) -> Value {
     serde_json::from_str(&content).unwrap_or_else(|e| panic!("failed to parse {} as json: {e}", path.display()))
 }
 
+/// Callees of every call site of `caller_path` in callgraph.json, empty if it is not a caller
+pub fn callees_of<'a>(callgraph: &'a Value, caller_path: &str) -> Vec<&'a Value> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter(|entry| entry["caller"]["path"].as_str() == Some(caller_path))
+        .flat_map(|entry| entry["callee"].as_array().expect("callee should be an array"))
+        .collect()
+}
+
+/// Callees of every call site in callgraph.json
+pub fn all_callees(callgraph: &Value) -> Vec<&Value> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .flat_map(|entry| entry["callee"].as_array().expect("callee should be an array"))
+        .collect()
+}
+
+/// Callers and callees of every call site in callgraph.json
+pub fn all_functions(callgraph: &Value) -> Vec<&Value> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .flat_map(|entry| {
+            let callees = entry["callee"].as_array().expect("callee should be an array");
+            std::iter::once(&entry["caller"]).chain(callees)
+        })
+        .collect()
+}
+
+/// Sorted callee paths of `caller_path` in callgraph.json, empty if it is not a caller
+pub fn callee_paths(callgraph: &Value, caller_path: &str) -> Vec<String> {
+    let mut paths: Vec<String> = callees_of(callgraph, caller_path)
+        .into_iter()
+        .map(|callee| callee["path"].as_str().expect("callee path").to_string())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Def paths of all callees in callgraph.json
+pub fn all_callee_paths(callgraph: &Value) -> Vec<String> {
+    all_callees(callgraph)
+        .into_iter()
+        .map(|callee| callee["path"].as_str().expect("callee path").to_string())
+        .collect()
+}
+
+/// Same as `sanitize_path_for_filename` in the analysis, which names the `--find-callers` outputs
+pub fn sanitize_path_for_filename(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = match c {
+            ':' | '<' | '>' | '/' | '\\' | '"' | '|' | '?' | '*' => '-',
+            c => c,
+        };
+        if c == '-' && result.ends_with('-') {
+            continue;
+        }
+        result.push(c);
+    }
+    result.trim_matches('-').to_string()
+}
+
 pub fn read_callers_json(output_dir: &Path, target_name: &str) -> Value {
-    read_json(&output_dir.join(format!("callers-{target_name}.json")))
+    let file_stem = sanitize_path_for_filename(&format!("callers-{target_name}"));
+    read_json(&output_dir.join(format!("{file_stem}.json")))
 }
 
 pub fn read_public_exposure_json(output_dir: &Path, crate_name: &str) -> Value {
@@ -90,3 +211,35 @@ impl SharedOutput {
         self.cell.get_or_init(init)
     }
 }
+
+static TEST1_JSON_OUTPUT: SharedOutput = SharedOutput::new();
+
+/// Output directory of a plain `--json-output` run on testdata/test1, shared by the tests of a test binary
+pub fn test1_json_output() -> &'static PathBuf {
+    TEST1_JSON_OUTPUT.get_or_init(|| {
+        let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+        let output_dir = unique_output_dir("cg4rs-test1-json");
+        run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+        output_dir
+    })
+}
+
+/// Run `cargo cg4rs <args>` with the freshly built binaries first in `PATH`
+pub fn run_cargo_cg4rs(args: &[&str]) -> std::process::Output {
+    let cargo_cg4rs = PathBuf::from(env!("CARGO_BIN_EXE_cargo-cg4rs"));
+    let bins_dir = cargo_cg4rs
+        .parent()
+        .expect("cargo-cg4rs binary should have a parent directory");
+    let current_path = std::env::var_os("PATH").expect("PATH should be set");
+    let prefixed_path =
+        std::env::join_paths(std::iter::once(bins_dir.to_path_buf()).chain(std::env::split_paths(&current_path)))
+            .expect("failed to construct PATH");
+
+    Command::new("cargo")
+        .env("PATH", prefixed_path)
+        .current_dir(repo_root())
+        .arg("cg4rs")
+        .args(args)
+        .output()
+        .expect("failed to run cargo cg4rs")
+}
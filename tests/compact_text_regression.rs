@@ -0,0 +1,53 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+/// Split a `caller -> callee [N]` line into its parts
+fn parse_line(line: &str) -> (&str, &str, usize) {
+    let (caller, rest) = line
+        .split_once(" -> ")
+        .unwrap_or_else(|| panic!("line without ` -> `: {line}"));
+    let (callee, constraints) = rest
+        .rsplit_once(" [")
+        .unwrap_or_else(|| panic!("line without ` [N]`: {line}"));
+    let constraints = constraints
+        .strip_suffix(']')
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| panic!("line without a constraint count: {line}"));
+    assert!(!caller.is_empty() && !callee.is_empty(), "{line}");
+    (caller, callee, constraints)
+}
+
+#[test]
+fn compact_text_has_one_call_site_per_line() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-compact-text");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--compact-text", "--find-callers", "stage4"],
+    );
+
+    let call_graph = fs::read_to_string(output_dir.join("test7-callgraph.txt")).expect("failed to read call graph");
+    let edges: Vec<(&str, &str, usize)> = call_graph.lines().map(parse_line).collect();
+    assert!(edges.contains(&("stage1", "stage2", 0)), "{call_graph}");
+    assert!(edges.contains(&("main", "shortcut", 0)), "{call_graph}");
+    assert!(
+        !call_graph.contains("Call Graph:"),
+        "compact output should have no header"
+    );
+
+    let callers = fs::read_to_string(output_dir.join("callers-stage4.txt")).expect("failed to read callers");
+    let mut callers: Vec<&str> = callers
+        .lines()
+        .map(parse_line)
+        .map(|(caller, target, _)| {
+            assert_eq!(target, "stage4");
+            caller
+        })
+        .collect();
+    callers.sort();
+    assert_eq!(callers, ["main", "shortcut", "stage1", "stage2", "stage3"]);
+}
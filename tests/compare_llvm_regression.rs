@@ -0,0 +1,53 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn compare_llvm_reports_edges_missing_on_either_side() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-compare-llvm");
+    std::fs::create_dir_all(&output_dir).expect("output dir should be created");
+
+    // A call graph unrelated to test1, as if LLVM had seen an entirely different module
+    let dot_path = output_dir.join("llvm-callgraph.dot");
+    std::fs::write(
+        &dot_path,
+        r#"digraph "Call graph: other.ll" {
+	label="Call graph: other.ll";
+
+	Node0x1 [shape=record,label="{external node}"];
+	Node0x1 -> Node0x2;
+	Node0x2 [shape=record,label="{_ZN5other4main17h0000000000000000E}"];
+	Node0x2 -> Node0x3;
+	Node0x3 [shape=record,label="{_ZN5other6helper17h0000000000000000E}"];
+}
+"#,
+    )
+    .expect("dot file should be written");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--compare-llvm", dot_path.to_str().expect("dot path should be utf-8")],
+    );
+
+    let comparison = read_json(&output_dir.join("test1-llvm-comparison.json"));
+    assert_eq!(comparison["llvm_edges"].as_u64(), Some(1));
+    assert_eq!(comparison["common_edges"].as_u64(), Some(0));
+    assert_eq!(comparison["llvm_edges_outside_graph"].as_u64(), Some(1));
+
+    let only_in_ours = comparison["only_in_ours"]
+        .as_array()
+        .expect("only_in_ours should be an array");
+    assert!(!only_in_ours.is_empty());
+    assert_eq!(comparison["our_edges"].as_u64(), Some(only_in_ours.len() as u64));
+    // Edges are compared by mangled symbol name, as LLVM labels its nodes
+    assert!(
+        only_in_ours.iter().all(|edge| edge["caller"]
+            .as_str()
+            .is_some_and(|caller| caller.starts_with("_ZN") || caller.starts_with("_R"))),
+        "got {only_in_ours:#?}"
+    );
+
+    assert!(output_dir.join("test1-llvm-comparison.txt").is_file());
+}
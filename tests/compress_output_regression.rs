@@ -0,0 +1,74 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+#[test]
+fn compress_writes_gzip_outputs_with_gz_extension() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-compress");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--compress"]);
+
+    assert!(
+        !output_dir.join("callgraph.json").exists(),
+        "uncompressed output should not be written"
+    );
+    let compressed_path = output_dir.join("callgraph.json.gz");
+    let compressed = std::fs::File::open(&compressed_path)
+        .unwrap_or_else(|e| panic!("failed to open {}: {e}", compressed_path.display()));
+
+    let mut content = String::new();
+    GzDecoder::new(compressed)
+        .read_to_string(&mut content)
+        .expect("callgraph.json.gz should be valid gzip");
+    let callgraph: serde_json::Value = serde_json::from_str(&content).expect("decompressed output should be JSON");
+    assert!(
+        !callgraph
+            .as_array()
+            .expect("callgraph.json should be an array")
+            .is_empty()
+    );
+}
+
+#[test]
+fn compressed_outputs_are_smaller_and_decompress_to_the_original() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let plain_dir = unique_output_dir("cg4rs-compress-plain");
+    let compressed_dir = unique_output_dir("cg4rs-compress-gz");
+    let formats = [
+        "--format", "text", "--format", "json", "--format", "dot", "--format", "csv",
+    ];
+
+    run_call_cg4rs_with_args(&manifest_path, &plain_dir, &formats);
+    let mut compressed_args = formats.to_vec();
+    compressed_args.push("--compress");
+    run_call_cg4rs_with_args(&manifest_path, &compressed_dir, &compressed_args);
+
+    for file_name in [
+        "test1-callgraph.txt",
+        "callgraph.json",
+        "test1-callgraph.dot",
+        "test1-callgraph.csv",
+    ] {
+        let original = std::fs::read(plain_dir.join(file_name)).expect("uncompressed output should exist");
+        let compressed = std::fs::read(compressed_dir.join(format!("{file_name}.gz")))
+            .unwrap_or_else(|e| panic!("{file_name}.gz should exist: {e}"));
+        assert!(
+            compressed.len() < original.len(),
+            "{file_name}.gz ({} bytes) should be smaller than {file_name} ({} bytes)",
+            compressed.len(),
+            original.len()
+        );
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .expect("output should be valid gzip");
+        assert!(
+            decompressed == original,
+            "{file_name}.gz should decompress to the original output"
+        );
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, run_call_cg4rs_with_env, unique_output_dir};
+
+#[test]
+fn config_file_next_to_manifest_provides_defaults() {
+    let manifest_path = manifest_path("testdata/test4/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-config-file");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &[]);
+
+    assert!(
+        output_dir.join("callgraph.json").is_file(),
+        "json-output from cg4rs.toml should be applied"
+    );
+    assert!(
+        output_dir.join("callers-config_helper.json").is_file(),
+        "find_callers from cg4rs.toml should be applied"
+    );
+}
+
+#[test]
+fn command_line_overrides_config_file() {
+    let manifest_path = manifest_path("testdata/test4/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-config-file-override");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--find-callers", "other_helper"]);
+
+    assert!(output_dir.join("callers-other_helper.json").is_file());
+    assert!(
+        !output_dir.join("callers-config_helper.json").exists(),
+        "--find-callers on the command line should replace the value from cg4rs.toml"
+    );
+    assert!(
+        output_dir.join("callgraph.json").is_file(),
+        "options not given on the command line should still come from cg4rs.toml"
+    );
+}
+
+#[test]
+fn environment_overrides_config_file() {
+    let manifest_path = manifest_path("testdata/test4/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-config-file-env");
+
+    run_call_cg4rs_with_env(
+        &manifest_path,
+        &output_dir,
+        &[],
+        &[("CG4RS_FIND_CALLERS", "other_helper")],
+    );
+
+    assert!(output_dir.join("callers-other_helper.json").is_file());
+    assert!(
+        !output_dir.join("callers-config_helper.json").exists(),
+        "CG4RS_FIND_CALLERS should replace the value from cg4rs.toml"
+    );
+    assert!(output_dir.join("callgraph.json").is_file());
+}
+
+#[test]
+fn command_line_overrides_environment() {
+    let manifest_path = manifest_path("testdata/test4/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-config-env-override");
+
+    run_call_cg4rs_with_env(
+        &manifest_path,
+        &output_dir,
+        &["--find-callers", "config_helper"],
+        &[("CG4RS_FIND_CALLERS", "other_helper")],
+    );
+
+    assert!(output_dir.join("callers-config_helper.json").is_file());
+    assert!(
+        !output_dir.join("callers-other_helper.json").exists(),
+        "--find-callers on the command line should replace CG4RS_FIND_CALLERS"
+    );
+}
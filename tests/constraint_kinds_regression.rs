@@ -0,0 +1,28 @@
+mod common;
+
+use common::{manifest_path, read_callers_json, run_call_cg4rs, unique_output_dir};
+
+#[test]
+fn callers_json_reports_constraint_kinds_along_path() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-constraint-kinds");
+
+    run_call_cg4rs(&manifest_path, &output_dir, "panic_example::guarded_sink");
+
+    let sink = read_callers_json(&output_dir, "panic_example::guarded_sink");
+    let lookup = sink["callers"]
+        .as_array()
+        .expect("callers should be an array")
+        .iter()
+        .find(|entry| entry["path"].as_str() == Some("panic_example::lookup"))
+        .expect("lookup should call guarded_sink");
+
+    let kinds = &lookup["constraint_kinds"];
+    assert_eq!(kinds["SwitchInt"].as_u64(), Some(1), "guarded by `if value > 10`");
+    assert_eq!(kinds["Assert"].as_u64(), Some(1), "guarded by the slice bounds check");
+    assert_eq!(
+        lookup["path_constraints"].as_u64(),
+        Some(1),
+        "asserts are reported but do not add to the constraint count"
+    );
+}
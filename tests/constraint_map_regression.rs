@@ -0,0 +1,42 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir};
+
+#[test]
+fn constraint_map_counts_if_and_match() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-constraint-maps");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--emit-constraint-maps"],
+    );
+
+    let map = read_json(&output_dir.join("constraints").join("branch_example-classify.json"));
+    let map = map.as_object().expect("constraint map should be an object");
+
+    // The entry block is reached without constraints
+    assert_eq!(map["0"]["constraints"].as_u64(), Some(0));
+    assert_eq!(map["0"]["length"].as_u64(), Some(1));
+
+    for (block, entry) in map {
+        let blocks = entry["blocks"].as_array().expect("blocks should be an array");
+        assert_eq!(entry["length"].as_u64(), Some(blocks.len() as u64), "block {block}");
+        assert_eq!(blocks.first().and_then(|b| b.as_u64()), Some(0), "block {block}");
+        assert_eq!(
+            blocks.last().and_then(|b| b.as_u64()).map(|b| b.to_string()).as_deref(),
+            Some(block.as_str())
+        );
+    }
+
+    // The `if` adds one constraint, the `match` a second one
+    let counts: Vec<u64> = map.values().filter_map(|entry| entry["constraints"].as_u64()).collect();
+    assert!(counts.contains(&1), "got {counts:?}");
+    assert_eq!(counts.iter().max(), Some(&2), "got {counts:?}");
+}
+
+#[test]
+fn constraint_maps_are_absent_by_default() {
+    assert!(!test1_json_output().join("constraints").exists());
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{all_callees, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn constraint_depths(callgraph: &Value) -> Vec<u64> {
+    all_callees(callgraph)
+        .into_iter()
+        .map(|call| {
+            call["constraint_depth"]
+                .as_u64()
+                .expect("constraint_depth should be a number")
+        })
+        .collect()
+}
+
+#[test]
+fn max_constraints_keeps_only_unconditional_calls() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-constraints");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--max-constraints", "0"],
+    );
+
+    let depths = constraint_depths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(!depths.is_empty());
+    assert!(depths.iter().all(|&depth| depth == 0), "got {depths:?}");
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    let filtered = stats["filtered_by_constraints"]
+        .as_u64()
+        .expect("filtered_by_constraints should be a number");
+    assert!(filtered > 0, "test1 has guarded call sites");
+}
+
+#[test]
+fn min_constraints_keeps_only_guarded_calls() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-min-constraints");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--min-constraints", "1"],
+    );
+
+    let depths = constraint_depths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(!depths.is_empty());
+    assert!(depths.iter().all(|&depth| depth >= 1), "got {depths:?}");
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{all_functions, manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir};
+use std::collections::HashSet;
+
+#[test]
+fn crate_graph_is_smaller_than_function_graph() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+
+    let callgraph = read_json(&test1_json_output().join("callgraph.json"));
+    let functions: HashSet<&str> = all_functions(&callgraph)
+        .into_iter()
+        .map(|function| function["name"].as_str().expect("function name"))
+        .collect();
+
+    let crate_dir = unique_output_dir("cg4rs-crate-graph");
+    run_call_cg4rs_with_args(&manifest_path, &crate_dir, &["--json-output", "--crate-graph"]);
+    assert!(
+        !crate_dir.join("callgraph.json").exists(),
+        "the function-level graph should not be written"
+    );
+    let crate_graph = read_json(&crate_dir.join("crate-graph.json"));
+
+    let nodes: Vec<&str> = crate_graph["nodes"]
+        .as_array()
+        .expect("nodes should be an array")
+        .iter()
+        .map(|node| node["name"].as_str().expect("node name"))
+        .collect();
+    assert!(nodes.contains(&"test1"), "got {nodes:?}");
+    assert!(
+        nodes.len() < functions.len(),
+        "{} crates for {} functions",
+        nodes.len(),
+        functions.len()
+    );
+
+    let edges = crate_graph["edges"].as_array().expect("edges should be an array");
+    let test1_to_std = edges
+        .iter()
+        .find(|edge| edge["caller_crate"] == "test1" && edge["callee_crate"] == "std")
+        .expect("test1 should call into std");
+    assert!(test1_to_std["calls"].as_u64().is_some_and(|calls| calls > 0));
+    assert!(edges.iter().all(|edge| edge["caller_crate"] != edge["callee_crate"]));
+}
+
+#[test]
+fn crate_graph_dot_labels_nodes_with_versions() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-graph-dot");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--format", "dot", "--crate-graph"]);
+
+    let dot_path = output_dir.join("crate-graph.dot");
+    let dot =
+        std::fs::read_to_string(&dot_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", dot_path.display()));
+    assert!(dot.starts_with("digraph crate_graph {"), "{dot}");
+    assert!(dot.contains("\"test1\" [label=\"test1\\n"), "{dot}");
+    assert!(dot.contains("\"test1\" -> \"std\""), "{dot}");
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+#[test]
+fn every_analyzed_crate_appends_a_row_to_the_index() {
+    let manifest_path = manifest_path("testdata/test8/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-index");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--analyze-proc-macros"]);
+
+    let index = fs::read_to_string(output_dir.join("cg4rs-index.csv")).expect("failed to read the crate index");
+    let mut lines = index.lines();
+    assert_eq!(lines.next(), Some("crate_name,node_count,edge_count,duration_ms"));
+
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 2, "one row per crate, with a single header: {index}");
+    let mut crate_names: Vec<&str> = rows.iter().map(|row| row[0]).collect();
+    crate_names.sort();
+    assert_eq!(crate_names, ["test8", "test8_derive"]);
+    for row in &rows {
+        assert_eq!(row.len(), 4, "{row:?}");
+        for count in &row[1..] {
+            assert!(count.parse::<u64>().is_ok(), "{row:?}");
+        }
+        assert!(row[2].parse::<u64>().unwrap() > 0, "every crate makes calls: {row:?}");
+    }
+}
+
+#[test]
+fn every_run_starts_a_fresh_index() {
+    let manifest_path = manifest_path("testdata/test8/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-index-rerun");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--analyze-proc-macros"]);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--analyze-proc-macros"]);
+
+    let index = fs::read_to_string(output_dir.join("cg4rs-index.csv")).expect("failed to read the crate index");
+    assert_eq!(
+        index.lines().count(),
+        3,
+        "the header and one row per crate of the second run: {index}"
+    );
+}
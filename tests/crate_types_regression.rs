@@ -0,0 +1,39 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn has_test_callers(callgraph: &Value) -> bool {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter_map(|entry| entry["caller"]["path"].as_str())
+        .any(|caller| caller.contains("::tests::"))
+}
+
+#[test]
+fn test_harnesses_are_skipped_by_default() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-types-default");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--", "--tests"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(!has_test_callers(&callgraph), "test functions should not be analyzed");
+}
+
+#[test]
+fn crate_types_test_selects_test_harnesses() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-types-test");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--crate-types", "test", "--", "--tests"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(has_test_callers(&callgraph), "test functions should be analyzed");
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::{
+    all_callees, manifest_path, read_json, run_call_cg4rs_with_args, run_call_cg4rs_with_env, unique_output_dir,
+};
+
+#[test]
+fn unmatched_crate_version_leaves_no_calls() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-version-filter");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--crate-version-filter", "serde:99.0"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callees = all_callees(&callgraph);
+    assert!(callees.is_empty(), "no call should go into serde 99.0: {callees:#?}");
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert_eq!(stats["version_filter_applied"].as_bool(), Some(true));
+}
+
+#[test]
+fn crate_version_filters_can_be_set_in_the_environment() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-crate-version-filter-env");
+
+    // The comma-separated requirement of serde continues after the list separator
+    run_call_cg4rs_with_env(
+        &manifest_path,
+        &output_dir,
+        &["--json-output"],
+        &[("CG4RS_CRATE_VERSION_FILTERS", "serde:>=99.0,<100.0,rand:99.0")],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callee_count = all_callees(&callgraph).len();
+    assert_eq!(callee_count, 0, "no call should go into serde 99 or rand 99");
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert_eq!(stats["version_filter_applied"].as_bool(), Some(true));
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+#[test]
+fn only_calls_into_other_crates_are_kept() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-cross-crate-only");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--cross-crate-only", "--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(!entries.is_empty(), "test1 calls into std and its dependencies");
+    for entry in entries {
+        for callee in entry["callee"].as_array().expect("callee should be an array") {
+            assert!(
+                !(entry["caller"]["path"] == "dedup_location_example::main"
+                    && callee["path"] == "dedup_location_example::describe"),
+                "calls within test1 should be dropped"
+            );
+        }
+    }
+
+    let report =
+        fs::read_to_string(output_dir.join("test1-cross-crate-calls.txt")).expect("failed to read cross-crate calls");
+    assert!(report.starts_with("Cross-Crate Calls of test1:"), "{report}");
+    assert!(
+        report
+            .lines()
+            .any(|line| line.starts_with("std ") || line.starts_with("core ")),
+        "calls should be grouped by callee crate: {report}"
+    );
+}
@@ -0,0 +1,41 @@
+//! Cross-compilation analysis for a no-std target.
+//!
+//! Not run by default because it needs the target's standard library. To run it locally:
+//!
+//! ```bash
+//! rustup target add thumbv7em-none-eabi --toolchain nightly-2025-08-09
+//! cargo test --test cross_target_regression -- --ignored
+//! ```
+
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+const TARGET: &str = "thumbv7em-none-eabi";
+
+#[test]
+#[ignore = "requires `rustup target add thumbv7em-none-eabi`"]
+fn no_std_crate_is_analyzed_for_cross_target() {
+    let manifest_path = manifest_path("testdata/test5/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-cross-target");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--target", TARGET]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(
+        entries
+            .iter()
+            .any(|entry| entry["caller"]["path"].as_str() == Some("process_samples")),
+        "process_samples should be analyzed"
+    );
+    assert!(
+        entries
+            .iter()
+            .all(|entry| entry["compile_target"].as_str() == Some(TARGET)),
+        "every entry should be tagged with the compile target"
+    );
+
+    let stats = read_json(&output_dir.join("test5-callgraph-stats.json"));
+    assert_eq!(stats["compile_target"].as_str(), Some(TARGET));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::collections::HashSet;
+
+#[test]
+fn cytoscape_output_has_data_wrapped_nodes_and_edges() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-cytoscape-output");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--cytoscape-output"]);
+
+    let cytoscape = read_json(&output_dir.join("test1-callgraph.cytoscape.json"));
+    let nodes = cytoscape["elements"]["nodes"]
+        .as_array()
+        .expect("elements.nodes should be an array");
+    let edges = cytoscape["elements"]["edges"]
+        .as_array()
+        .expect("elements.edges should be an array");
+    assert!(!nodes.is_empty() && !edges.is_empty());
+
+    let node_ids: HashSet<&str> = nodes
+        .iter()
+        .map(|node| node["data"]["id"].as_str().expect("node id should be a string"))
+        .collect();
+    assert_eq!(node_ids.len(), nodes.len(), "nodes should be deduplicated");
+
+    for edge in edges {
+        let data = &edge["data"];
+        assert!(node_ids.contains(data["source"].as_str().expect("edge source")));
+        assert!(node_ids.contains(data["target"].as_str().expect("edge target")));
+        assert!(data["weight"].is_u64(), "edge weight should be the constraint count");
+    }
+}
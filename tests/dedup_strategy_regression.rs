@@ -0,0 +1,49 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn describe_callees(callgraph: &Value) -> Vec<&Value> {
+    callees_of(callgraph, "dedup_location_example::main")
+        .into_iter()
+        .filter(|call| call["path"].as_str() == Some("dedup_location_example::describe"))
+        .collect()
+}
+
+#[test]
+fn location_dedup_collapses_instantiations_on_one_line() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-dedup-location");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--dedup-strategy", "location"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callees = describe_callees(&callgraph);
+    assert_eq!(
+        callees.len(),
+        1,
+        "describe::<u8> and describe::<&str> are called on the same line"
+    );
+    let location = callees[0]["call_location"]
+        .as_str()
+        .expect("location dedup should imply --with-locations");
+    assert!(
+        location.contains("dedup_location_example.rs:10:"),
+        "unexpected location {location}"
+    );
+}
+
+#[test]
+fn constraint_dedup_keeps_each_instantiation() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-dedup-constraint");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--with-locations"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(describe_callees(&callgraph).len(), 2);
+}
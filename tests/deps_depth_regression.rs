@@ -0,0 +1,40 @@
+mod common;
+
+use common::{
+    all_callee_paths, manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir,
+};
+
+#[test]
+fn deps_depth_zero_leaves_out_calls_into_dependencies() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-deps-depth");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--deps-depth", "0"]);
+
+    let paths = all_callee_paths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(
+        !paths.iter().any(|path| path.starts_with("rand::")),
+        "calls into rand should be left out: {paths:?}"
+    );
+    assert!(
+        paths
+            .iter()
+            .any(|path| path.starts_with("std::") || path.starts_with("core::")),
+        "the standard library is not a cargo dependency and should be kept: {paths:?}"
+    );
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert!(
+        stats["pruned_by_deps_depth"].as_u64().is_some_and(|pruned| pruned > 0),
+        "{stats:#}"
+    );
+}
+
+#[test]
+fn dependencies_are_followed_without_deps_depth() {
+    let output_dir = test1_json_output();
+    let paths = all_callee_paths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(paths.iter().any(|path| path.starts_with("rand::")), "{paths:?}");
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert!(stats.get("pruned_by_deps_depth").is_none());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+
+fn read_dot(extra_args: &[&str], prefix: &str) -> String {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+
+    let mut args = vec!["--format", "dot"];
+    args.extend_from_slice(extra_args);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    let dot_path = output_dir.join("test1-callgraph.dot");
+    std::fs::read_to_string(&dot_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", dot_path.display()))
+}
+
+#[test]
+fn dot_cluster_groups_nodes_by_crate() {
+    let dot = read_dot(&["--dot-cluster"], "cg4rs-dot-cluster");
+
+    assert!(
+        dot.contains("subgraph cluster_test1 {"),
+        "local crate should get a cluster"
+    );
+    assert!(
+        dot.contains("subgraph cluster_std {"),
+        "std callees should get their own cluster"
+    );
+    assert!(dot.contains("style=dashed"), "cross-crate edges should be dashed");
+    assert!(dot.contains("style=solid"), "intra-crate edges should be solid");
+}
+
+#[test]
+fn dot_without_cluster_is_flat() {
+    let dot = read_dot(&[], "cg4rs-dot-flat");
+
+    assert!(dot.starts_with("digraph callgraph {"));
+    assert!(!dot.contains("subgraph cluster_"));
+}
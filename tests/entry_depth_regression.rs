@@ -0,0 +1,66 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn caller_entry<'a>(callgraph: &'a Value, caller: &str) -> &'a Value {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .find(|entry| entry["caller"]["path"].as_str() == Some(caller))
+        .unwrap_or_else(|| panic!("{caller} should be a caller"))
+}
+
+fn callee_depth(callgraph: &Value, caller: &str, callee: &str) -> Option<u64> {
+    callees_of(callgraph, caller)
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some(callee))
+        .and_then(|call| call["depth_from_entry"].as_u64())
+}
+
+#[test]
+fn depths_are_counted_from_the_entry_point() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-entry-depth");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--entry-point", "run"]);
+
+    // main -> run -> common_step
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(
+        caller_entry(&callgraph, "run")["caller"]["depth_from_entry"].as_u64(),
+        Some(0)
+    );
+    assert_eq!(callee_depth(&callgraph, "main", "run"), Some(0));
+    assert_eq!(callee_depth(&callgraph, "run", "common_step"), Some(1));
+
+    // The caller of the entry point is unreachable from it
+    assert!(
+        caller_entry(&callgraph, "main")["caller"]
+            .get("depth_from_entry")
+            .is_none()
+    );
+}
+
+#[test]
+fn dot_labels_include_depth() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-entry-depth-dot");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--format", "dot", "--entry-point", "main"],
+    );
+
+    let dot_path = output_dir.join("test6-callgraph.dot");
+    let dot =
+        std::fs::read_to_string(&dot_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", dot_path.display()));
+    assert!(dot.contains("\"main\" [label=\"main\\ndepth: 0\"];"), "{dot}");
+    assert!(dot.contains("\"run\" [label=\"run\\ndepth: 1\"];"), "{dot}");
+    assert!(
+        dot.contains("\"common_step\" [label=\"common_step\\ndepth: 2\"];"),
+        "{dot}"
+    );
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn caller_distance(callgraph: &Value, caller: &str) -> Option<u64> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .find(|entry| entry["caller"]["path"].as_str() == Some(caller))
+        .and_then(|entry| entry["caller"]["distance_from_entry"].as_u64())
+}
+
+fn callee_distance(callgraph: &Value, caller: &str, callee: &str) -> Option<u64> {
+    callees_of(callgraph, caller)
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some(callee))
+        .and_then(|call| call["distance_from_entry"].as_u64())
+}
+
+#[test]
+fn distances_follow_the_call_chain_from_main() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-entry-distances");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--entry-distances"]);
+
+    // main -> run -> common_step
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(caller_distance(&callgraph, "main"), Some(0));
+    assert_eq!(callee_distance(&callgraph, "main", "run"), Some(1));
+    assert_eq!(caller_distance(&callgraph, "run"), Some(1));
+    assert_eq!(callee_distance(&callgraph, "run", "common_step"), Some(2));
+
+    let stats = read_json(&output_dir.join("test6-callgraph-stats.json"));
+    let max_distance = stats["max_distance"].as_u64().expect("stats should have max_distance");
+    assert!(max_distance >= 2, "common_step is two calls away from main");
+    let histogram = stats["distance_histogram"]
+        .as_object()
+        .expect("stats should have distance_histogram");
+    assert_eq!(
+        histogram.get("0").and_then(Value::as_u64),
+        Some(1),
+        "main is the only entry point"
+    );
+}
+
+#[test]
+fn distances_are_omitted_by_default() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-entry-distances-default");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(caller_distance(&callgraph, "main"), None);
+    let stats = read_json(&output_dir.join("test6-callgraph-stats.json"));
+    assert!(stats.get("distance_histogram").is_none());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{all_functions, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn macro_generated_functions_are_excluded() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-exclude-generated");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--exclude-generated"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let paths: Vec<&str> = all_functions(&callgraph)
+        .into_iter()
+        .filter_map(|func| func["path"].as_str())
+        .collect();
+    assert!(
+        !paths.contains(&"large_fn_example::dispatch_opcode"),
+        "the function expanded from opcode_dispatch! should be excluded"
+    );
+    assert!(
+        paths.contains(&"large_fn_example::main"),
+        "hand-written functions should be kept"
+    );
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert!(
+        stats["excluded_generated"]
+            .as_u64()
+            .is_some_and(|excluded| excluded >= 1),
+        "{stats:#}"
+    );
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_exit_code, unique_output_dir};
+
+fn exit_code(manifest: &str, prefix: &str, args: &[&str]) -> Option<i32> {
+    let manifest_path = manifest_path(manifest);
+    let output_dir = unique_output_dir(prefix);
+    run_call_cg4rs_exit_code(&manifest_path, &output_dir, args)
+}
+
+#[test]
+fn exit_on_callers() {
+    let args = ["--find-callers", "common_step", "--exit-on-callers"];
+    assert_eq!(
+        exit_code("testdata/test6/Cargo.toml", "cg4rs-exit-callers", &args),
+        Some(1)
+    );
+
+    // `fast_step` only exists with the `fast` feature
+    let args = ["--find-callers", "fast_step", "--exit-on-callers"];
+    assert_eq!(
+        exit_code("testdata/test6/Cargo.toml", "cg4rs-exit-no-callers", &args),
+        Some(0)
+    );
+}
+
+#[test]
+fn exit_on_cycles() {
+    // `unsafe_test::recursive_unsafe` calls itself
+    let args = ["--exit-on-cycles"];
+    assert_eq!(
+        exit_code("testdata/test1/Cargo.toml", "cg4rs-exit-cycles", &args),
+        Some(2)
+    );
+    assert_eq!(
+        exit_code("testdata/test6/Cargo.toml", "cg4rs-exit-no-cycles", &args),
+        Some(0)
+    );
+}
+
+#[test]
+fn exit_on_unsafe_calls() {
+    // `unsafe_test::safe_wrapper` calls `unsafe_test::dangerous_operation`
+    let args = ["--exit-on-unsafe-calls"];
+    assert_eq!(
+        exit_code("testdata/test1/Cargo.toml", "cg4rs-exit-unsafe", &args),
+        Some(3)
+    );
+    assert_eq!(
+        exit_code("testdata/test6/Cargo.toml", "cg4rs-exit-no-unsafe", &args),
+        Some(0)
+    );
+}
+
+#[test]
+fn lowest_exit_code_wins() {
+    let args = ["--exit-on-cycles", "--exit-on-unsafe-calls"];
+    assert_eq!(
+        exit_code("testdata/test1/Cargo.toml", "cg4rs-exit-both", &args),
+        Some(2)
+    );
+}
+
+#[test]
+fn strict_errors_fails_exactly_when_errors_are_reported() {
+    for (manifest, crate_name) in [
+        ("testdata/test7/Cargo.toml", "test7"),
+        ("testdata/test1/Cargo.toml", "test1"),
+    ] {
+        let manifest_path = manifest_path(manifest);
+        let output_dir = unique_output_dir("cg4rs-exit-strict-errors");
+        let code = run_call_cg4rs_exit_code(&manifest_path, &output_dir, &["--json-output", "--strict-errors"]);
+
+        let stats = read_json(&output_dir.join(format!("{crate_name}-callgraph-stats.json")));
+        let errors = stats["analysis_errors"]
+            .as_array()
+            .expect("analysis_errors should be an array");
+        for error in errors {
+            assert!(error["kind"].is_string() && error["function"].is_string(), "{error}");
+        }
+        let expected = if errors.is_empty() { 0 } else { 5 };
+        assert_eq!(code, Some(expected), "{crate_name}: {errors:?}");
+    }
+}
+
+// Needs the hooks of the `fault-injection` feature: `cargo test --features fault-injection`
+#[cfg(feature = "fault-injection")]
+#[test]
+fn strict_errors_exits_with_5_on_a_known_error() {
+    use common::run_call_cg4rs_exit_code_with_env;
+
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    // Stopping the expansion for the driver timeout always records `timeout_exceeded` errors
+    let envs = [("CG4RS_INJECT_TIMEOUT", "1")];
+
+    let output_dir = unique_output_dir("cg4rs-exit-strict-errors-timeout");
+    let args = ["--json-output"];
+    assert_eq!(
+        run_call_cg4rs_exit_code_with_env(&manifest_path, &output_dir, &args, &envs),
+        Some(0)
+    );
+
+    let output_dir = unique_output_dir("cg4rs-exit-strict-errors-timeout");
+    let strict_args = ["--json-output", "--strict-errors"];
+    assert_eq!(
+        run_call_cg4rs_exit_code_with_env(&manifest_path, &output_dir, &strict_args, &envs),
+        Some(5)
+    );
+
+    let stats = read_json(&output_dir.join("test7-callgraph-stats.json"));
+    assert!(
+        stats["analysis_errors"]
+            .as_array()
+            .expect("analysis_errors should be an array")
+            .iter()
+            .any(|error| error["kind"] == "timeout_exceeded"),
+        "{stats:#}"
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{all_functions, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+/// Def paths of all callers and callees in callgraph.json
+fn function_paths(callgraph: &Value) -> Vec<String> {
+    all_functions(callgraph)
+        .into_iter()
+        .map(|func| func["path"].as_str().expect("function path").to_string())
+        .collect()
+}
+
+#[test]
+fn extern_impls_are_skipped_when_requested() {
+    let manifest_path = manifest_path("testdata/test10/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-no-extern-impls");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--no-extern-impls"]);
+
+    let paths = function_paths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(
+        !paths.iter().any(|path| path.contains("Celsius as std::fmt::Display")),
+        "Display::fmt of a local type should be skipped: {paths:?}"
+    );
+    assert!(
+        !paths.iter().any(|path| path.contains("as Describe>::describe")),
+        "a local trait impl for String should be skipped: {paths:?}"
+    );
+    assert!(
+        paths.contains(&"Celsius::new".to_string()),
+        "inherent impls of local types should be kept: {paths:?}"
+    );
+
+    let stats = read_json(&output_dir.join("test10-callgraph-stats.json"));
+    assert!(
+        stats["skipped_extern_impls"]
+            .as_u64()
+            .is_some_and(|skipped| skipped >= 2),
+        "{stats:#}"
+    );
+}
+
+#[test]
+fn extern_impls_are_analyzed_by_default() {
+    let manifest_path = manifest_path("testdata/test10/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-extern-impls");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let paths = function_paths(&read_json(&output_dir.join("callgraph.json")));
+    assert!(
+        paths.iter().any(|path| path.contains("Celsius as std::fmt::Display")),
+        "Display::fmt should be analyzed without --no-extern-impls: {paths:?}"
+    );
+    let stats = read_json(&output_dir.join("test10-callgraph-stats.json"));
+    assert!(stats.get("skipped_extern_impls").is_none());
+}
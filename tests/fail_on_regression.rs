@@ -0,0 +1,73 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_exit_code, unique_output_dir};
+use serde_json::Value;
+
+/// Exit code and `--fail-on` summary of analyzing `manifest` with `args`
+fn fail_on(manifest: &str, prefix: &str, args: &[&str]) -> (Option<i32>, Value) {
+    let manifest_path = manifest_path(manifest);
+    let output_dir = unique_output_dir(prefix);
+    let exit_code = run_call_cg4rs_exit_code(&manifest_path, &output_dir, args);
+    let crate_name = manifest.split('/').nth(1).expect("manifest should be under testdata");
+    let summary = read_json(&output_dir.join(format!("{crate_name}-fail-on.json")));
+    (exit_code, summary)
+}
+
+fn check_triggered(summary: &Value, check: &str) -> bool {
+    summary["checks"]
+        .as_array()
+        .expect("checks should be an array")
+        .iter()
+        .find(|result| result["check"].as_str() == Some(check))
+        .unwrap_or_else(|| panic!("{check} should be evaluated, got {summary:#}"))["triggered"]
+        .as_bool()
+        .expect("triggered should be a bool")
+}
+
+#[test]
+fn reachable_function_fails_the_run() {
+    // main -> run -> common_step
+    let (exit_code, summary) = fail_on(
+        "testdata/test6/Cargo.toml",
+        "cg4rs-fail-on-reachable",
+        &["--fail-on", "reachable:common_step"],
+    );
+    assert_eq!(exit_code, Some(4));
+    assert_eq!(summary["failed"].as_bool(), Some(true));
+    assert!(check_triggered(&summary, "reachable:common_step"));
+}
+
+#[test]
+fn passing_checks_exit_cleanly() {
+    // `fast_step` only exists with the `fast` feature, and test6 has no cycles
+    let (exit_code, summary) = fail_on(
+        "testdata/test6/Cargo.toml",
+        "cg4rs-fail-on-clean",
+        &["--fail-on", "cycle,reachable:fast_step,unresolved-threshold:100000"],
+    );
+    assert_eq!(exit_code, Some(0));
+    assert_eq!(summary["failed"].as_bool(), Some(false));
+    for check in ["cycle", "reachable:fast_step", "unresolved-threshold:100000"] {
+        assert!(!check_triggered(&summary, check), "{check}");
+    }
+}
+
+#[test]
+fn cycle_check_fails_the_run() {
+    // `unsafe_test::recursive_unsafe` calls itself
+    let (exit_code, summary) = fail_on(
+        "testdata/test1/Cargo.toml",
+        "cg4rs-fail-on-cycle",
+        &["--fail-on", "cycle"],
+    );
+    assert_eq!(exit_code, Some(4));
+    assert!(check_triggered(&summary, "cycle"));
+}
+
+#[test]
+fn exit_on_flags_take_precedence() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-fail-on-precedence");
+    let exit_code = run_call_cg4rs_exit_code(&manifest_path, &output_dir, &["--exit-on-cycles", "--fail-on", "cycle"]);
+    assert_eq!(exit_code, Some(2));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+/// Callee paths of `run` in a feature-combination output
+fn callees_of_run(output: &Value) -> Vec<String> {
+    output["call_graph"]
+        .as_array()
+        .expect("call_graph should be an array")
+        .iter()
+        .filter(|entry| entry["caller"]["path"].as_str() == Some("run"))
+        .flat_map(|entry| entry["callee"].as_array().cloned().unwrap_or_default())
+        .filter_map(|callee| callee["path"].as_str().map(String::from))
+        .collect()
+}
+
+#[test]
+fn each_feature_combo_gets_its_own_output() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-feature-combo");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--feature-combo", "fast", "--feature-combo", "verbose"],
+    );
+
+    let outputs: Vec<Value> = std::fs::read_dir(&output_dir)
+        .expect("output dir should exist")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("test6-features-") && name.ends_with(".json"))
+        })
+        .map(|path| read_json(&path))
+        .collect();
+    assert_eq!(outputs.len(), 2, "one output per feature combination");
+
+    let output_for = |feature: &str| {
+        outputs
+            .iter()
+            .find(|output| output["features"] == serde_json::json!([feature]))
+            .unwrap_or_else(|| panic!("missing output for feature {feature}"))
+    };
+
+    let fast = callees_of_run(output_for("fast"));
+    assert!(
+        fast.contains(&"fast_step".to_string()),
+        "fast combo should call fast_step: {fast:?}"
+    );
+    assert!(!fast.contains(&"log_step".to_string()));
+
+    let verbose = callees_of_run(output_for("verbose"));
+    assert!(
+        verbose.contains(&"log_step".to_string()),
+        "verbose combo should call log_step: {verbose:?}"
+    );
+    assert!(!verbose.contains(&"fast_step".to_string()));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{
+    all_callees, callees_of, manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir,
+};
+use serde_json::Value;
+
+fn callee_abi<'a>(callgraph: &'a Value, caller: &str, callee: &str) -> Option<&'a str> {
+    callees_of(callgraph, caller)
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some(callee))
+        .and_then(|call| call["abi"].as_str())
+}
+
+#[test]
+fn callee_abi_is_reported() {
+    let callgraph = read_json(&test1_json_output().join("callgraph.json"));
+    let caller = "ffi_example::main";
+    assert_eq!(callee_abi(&callgraph, caller, "ffi_example::abs"), Some("C"));
+    assert_eq!(callee_abi(&callgraph, caller, "ffi_example::c_callback"), Some("C"));
+    assert_eq!(callee_abi(&callgraph, caller, "ffi_example::rust_helper"), Some("Rust"));
+}
+
+#[test]
+fn ffi_only_keeps_foreign_calls() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-ffi-only");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--ffi-only"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callees = callees_of(&callgraph, "ffi_example::main");
+    let paths: Vec<&str> = callees.iter().filter_map(|call| call["path"].as_str()).collect();
+    assert!(paths.contains(&"ffi_example::abs"), "got {paths:?}");
+    assert!(paths.contains(&"ffi_example::c_callback"), "got {paths:?}");
+    assert!(!paths.contains(&"ffi_example::rust_helper"), "got {paths:?}");
+
+    let all_foreign = all_callees(&callgraph)
+        .into_iter()
+        .all(|call| call["abi"].as_str() != Some("Rust"));
+    assert!(all_foreign, "--ffi-only should drop every Rust ABI call");
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, run_cargo_cg4rs, unique_output_dir};
+
+#[test]
+fn file_restricts_analysis_to_its_crate() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let file = common::manifest_path("testdata/test6/src/main.rs");
+    let output_dir = unique_output_dir("cg4rs-file-filter");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &[
+            "--json-output",
+            "--file",
+            file.to_str().expect("file path is not valid utf-8"),
+        ],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callers: Vec<&str> = callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter_map(|entry| entry["caller"]["path"].as_str())
+        .collect();
+    assert!(
+        callers.contains(&"run"),
+        "the crate containing main.rs should be analyzed, got {callers:?}"
+    );
+}
+
+#[test]
+fn missing_file_is_rejected() {
+    let output = run_cargo_cg4rs(&["--file", "testdata/test6/src/does_not_exist.rs"]);
+    assert!(!output.status.success(), "a missing --file should fail");
+}
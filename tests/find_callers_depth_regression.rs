@@ -0,0 +1,83 @@
+mod common;
+
+use common::{manifest_path, read_callers_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::collections::BTreeMap;
+
+/// Callers of test7's `stage4` with their hop counts, for the given `--find-callers-depth`
+fn stage4_callers(depth: Option<&str>) -> BTreeMap<String, u64> {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-find-callers-depth");
+
+    let mut args = vec!["--find-callers", "stage4", "--json-output"];
+    if let Some(depth) = depth {
+        args.extend(["--find-callers-depth", depth]);
+    }
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    read_callers_json(&output_dir, "stage4")["callers"]
+        .as_array()
+        .expect("callers should be an array")
+        .iter()
+        .map(|caller| {
+            (
+                caller["path"].as_str().expect("caller path").to_string(),
+                caller["hop_count"].as_u64().expect("hop_count"),
+            )
+        })
+        .collect()
+}
+
+fn callers(expected: &[(&str, u64)]) -> BTreeMap<String, u64> {
+    expected.iter().map(|(path, hops)| (path.to_string(), *hops)).collect()
+}
+
+#[test]
+fn depth_one_keeps_direct_callers_only() {
+    assert_eq!(stage4_callers(Some("1")), callers(&[("stage3", 1), ("shortcut", 1)]));
+}
+
+#[test]
+fn depth_zero_reports_the_target_itself() {
+    assert_eq!(stage4_callers(Some("0")), callers(&[("stage4", 0)]));
+}
+
+#[test]
+fn unlimited_depth_reports_the_transitive_closure() {
+    // main reaches stage4 in two calls through shortcut
+    assert_eq!(
+        stage4_callers(None),
+        callers(&[
+            ("stage3", 1),
+            ("shortcut", 1),
+            ("stage2", 2),
+            ("main", 2),
+            ("stage1", 3)
+        ])
+    );
+}
+
+#[test]
+fn callers_can_be_sorted_by_distance() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-sort-callers-by");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &[
+            "--find-callers",
+            "stage4",
+            "--json-output",
+            "--sort-callers-by",
+            "distance",
+        ],
+    );
+
+    let hop_counts: Vec<u64> = read_callers_json(&output_dir, "stage4")["callers"]
+        .as_array()
+        .expect("callers should be an array")
+        .iter()
+        .map(|caller| caller["hop_count"].as_u64().expect("hop_count"))
+        .collect();
+    assert_eq!(hop_counts, [1, 1, 2, 2, 3], "nearest callers should come first");
+}
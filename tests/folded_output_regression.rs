@@ -0,0 +1,47 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use std::fs;
+
+fn folded_stacks(prefix: &str, extra_args: &[&str]) -> Vec<String> {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+    let mut args = vec!["--folded-output"];
+    args.extend_from_slice(extra_args);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    let folded_path = output_dir.join("test7-callgraph.folded");
+    fs::read_to_string(&folded_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", folded_path.display()))
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn folded_output_has_one_line_per_root_to_leaf_path() {
+    let lines = folded_stacks("cg4rs-folded", &[]);
+    for expected in [
+        "main;stage1;stage2;stage3;stage4 1",
+        "main;shortcut;stage4 1",
+        "main;countdown 1",
+    ] {
+        assert!(lines.contains(&expected.to_string()), "missing {expected}: {lines:#?}");
+    }
+    assert!(
+        lines
+            .iter()
+            .all(|line| line.starts_with("main;") && line.ends_with(" 1")),
+        "{lines:#?}"
+    );
+}
+
+#[test]
+fn folded_output_starts_at_entry_point_and_respects_max_path_len() {
+    let lines = folded_stacks("cg4rs-folded-entry", &["--entry-point", "stage2"]);
+    assert_eq!(lines, ["stage2;stage3;stage4 1"]);
+
+    let lines = folded_stacks("cg4rs-folded-len", &["--max-path-len", "2"]);
+    assert!(lines.contains(&"main;stage1;stage2 1".to_string()), "{lines:#?}");
+    assert!(lines.iter().all(|line| line.matches(';').count() <= 2), "{lines:#?}");
+}
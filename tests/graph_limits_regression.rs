@@ -0,0 +1,81 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir};
+
+#[test]
+fn max_edges_truncates_the_graph() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-edges");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--max-edges", "5"]);
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert_eq!(stats["truncated"].as_bool(), Some(true));
+    let total_call_sites = stats["total_call_sites"]
+        .as_u64()
+        .expect("total_call_sites should be a number");
+    assert!(total_call_sites <= 5, "got {total_call_sites} call sites");
+}
+
+#[test]
+fn max_nodes_truncates_the_graph() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-nodes");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--max-nodes", "3"]);
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    assert_eq!(stats["truncated"].as_bool(), Some(true));
+    let total_functions = stats["total_functions"]
+        .as_u64()
+        .expect("total_functions should be a number");
+    assert!(total_functions <= 3, "got {total_functions} functions");
+}
+
+#[test]
+fn truncation_is_deterministic() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let first_dir = unique_output_dir("cg4rs-max-edges-first");
+    let second_dir = unique_output_dir("cg4rs-max-edges-second");
+
+    run_call_cg4rs_with_args(&manifest_path, &first_dir, &["--json-output", "--max-edges", "10"]);
+    run_call_cg4rs_with_args(&manifest_path, &second_dir, &["--json-output", "--max-edges", "10"]);
+
+    assert_eq!(
+        read_json(&first_dir.join("callgraph.json")),
+        read_json(&second_dir.join("callgraph.json"))
+    );
+}
+
+#[test]
+fn graph_is_not_truncated_without_limits() {
+    let stats = read_json(&test1_json_output().join("test1-callgraph-stats.json"));
+    assert_eq!(stats["truncated"].as_bool(), Some(false));
+}
+
+// Needs the hooks of the `fault-injection` feature: `cargo test --features fault-injection`
+#[cfg(feature = "fault-injection")]
+#[test]
+fn driver_timeout_reports_unexpanded_functions() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-driver-timeout");
+
+    // The expansion stops before the first seed is expanded
+    common::run_call_cg4rs_with_env(
+        &manifest_path,
+        &output_dir,
+        &["--json-output"],
+        &[("CG4RS_INJECT_TIMEOUT", "1")],
+    );
+
+    let stats = read_json(&output_dir.join("test7-callgraph-stats.json"));
+    assert_eq!(stats["total_call_sites"].as_u64(), Some(0), "{stats:#}");
+    let timed_out: Vec<&str> = stats["analysis_errors"]
+        .as_array()
+        .expect("analysis_errors should be an array")
+        .iter()
+        .filter(|error| error["kind"] == "timeout_exceeded")
+        .map(|error| error["function"].as_str().expect("function path"))
+        .collect();
+    assert!(timed_out.contains(&"main"), "{timed_out:?}");
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use cg4rs::GENERATED_FN_PATTERNS;
+use common::{all_functions, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn hide_generated_removes_async_state_machine_functions() {
+    let manifest_path = manifest_path("testdata/test2/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-hide-generated");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--hide-generated"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(!entries.is_empty(), "non-generated call sites should remain");
+
+    for function in all_functions(&callgraph) {
+        let path = function["path"].as_str().expect("path should be a string");
+        assert!(
+            !GENERATED_FN_PATTERNS.iter().any(|fragment| path.contains(fragment)),
+            "generated function {path} should be hidden"
+        );
+    }
+}
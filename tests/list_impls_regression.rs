@@ -0,0 +1,41 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn list_impls_reports_every_impl_and_its_methods() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-list-impls");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--list-impls", "dyn_example::Signal", "--json-output"],
+    );
+
+    let impls = read_json(&output_dir.join("test1-impls-dyn_example-Signal.json"));
+    assert_eq!(impls["total_impls"].as_u64(), Some(3));
+
+    let impls = impls["impls"].as_array().expect("impls should be an array");
+    for self_ty in ["SineWave", "SquareWave", "NoiseGenerator"] {
+        let imp = impls
+            .iter()
+            .find(|imp| imp["self_ty"].as_str().is_some_and(|ty| ty.ends_with(self_ty)))
+            .unwrap_or_else(|| panic!("missing impl Signal for {self_ty}"));
+        let methods: Vec<&str> = imp["methods"]
+            .as_array()
+            .expect("methods should be an array")
+            .iter()
+            .filter_map(|method| method.as_str())
+            .collect();
+        assert!(
+            methods.iter().any(|m| m.ends_with("::sample")) && methods.iter().any(|m| m.ends_with("::name")),
+            "impl for {self_ty} should define sample and name, got {methods:?}"
+        );
+    }
+
+    assert!(
+        !output_dir.join("callgraph.json").exists(),
+        "listing impls should not build the call graph"
+    );
+}
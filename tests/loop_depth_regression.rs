@@ -0,0 +1,38 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn callee_loop_depth(callgraph: &Value, caller: &str, callee: &str) -> Option<u64> {
+    callees_of(callgraph, caller)
+        .into_iter()
+        .find(|call| call["path"].as_str().is_some_and(|path| path.contains(callee)))
+        .and_then(|call| call["loop_depth"].as_u64())
+}
+
+#[test]
+fn calls_inside_loops_report_loop_depth() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-loop-depth");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--sort-by-loop-depth"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let in_loop = callee_loop_depth(
+        &callgraph,
+        "large_fn_example::main",
+        "large_fn_example::dispatch_opcode",
+    )
+    .expect("main should call dispatch_opcode");
+    assert!(in_loop >= 1, "dispatch_opcode is called inside a for loop");
+
+    let max_depth = callees_of(&callgraph, "large_fn_example::main")
+        .into_iter()
+        .filter_map(|call| call["loop_depth"].as_u64())
+        .max()
+        .unwrap();
+    assert!(
+        (1..=2).contains(&max_depth),
+        "a single for loop should not be reported as deeply nested"
+    );
+}
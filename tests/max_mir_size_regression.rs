@@ -0,0 +1,95 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn functions_above_max_mir_size_are_skipped_and_reported() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-max-mir-size");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--max-mir-size", "40"]);
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    let skipped = stats["skipped_large_functions"]
+        .as_array()
+        .expect("skipped_large_functions should be an array");
+
+    let dispatch = skipped
+        .iter()
+        .find(|entry| entry["path"].as_str() == Some("large_fn_example::dispatch_opcode"))
+        .expect("macro-generated dispatch_opcode should be skipped at a low threshold");
+    assert!(
+        dispatch["basic_blocks"]
+            .as_u64()
+            .expect("basic_blocks should be a number")
+            > 40
+    );
+    assert_eq!(
+        stats["mir_failures"],
+        serde_json::json!([]),
+        "all functions of test1 should have optimized MIR"
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(
+        !callgraph
+            .as_array()
+            .expect("callgraph.json should be an array")
+            .iter()
+            .any(|entry| entry["caller"]["path"].as_str() == Some("large_fn_example::dispatch_opcode")),
+        "skipped functions should not contribute call sites"
+    );
+}
+
+// Needs the hook of the `fault-injection` feature: `cargo test --features fault-injection`
+#[cfg(feature = "fault-injection")]
+#[test]
+fn functions_whose_mir_cannot_be_built_are_skipped_and_reported() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-mir-failure");
+
+    // The driver panics on this function's `optimized_mir`
+    common::run_call_cg4rs_with_env(
+        &manifest_path,
+        &output_dir,
+        &["--json-output"],
+        &[
+            ("CG4RS_INJECT_MIR_PANIC", "strategy_example::finish_with"),
+            ("RUSTC_ICE", "0"),
+        ],
+    );
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    let failures = stats["mir_failures"]
+        .as_array()
+        .expect("mir_failures should be an array");
+    assert_eq!(failures.len(), 1, "{failures:?}");
+    assert_eq!(failures[0]["path"].as_str(), Some("strategy_example::finish_with"));
+    assert!(
+        failures[0]["error"]
+            .as_str()
+            .expect("error should be a string")
+            .contains("CG4RS_INJECT_MIR_PANIC"),
+        "{failures:?}"
+    );
+    let errors = stats["analysis_errors"]
+        .as_array()
+        .expect("analysis_errors should be an array");
+    assert!(
+        errors
+            .iter()
+            .any(|error| error["kind"].as_str() == Some("mir_unavailable")
+                && error["function"].as_str() == Some("strategy_example::finish_with")),
+        "{errors:?}"
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(!entries.is_empty(), "the rest of the crate should still be analyzed");
+    assert!(
+        !entries
+            .iter()
+            .any(|entry| entry["caller"]["path"].as_str() == Some("strategy_example::finish_with")),
+        "skipped functions should not contribute call sites"
+    );
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{callee_paths, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn inline_always_functions_are_merged_into_their_callers() {
+    let manifest_path = manifest_path("testdata/test11/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-merge-inline");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--merge-inline"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let main_callees = callee_paths(&callgraph, "main");
+    assert!(
+        main_callees.contains(&"checked_add".to_string()),
+        "main should call through clamp_small directly: {main_callees:?}"
+    );
+    assert!(!main_callees.contains(&"clamp_small".to_string()), "{main_callees:?}");
+    assert!(callee_paths(&callgraph, "clamp_small").is_empty());
+
+    assert!(
+        main_callees.contains(&"accumulate".to_string()),
+        "regular functions should be kept: {main_callees:?}"
+    );
+    assert!(callee_paths(&callgraph, "accumulate").contains(&"checked_add".to_string()));
+}
+
+#[test]
+fn inline_always_functions_are_kept_by_default() {
+    let manifest_path = manifest_path("testdata/test11/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-no-merge-inline");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(callee_paths(&callgraph, "main").contains(&"clamp_small".to_string()));
+    assert!(callee_paths(&callgraph, "clamp_small").contains(&"checked_add".to_string()));
+}
+
+#[test]
+fn inline_always_leaves_are_kept() {
+    let manifest_path = manifest_path("testdata/test11/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-merge-inline-leaf");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--merge-inline"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let main_callees = callee_paths(&callgraph, "main");
+    assert!(
+        main_callees.contains(&"flip_low_bit".to_string()),
+        "an always-inlined function without callees should stay: {main_callees:?}"
+    );
+}
+
+#[test]
+fn merged_functions_leave_the_node_count() {
+    let manifest_path = manifest_path("testdata/test11/Cargo.toml");
+    let default_dir = unique_output_dir("cg4rs-merge-inline-count-default");
+    let merged_dir = unique_output_dir("cg4rs-merge-inline-count-merged");
+
+    run_call_cg4rs_with_args(&manifest_path, &default_dir, &["--json-output"]);
+    run_call_cg4rs_with_args(&manifest_path, &merged_dir, &["--json-output", "--merge-inline"]);
+
+    let total_functions = |dir: &std::path::Path| {
+        read_json(&dir.join("test11-callgraph-stats.json"))["total_functions"]
+            .as_u64()
+            .expect("total_functions should be a number")
+    };
+    // Only `clamp_small` is merged, `flip_low_bit` is a leaf
+    assert_eq!(total_functions(&merged_dir), total_functions(&default_dir) - 1);
+}
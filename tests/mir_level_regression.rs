@@ -0,0 +1,21 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn built_mir_level_collects_local_calls() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-mir-level-built");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--mir-level", "built"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let describe_calls = callees_of(&callgraph, "dedup_location_example::main")
+        .into_iter()
+        .filter(|call| call["path"].as_str() == Some("dedup_location_example::describe"))
+        .count();
+    assert_eq!(
+        describe_calls, 2,
+        "both instantiations of describe should be found in built MIR"
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn mono_report_counts_instantiations_per_generic_function() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-mono-report");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--mono-report"]);
+
+    let report_path = output_dir.join("test1-mono-report.txt");
+    let report = std::fs::read_to_string(&report_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", report_path.display()));
+
+    let total_value = report
+        .lines()
+        .find(|line| line.contains("DataStore") && line.contains("total_value ->"))
+        .expect("DataStore::total_value should be reported");
+    let count: usize = total_value
+        .split("-> ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|count| count.parse().ok())
+        .expect("report line should end with an instantiation count");
+    assert_eq!(
+        count, 2,
+        "DataStore<Electronics> and DataStore<Clothing> are two instantiations"
+    );
+
+    let counts: Vec<usize> = report
+        .lines()
+        .filter_map(|line| line.split("-> ").nth(1))
+        .filter_map(|rest| rest.split_whitespace().next()?.parse().ok())
+        .collect();
+    assert!(
+        counts.windows(2).all(|pair| pair[0] >= pair[1]),
+        "report should be sorted by descending instantiation count"
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir};
+use std::collections::HashSet;
+
+#[test]
+fn no_cleanup_writes_one_debug_file_per_function() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-no-cleanup");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--no-cleanup"]);
+
+    let debug_dir = output_dir.join("debug");
+    let mut function_paths = HashSet::new();
+    for entry in std::fs::read_dir(&debug_dir).expect("debug directory should exist") {
+        let artifact = read_json(&entry.expect("debug entry should be readable").path());
+        let function_path = artifact["function_path"]
+            .as_str()
+            .expect("function_path should be a string")
+            .to_string();
+        assert!(artifact["mir_block_count"].as_u64().is_some_and(|count| count > 0));
+        assert!(artifact["raw_callsite_count"].is_u64());
+        assert!(artifact["constraint_map"].is_object());
+        assert!(artifact["elapsed_ms"].is_number());
+        assert!(
+            function_paths.insert(function_path.clone()),
+            "{function_path} should be written once"
+        );
+    }
+
+    // Every caller in the graph has been analyzed
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callers = callgraph.as_array().expect("callgraph.json should be an array").len();
+    assert!(
+        function_paths.len() >= callers,
+        "{} debug files for {callers} callers",
+        function_paths.len()
+    );
+    assert!(
+        function_paths.iter().any(|path| path == "main"),
+        "got {function_paths:?}"
+    );
+}
+
+#[test]
+fn debug_directory_is_absent_by_default() {
+    assert!(!test1_json_output().join("debug").exists());
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{manifest_path, read_callers_json, run_call_cg4rs_with_args, unique_output_dir};
+
+const TARGET: &str = "describe::<u8>";
+
+fn target_variants_count(extra_args: &[&str], prefix: &str) -> u64 {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+
+    let mut args = vec!["--json-output", "--find-callers", TARGET];
+    args.extend_from_slice(extra_args);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    let callers = read_callers_json(&output_dir, TARGET);
+    callers["reachability_summary"]["target_variants_count"]
+        .as_u64()
+        .expect("target_variants_count should be a number")
+}
+
+#[test]
+fn generic_stripping_matches_all_instantiations() {
+    assert_eq!(target_variants_count(&[], "cg4rs-generic-stripping"), 2);
+}
+
+#[test]
+fn no_generic_stripping_matches_substring_only() {
+    assert_eq!(
+        target_variants_count(&["--no-generic-stripping"], "cg4rs-no-generic-stripping"),
+        1,
+        "only describe::<u8> contains the literal target"
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, run_cargo_cg4rs, unique_output_dir};
+use serde_json::Value;
+
+#[test]
+fn numeric_ids_reference_a_sorted_node_table() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-numeric-ids");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--numeric-ids"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let nodes = callgraph["nodes"].as_array().expect("nodes should be an array");
+    for (index, node) in nodes.iter().enumerate() {
+        assert_eq!(node["id"].as_u64(), Some(index as u64), "ids should count up from 0");
+        for field in ["name", "path", "crate", "version", "path_hash"] {
+            assert!(node[field].is_string(), "node should have a `{field}`: {node}");
+        }
+    }
+    let keys: Vec<(&str, &str)> = nodes
+        .iter()
+        .map(|node| (node["path_hash"].as_str().unwrap(), node["name"].as_str().unwrap()))
+        .collect();
+    assert!(keys.is_sorted(), "ids should follow the stable key order");
+
+    let id_of = |path: &str| {
+        nodes
+            .iter()
+            .find(|node| node["path"].as_str() == Some(path))
+            .unwrap_or_else(|| panic!("{path} should be a node"))["id"]
+            .clone()
+    };
+    let edges = callgraph["edges"].as_array().expect("edges should be an array");
+    let has_edge = |from: &str, to: &str| {
+        edges
+            .iter()
+            .any(|edge: &Value| edge["from"] == id_of(from) && edge["to"] == id_of(to))
+    };
+    assert!(has_edge("main", "shortcut"));
+    assert!(has_edge("shortcut", "stage4"));
+    assert!(has_edge("countdown", "countdown"));
+    assert!(!has_edge("main", "stage4"));
+    assert!(edges.iter().all(|edge| edge["constraint_count"].is_u64()));
+
+    let callgraph_path = output_dir.join("callgraph.json");
+    let output = run_cargo_cg4rs(&[
+        "query",
+        callgraph_path.to_str().expect("output path is not valid utf-8"),
+        "--path-from",
+        "main",
+        "--path-to",
+        "stage4",
+    ]);
+    assert!(output.status.success(), "query should load the numeric-id layout");
+    let text = String::from_utf8(output.stdout).expect("text output should be utf-8");
+    assert!(text.contains("main -> shortcut -> stage4"), "{text}");
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{all_callees, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn only_prefix_limits_expansion_to_module() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-only-prefix");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--only-prefix", "dyn_example"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    assert!(!entries.is_empty(), "dyn_example should have call sites");
+
+    for entry in entries {
+        let caller = entry["caller"]["path"]
+            .as_str()
+            .expect("caller path should be a string");
+        assert!(
+            caller.starts_with("dyn_example::") || caller.starts_with("<dyn_example::"),
+            "only functions under the prefix should be analyzed, found {caller}"
+        );
+    }
+
+    assert!(
+        entries.iter().any(|entry| entry["caller"]["path"]
+            .as_str()
+            .is_some_and(|caller| caller.starts_with("<dyn_example::SineWave as dyn_example::Signal>::"))),
+        "methods of trait impls in the module should be analyzed"
+    );
+
+    assert!(
+        all_callees(&callgraph)
+            .into_iter()
+            .any(|callee| !callee["path"].as_str().unwrap_or_default().starts_with("dyn_example")),
+        "edges leaving the prefix should still be recorded"
+    );
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn multiple_formats_are_written_in_one_run() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-output-formats");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--format", "json", "--format", "csv"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(
+        !callgraph
+            .as_array()
+            .expect("callgraph.json should be an array")
+            .is_empty(),
+        "JSON call graph should not be empty"
+    );
+
+    let csv_path = output_dir.join("test1-callgraph.csv");
+    let csv =
+        std::fs::read_to_string(&csv_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", csv_path.display()));
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("caller,callee,constraint_depth,loop_depth,call_kind,resolution")
+    );
+    assert!(
+        lines.any(|line| line.contains("dedup_location_example::main") && line.contains("describe")),
+        "CSV should contain the main -> describe call sites"
+    );
+
+    assert!(
+        !output_dir.join("test1-callgraph.txt").exists(),
+        "text output is only the default when no format is given"
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn output_prefix_is_prepended_to_generated_files() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-output-prefix");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--find-callers", "stage4", "--output-prefix", "run1-"],
+    );
+
+    for file_name in [
+        "run1-callgraph.json",
+        "run1-test7-callgraph-stats.json",
+        "run1-callers-stage4.json",
+    ] {
+        assert!(output_dir.join(file_name).exists(), "{file_name} should be written");
+    }
+    for file_name in ["callgraph.json", "test7-callgraph-stats.json", "callers-stage4.json"] {
+        assert!(!output_dir.join(file_name).exists(), "{file_name} should be prefixed");
+    }
+
+    let callers = read_json(&output_dir.join("run1-callers-stage4.json"));
+    assert!(
+        !callers["callers"]
+            .as_array()
+            .expect("callers should be an array")
+            .is_empty()
+    );
+}
+
+#[test]
+fn runs_with_different_prefixes_do_not_clobber_each_other() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-output-prefix-runs");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--output-prefix", "full-"]);
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--output-prefix", "bounded-", "--max-constraints", "0"],
+    );
+
+    assert!(output_dir.join("full-test7-callgraph.txt").exists());
+    assert!(output_dir.join("bounded-test7-callgraph.txt").exists());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn panic_paths_trace_callers_of_panic_sites() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-panic-paths");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--panic-paths"]);
+
+    let report_path = output_dir.join("test1-panic-paths.txt");
+    let report = std::fs::read_to_string(&report_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {e}", report_path.display()));
+
+    let branch = report
+        .lines()
+        .find(|line| line.starts_with("- ") && line.contains("panic_example::unreachable_branch"))
+        .expect("the function calling panic!(\"unreachable\") should be a panic path");
+    assert!(
+        branch.contains("path constraints:"),
+        "each chain reports its constraint count"
+    );
+    assert!(
+        report
+            .lines()
+            .any(|line| line.starts_with("- ") && line.contains("panic_example::main")),
+        "transitive callers of panic sites should be listed"
+    );
+}
+
+#[test]
+fn panic_paths_json_lists_callers() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-panic-paths-json");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--panic-paths", "--json-output"]);
+
+    let report = read_json(&output_dir.join("test1-panic-paths.json"));
+    assert!(
+        report.to_string().contains("panic_example::unreachable_branch"),
+        "JSON panic paths should include unreachable_branch"
+    );
+}
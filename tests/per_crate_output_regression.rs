@@ -0,0 +1,60 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+
+fn call_site_keys(entries: &Value) -> BTreeSet<(String, String, u64)> {
+    let mut keys = BTreeSet::new();
+    for entry in entries.as_array().expect("call graph should be an array") {
+        let caller = entry["caller"]["name"].as_str().expect("caller name").to_owned();
+        for callee in entry["callee"].as_array().expect("callee should be an array") {
+            keys.insert((
+                caller.clone(),
+                callee["name"].as_str().expect("callee name").to_owned(),
+                callee["constraint_depth"].as_u64().expect("constraint depth"),
+            ));
+        }
+    }
+    keys
+}
+
+#[test]
+fn per_crate_outputs_partition_the_combined_call_graph() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-per-crate-output");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--per-crate-output"]);
+
+    let combined = call_site_keys(&read_json(&output_dir.join("callgraph.json")));
+    assert!(!combined.is_empty(), "combined call graph should not be empty");
+
+    let mut union = BTreeSet::new();
+    let mut per_crate_files = 0;
+    for entry in fs::read_dir(&output_dir).expect("failed to read output dir") {
+        let path = entry.expect("failed to read dir entry").path();
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if !file_name.starts_with("test1-") || !file_name.ends_with("-callgraph.json") {
+            continue;
+        }
+
+        let per_crate = read_json(&path);
+        assert_eq!(per_crate["source_crate"].as_str(), Some("test1"));
+        let target_crate = per_crate["target_crate"]
+            .as_str()
+            .expect("target_crate should be a string");
+        assert_eq!(file_name, format!("test1-{target_crate}-callgraph.json"));
+
+        let keys = call_site_keys(&per_crate["call_graph"]);
+        assert!(
+            union.is_disjoint(&keys),
+            "call sites should belong to exactly one per-crate file"
+        );
+        union.extend(keys);
+        per_crate_files += 1;
+    }
+
+    assert!(per_crate_files > 1, "test1 should call into more than one crate");
+    assert_eq!(union, combined);
+}
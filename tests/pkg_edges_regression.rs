@@ -0,0 +1,58 @@
+mod common;
+
+use common::{manifest_path, read_callers_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::collections::BTreeMap;
+
+/// Callers of test11's `accumulate` (same crate) and `std::io::_print` (one crate boundary away)
+/// with their package edges, after `extra_args`
+fn callers_by_target(extra_args: &[&str], prefix: &str) -> BTreeMap<&'static str, Vec<(String, u64)>> {
+    let manifest_path = manifest_path("testdata/test11/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+
+    let mut args = vec!["--find-callers", "accumulate,std::io::_print", "--json-output"];
+    args.extend_from_slice(extra_args);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &args);
+
+    ["accumulate", "std::io::_print"]
+        .into_iter()
+        .map(|target| {
+            let callers = read_callers_json(&output_dir, target)["callers"]
+                .as_array()
+                .expect("callers should be an array")
+                .iter()
+                .map(|caller| {
+                    (
+                        caller["path"].as_str().expect("caller path").to_string(),
+                        caller["package_edges"].as_u64().expect("package_edges"),
+                    )
+                })
+                .collect();
+            (target, callers)
+        })
+        .collect()
+}
+
+fn main_with(package_edges: u64) -> Vec<(String, u64)> {
+    vec![("main".to_string(), package_edges)]
+}
+
+#[test]
+fn package_edges_are_reported_without_filters() {
+    let callers = callers_by_target(&[], "cg4rs-pkg-edges");
+    assert_eq!(callers["accumulate"], main_with(0));
+    assert_eq!(callers["std::io::_print"], main_with(1));
+}
+
+#[test]
+fn max_pkg_edges_zero_keeps_callers_in_the_same_crate() {
+    let callers = callers_by_target(&["--max-pkg-edges", "0"], "cg4rs-max-pkg-edges");
+    assert_eq!(callers["accumulate"], main_with(0));
+    assert!(callers["std::io::_print"].is_empty(), "{callers:?}");
+}
+
+#[test]
+fn min_pkg_edges_one_requires_crossing_a_crate_boundary() {
+    let callers = callers_by_target(&["--min-pkg-edges", "1"], "cg4rs-min-pkg-edges");
+    assert!(callers["accumulate"].is_empty(), "{callers:?}");
+    assert_eq!(callers["std::io::_print"], main_with(1));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+use std::fs;
+
+/// Callees of the first caller with the given def path
+fn callees_of<'a>(call_graph: &'a Value, caller_path: &str) -> &'a Vec<Value> {
+    call_graph["call_graph"]
+        .as_array()
+        .expect("call_graph should be an array")
+        .iter()
+        .find(|entry| entry["caller"]["path"].as_str() == Some(caller_path))
+        .unwrap_or_else(|| panic!("{caller_path} should appear as a caller"))["callee"]
+        .as_array()
+        .expect("callee should be an array")
+}
+
+#[test]
+fn proc_macros_are_analyzed_and_tagged_when_requested() {
+    let manifest_path = manifest_path("testdata/test8/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-proc-macro");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--analyze-proc-macros", "--json-output", "--per-crate-output"],
+    );
+
+    let derive = read_json(&output_dir.join("test8_derive-test8_derive-callgraph.json"));
+    let callees = callees_of(&derive, "derive_describe");
+    for helper in ["type_name", "render_impl"] {
+        let callee = callees
+            .iter()
+            .find(|callee| callee["path"].as_str() == Some(helper))
+            .unwrap_or_else(|| panic!("derive_describe should call {helper}"));
+        assert_eq!(callee["is_proc_macro"].as_bool(), Some(true));
+    }
+
+    let app = read_json(&output_dir.join("test8-test8-callgraph.json"));
+    assert!(
+        callees_of(&app, "main")
+            .iter()
+            .all(|callee| callee["is_proc_macro"].as_bool() == Some(false)),
+        "calls of the binary should not be tagged as proc macro calls"
+    );
+}
+
+#[test]
+fn dependency_proc_macros_are_skipped_by_default() {
+    let manifest_path = manifest_path("testdata/test8/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-proc-macro-default");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &[]);
+
+    assert!(output_dir.join("test8-callgraph.txt").exists());
+    assert!(
+        !fs::read_dir(&output_dir)
+            .expect("failed to read output dir")
+            .any(|entry| entry
+                .expect("failed to read dir entry")
+                .file_name()
+                .to_string_lossy()
+                .starts_with("test8_derive")),
+        "proc-macro crates of dependencies should not be analyzed without --analyze-proc-macros"
+    );
+}
+
+#[test]
+fn workspace_proc_macros_are_analyzed_by_default() {
+    let manifest_path = manifest_path("testdata/test8/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-proc-macro-workspace");
+
+    // With `--workspace`, the proc-macro member is a primary package
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--per-crate-output", "--", "--workspace"],
+    );
+
+    let derive = read_json(&output_dir.join("test8_derive-test8_derive-callgraph.json"));
+    let callees = callees_of(&derive, "derive_describe");
+    assert!(
+        callees
+            .iter()
+            .any(|callee| callee["path"].as_str() == Some("render_impl")),
+        "derive_describe should call render_impl: {callees:#?}"
+    );
+}
@@ -1,17 +1,13 @@
 mod common;
 
 use common::{
-    manifest_path, read_json, read_public_exposure_json, run_call_cg4rs, run_call_cg4rs_with_args, unique_output_dir,
+    manifest_path, read_json, read_public_exposure_json, run_call_cg4rs, test1_json_output, unique_output_dir,
 };
 
 #[test]
 fn public_exposure_output_is_emitted_even_without_targets() {
-    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
-    let output_dir = unique_output_dir("cg4rs-public-exposure-empty");
-
-    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
-
-    let public_exposure = read_public_exposure_json(&output_dir, "test1");
+    let output_dir = test1_json_output();
+    let public_exposure = read_public_exposure_json(output_dir, "test1");
 
     assert_eq!(public_exposure["crate_name"].as_str(), Some("test1"));
     assert_eq!(public_exposure["public_exposure"]["total_targets"].as_u64(), Some(0));
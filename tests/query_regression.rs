@@ -0,0 +1,90 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, run_cargo_cg4rs, unique_output_dir};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Analyze test7 once with `--json-output` and return its callgraph.json
+fn test7_callgraph(prefix: &str) -> PathBuf {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir(prefix);
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+    output_dir.join("callgraph.json")
+}
+
+fn query_json(args: &[&str]) -> Value {
+    let mut query_args = vec!["query"];
+    query_args.extend_from_slice(args);
+    query_args.push("--json");
+    let output = run_cargo_cg4rs(&query_args);
+    assert!(
+        output.status.success(),
+        "cargo cg4rs query should succeed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("query --json should print JSON")
+}
+
+fn names(functions: &Value) -> Vec<&str> {
+    functions
+        .as_array()
+        .expect("query results should be an array")
+        .iter()
+        .map(|func| func["name"].as_str().expect("results should have names"))
+        .collect()
+}
+
+#[test]
+fn query_answers_callers_and_callees_offline() {
+    let callgraph = test7_callgraph("cg4rs-query-callers");
+    let callgraph = callgraph.to_str().expect("output path is not valid utf-8");
+
+    let result = query_json(&[callgraph, "--find-callers", "stage4", "--find-callees", "stage2"]);
+    let callers = names(&result["callers"][0]["callers"]);
+    for caller in ["stage3", "shortcut", "stage2", "stage1", "main"] {
+        assert!(callers.contains(&caller), "{caller} should call stage4: {result:#}");
+    }
+    assert!(!callers.contains(&"countdown"), "{result:#}");
+    assert_eq!(names(&result["callees"][0]["callees"]), ["stage3", "stage4"]);
+}
+
+#[test]
+fn query_finds_paths_and_cycles() {
+    let callgraph = test7_callgraph("cg4rs-query-paths");
+    let callgraph = callgraph.to_str().expect("output path is not valid utf-8");
+
+    let result = query_json(&[callgraph, "--path-from", "main", "--path-to", "stage4", "--cycles"]);
+    assert_eq!(
+        result["path"]["path"],
+        serde_json::json!(["main", "shortcut", "stage4"])
+    );
+    assert_eq!(result["cycles"], serde_json::json!([["countdown"]]));
+
+    let output = run_cargo_cg4rs(&["query", callgraph, "--path-from", "stage4", "--path-to", "main"]);
+    assert!(output.status.success(), "cargo cg4rs query should succeed");
+    let text = String::from_utf8(output.stdout).expect("text output should be utf-8");
+    assert!(text.contains("No path from stage4 to main"), "{text}");
+}
+
+#[test]
+fn query_checks_direct_and_transitive_calls() {
+    let callgraph = test7_callgraph("cg4rs-query-check");
+    let callgraph = callgraph.to_str().expect("output path is not valid utf-8");
+
+    let result = query_json(&[callgraph, "--query", "main ->* stage4"]);
+    assert_eq!(result["query"]["holds"], true, "{result:#}");
+    assert_eq!(
+        result["query"]["witness"],
+        serde_json::json!(["main", "shortcut", "stage4"])
+    );
+
+    let output = run_cargo_cg4rs(&["query", callgraph, "--query", "main -> stage4"]);
+    assert_eq!(output.status.code(), Some(2), "main only calls stage4 indirectly");
+    let text = String::from_utf8(output.stdout).expect("text output should be utf-8");
+    assert!(text.contains("main -> stage4: no"), "{text}");
+
+    let output = run_cargo_cg4rs(&["query", callgraph, "--query", "shortcut -> stage4"]);
+    assert!(output.status.success(), "shortcut calls stage4 directly");
+    let text = String::from_utf8(output.stdout).expect("text output should be utf-8");
+    assert!(text.contains("shortcut -> stage4: yes"), "{text}");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::{all_callees, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use std::collections::HashSet;
+
+#[test]
+fn reachable_from_keeps_only_the_forward_subgraph() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-reachable-from");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--reachable-from", "panic_example::main"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let entries = callgraph.as_array().expect("callgraph.json should be an array");
+    let callers: HashSet<&str> = entries
+        .iter()
+        .map(|entry| {
+            entry["caller"]["path"]
+                .as_str()
+                .expect("caller path should be a string")
+        })
+        .collect();
+    let callees: HashSet<&str> = all_callees(&callgraph)
+        .into_iter()
+        .map(|call| call["path"].as_str().expect("callee path should be a string"))
+        .collect();
+
+    // The seed and its transitive callees are kept
+    assert!(callers.contains("panic_example::main"), "got callers {callers:?}");
+    assert!(callers.contains("panic_example::lookup"), "got callers {callers:?}");
+    assert!(
+        callees.contains("panic_example::guarded_sink"),
+        "got callees {callees:?}"
+    );
+
+    // Callers of the seed and unrelated functions are dropped
+    assert!(!callers.contains("main"), "got callers {callers:?}");
+    assert!(
+        !callers.iter().any(|caller| caller.starts_with("InventoryManager")),
+        "got callers {callers:?}"
+    );
+
+    // Only edges among reachable functions: every caller besides the seed is also a callee
+    for caller in callers.iter().filter(|&&caller| caller != "panic_example::main") {
+        assert!(callees.contains(caller), "{caller} is not reachable from the seed");
+    }
+}
@@ -14,5 +14,5 @@ fn test_resolution_module_integration() {
     run_call_cg4rs(&manifest_path, &output_dir, "fn_pointer_example::add");
 
     // Verify output files were created
-    assert!(output_dir.join("callers-fn_pointer_example::add.json").exists());
+    assert!(output_dir.join("callers-fn_pointer_example-add.json").exists());
 }
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{
+    all_callees, callees_of, manifest_path, read_json, run_call_cg4rs_with_args, test1_json_output, unique_output_dir,
+};
+
+#[test]
+fn callees_record_how_they_were_resolved() {
+    let callgraph = read_json(&test1_json_output().join("callgraph.json"));
+    for callee in all_callees(&callgraph) {
+        let resolution = callee["resolution"]
+            .as_str()
+            .expect("every callee should have a resolution");
+        assert!(
+            ["exact", "fnptr_candidate", "trivial", "unresolved"].contains(&resolution),
+            "unexpected resolution {resolution}"
+        );
+    }
+
+    let describe = callees_of(&callgraph, "dedup_location_example::main")
+        .into_iter()
+        .find(|callee| callee["path"].as_str() == Some("dedup_location_example::describe"))
+        .expect("main should call describe");
+    assert_eq!(describe["resolution"].as_str(), Some("exact"));
+
+    let slot_reader = callees_of(&callgraph, "fn_pointer_example::invoke_slot_reader")
+        .into_iter()
+        .find(|callee| callee["path"].as_str() == Some("fn_pointer_example::generic_slot_reader"))
+        .expect("invoke_slot_reader should call generic_slot_reader through a function pointer");
+    assert!(
+        matches!(slot_reader["resolution"].as_str(), Some("exact" | "fnptr_candidate")),
+        "function-pointer edges are traced or signature-matched"
+    );
+}
+
+#[test]
+fn statistics_break_down_edges_by_resolution() {
+    let stats = read_json(&test1_json_output().join("test1-callgraph-stats.json"));
+    let edges = stats["resolution"]["edges"]
+        .as_object()
+        .expect("statistics should count edges by resolution");
+    let counted: u64 = edges.values().map(|count| count.as_u64().expect("edge count")).sum();
+    assert_eq!(Some(counted), stats["total_call_sites"].as_u64());
+    assert!(edges["exact"].as_u64().expect("exact edges") > 0);
+
+    // Candidate edges always come from some candidate call site
+    let candidate_edges = edges["fnptr_candidate"].as_u64().expect("fnptr_candidate edges");
+    let candidate_call_sites = stats["resolution"]["candidate_call_sites"]
+        .as_u64()
+        .expect("candidate_call_sites");
+    assert!(candidate_edges == 0 || candidate_call_sites > 0, "{stats}");
+}
+
+#[test]
+fn statistics_count_candidate_edges_and_call_sites_exactly() {
+    let manifest_path = manifest_path("testdata/test13/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-resolution-exact-counts");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let mut candidates: Vec<&str> = callees_of(&callgraph, "apply")
+        .into_iter()
+        .filter(|callee| callee["resolution"].as_str() == Some("fnptr_candidate"))
+        .map(|callee| callee["path"].as_str().expect("callee path"))
+        .collect();
+    candidates.sort();
+    assert_eq!(candidates, ["flip", "mask"]);
+
+    let stats = read_json(&output_dir.join("test13-callgraph-stats.json"));
+    assert_eq!(
+        stats["resolution"]["edges"]["fnptr_candidate"].as_u64(),
+        Some(2),
+        "{stats}"
+    );
+    assert_eq!(stats["resolution"]["candidate_call_sites"].as_u64(), Some(1), "{stats}");
+}
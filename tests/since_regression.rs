@@ -0,0 +1,157 @@
+mod common;
+
+use common::{read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=cg4rs", "-c", "user.email=cg4rs@example.com"])
+        .args(args)
+        .status()
+        .expect("failed to run git");
+    assert!(status.success(), "git {args:?} should succeed");
+}
+
+/// A small crate in a fresh git repository, where `a::changed` is modified after the first commit
+///
+/// `b::unused` is not called from anywhere, so it is only analyzed as a seed of a full analysis.
+fn changed_crate() -> std::path::PathBuf {
+    let dir = unique_output_dir("cg4rs-since-crate");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"since_example\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/main.rs"),
+        "mod a;\nmod b;\n\nfn main() {\n    println!(\"{} {}\", a::changed(1), b::stable(2));\n}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("src/a.rs"), "pub fn changed(x: u32) -> u32 {\n    x + 1\n}\n").unwrap();
+    fs::write(
+        dir.join("src/b.rs"),
+        "pub fn stable(x: u32) -> u32 {\n    helper(x)\n}\n\npub fn unused(x: u32) -> u32 {\n    helper(x) + 1\n}\n\nfn helper(x: u32) -> u32 {\n    x * 2\n}\n",
+    )
+    .unwrap();
+
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+    fs::write(dir.join("src/a.rs"), "pub fn changed(x: u32) -> u32 {\n    x + 2\n}\n").unwrap();
+    dir
+}
+
+fn callers(callgraph: &Value) -> Vec<&str> {
+    callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter_map(|entry| entry["caller"]["path"].as_str())
+        .collect()
+}
+
+#[test]
+fn since_seeds_changed_functions_and_their_callers() {
+    let crate_dir = changed_crate();
+    let output_dir = unique_output_dir("cg4rs-since");
+
+    run_call_cg4rs_with_args(
+        &crate_dir.join("Cargo.toml"),
+        &output_dir,
+        &["--json-output", "--since", "HEAD"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callers = callers(&callgraph);
+    assert!(
+        callers.contains(&"a::changed"),
+        "changed function should be analyzed, got {callers:?}"
+    );
+    assert!(
+        callers.contains(&"main"),
+        "callers of changed functions should be analyzed, got {callers:?}"
+    );
+    assert!(
+        !callers.contains(&"b::unused"),
+        "unchanged functions off the impact path should not be analyzed, got {callers:?}"
+    );
+}
+
+#[test]
+fn since_falls_back_to_full_analysis_on_bad_ref() {
+    let crate_dir = changed_crate();
+    let output_dir = unique_output_dir("cg4rs-since-bad-ref");
+
+    run_call_cg4rs_with_args(
+        &crate_dir.join("Cargo.toml"),
+        &output_dir,
+        &["--json-output", "--since", "no-such-ref"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert!(callers(&callgraph).contains(&"b::unused"));
+}
+
+/// A workspace of `since_root` and its member `since_member` in a fresh git repository
+fn workspace() -> std::path::PathBuf {
+    let dir = unique_output_dir("cg4rs-since-workspace");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("member/src")).unwrap();
+    fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"since_root\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\nmembers = [\"member\"]\n",
+    )
+    .unwrap();
+    fs::write(dir.join("src/main.rs"), "fn main() {\n    println!(\"root\");\n}\n").unwrap();
+    fs::write(
+        dir.join("member/Cargo.toml"),
+        "[package]\nname = \"since_member\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("member/src/main.rs"),
+        "fn main() {\n    println!(\"member\");\n}\n",
+    )
+    .unwrap();
+
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+    dir
+}
+
+#[test]
+fn since_only_checks_packages_with_changed_files() {
+    let workspace_dir = workspace();
+    fs::write(
+        workspace_dir.join("member/src/main.rs"),
+        "fn main() {\n    println!(\"changed\");\n}\n",
+    )
+    .unwrap();
+    let output_dir = unique_output_dir("cg4rs-since-packages");
+
+    run_call_cg4rs_with_args(&workspace_dir.join("Cargo.toml"), &output_dir, &["--since", "HEAD"]);
+
+    assert!(output_dir.join("since_member-callgraph.txt").exists());
+    assert!(
+        !output_dir.join("since_root-callgraph.txt").exists(),
+        "the package around the changed member should not be analyzed"
+    );
+}
+
+#[test]
+fn since_without_changed_sources_analyzes_nothing() {
+    let workspace_dir = workspace();
+    let output_dir = unique_output_dir("cg4rs-since-unchanged");
+
+    run_call_cg4rs_with_args(&workspace_dir.join("Cargo.toml"), &output_dir, &["--since", "HEAD"]);
+
+    assert!(!output_dir.join("since_member-callgraph.txt").exists());
+    assert!(!output_dir.join("since_root-callgraph.txt").exists());
+}
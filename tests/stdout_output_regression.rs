@@ -0,0 +1,26 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_capturing, unique_output_dir};
+use serde_json::Value;
+
+#[test]
+fn json_output_goes_to_stdout() {
+    let manifest_path = manifest_path("testdata/test6/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-stdout");
+
+    let output = run_call_cg4rs_capturing(&manifest_path, &output_dir, &["--json-output", "--stdout"]);
+
+    // Logs go to stderr, so stdout is exactly the call graph
+    let callgraph: Value = serde_json::from_slice(&output.stdout).expect("stdout should be the JSON call graph");
+    assert!(
+        callgraph
+            .as_array()
+            .expect("call graph should be an array")
+            .iter()
+            .any(|entry| entry["caller"]["path"].as_str() == Some("main")),
+        "got {callgraph}"
+    );
+
+    assert!(!output_dir.join("callgraph.json").exists());
+    assert!(!output_dir.join("test6-callgraph-stats.json").exists());
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{manifest_path, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+#[test]
+fn direct_callers_are_streamed_as_json_lines() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-stream-callers");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--find-callers", "stage4", "--stream-callers"],
+    );
+
+    let stream = std::fs::read_to_string(output_dir.join("callers-stream-test7.jsonl"))
+        .expect("callers stream should be written");
+    let lines: Vec<Value> = stream
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line should be a JSON object"))
+        .collect();
+
+    let callers: BTreeSet<&str> = lines
+        .iter()
+        .map(|line| {
+            assert_eq!(line["target"], "stage4");
+            line["caller"].as_str().expect("caller path")
+        })
+        .collect();
+    assert_eq!(callers, BTreeSet::from(["shortcut", "stage3"]), "{stream}");
+    assert_eq!(lines.len(), callers.len(), "each caller is streamed once: {stream}");
+
+    // Lines are appended as the analysis advances, so their steps never go back
+    let steps: Vec<u64> = lines
+        .iter()
+        .map(|line| line["discovered_at_step"].as_u64().expect("discovered_at_step"))
+        .collect();
+    assert!(steps.iter().all(|step| *step > 0), "{steps:?}");
+    assert!(steps.windows(2).all(|pair| pair[0] <= pair[1]), "{steps:?}");
+}
+
+#[test]
+fn callers_are_not_streamed_by_default() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-no-stream-callers");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--find-callers", "stage4"]);
+
+    assert!(!output_dir.join("callers-stream-test7.jsonl").exists());
+}
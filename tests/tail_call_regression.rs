@@ -0,0 +1,68 @@
+mod common;
+
+use common::{callee_paths, callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+#[test]
+fn tail_calls_are_call_sites() {
+    let manifest_path = manifest_path("testdata/test9/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-tail-call");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let callees = callee_paths(&callgraph, "sum_to");
+    assert!(
+        callees.contains(&"sum_to".to_string()),
+        "tail recursion is missing: {callees:?}"
+    );
+    assert!(
+        callees.contains(&"finish".to_string()),
+        "tail call to finish is missing: {callees:?}"
+    );
+    assert_eq!(
+        callee_paths(&callgraph, "main"),
+        ["fail_via_tail", "run_picked", "sum_to"]
+    );
+}
+
+/// The callee entries of `callee_path` called by `caller_path` in callgraph.json
+fn callee_entries<'a>(callgraph: &'a Value, caller_path: &str, callee_path: &str) -> Vec<&'a Value> {
+    callees_of(callgraph, caller_path)
+        .into_iter()
+        .filter(|callee| callee["path"].as_str() == Some(callee_path))
+        .collect()
+}
+
+#[test]
+fn function_pointers_returned_by_tail_calls_fall_back_to_signature_matching() {
+    let manifest_path = manifest_path("testdata/test9/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-tail-call-return");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    // The return summary of `pick_via_tail` gives up on its tail call, so the pointer is
+    // resolved by signature instead of being traced to `pick_finish`'s `finish`
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let finish = callee_entries(&callgraph, "run_picked", "finish");
+    assert_eq!(finish.len(), 1, "run_picked should call finish: {callgraph}");
+    assert_eq!(finish[0]["resolution"].as_str(), Some("fnptr_candidate"));
+}
+
+#[test]
+fn tail_called_panicking_functions_may_panic() {
+    let manifest_path = manifest_path("testdata/test9/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-tail-call-panics");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--annotate-panics"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let fail = callee_entries(&callgraph, "fail_via_tail", "fail");
+    assert_eq!(fail.len(), 1, "fail_via_tail should tail-call fail: {callgraph}");
+    assert_eq!(fail[0]["may_panic"].as_bool(), Some(true));
+
+    // Only the callee's own body is inspected, and `fail_via_tail` only tail-calls
+    let fail_via_tail = callee_entries(&callgraph, "main", "fail_via_tail");
+    assert_eq!(fail_via_tail.len(), 1, "main should call fail_via_tail: {callgraph}");
+    assert_eq!(fail_via_tail[0]["may_panic"].as_bool(), Some(false));
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use common::{all_callees, all_functions, callees_of, read_json, test1_json_output};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn callgraph() -> Value {
+    read_json(&test1_json_output().join("callgraph.json"))
+}
+
+fn find_callee<'a>(callgraph: &'a Value, callee: &str) -> &'a Value {
+    all_callees(callgraph)
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some(callee))
+        .unwrap_or_else(|| panic!("{callee} should be called"))
+}
+
+#[test]
+fn call_sites_record_arg_count() {
+    let callgraph = callgraph();
+
+    let two_args = find_callee(&callgraph, "panic_example::checked_divide");
+    assert_eq!(two_args["arg_count"].as_u64(), Some(2));
+    assert_eq!(two_args["declared_arg_count"].as_u64(), Some(2));
+    assert_eq!(two_args["arg_count_mismatch"].as_bool(), Some(false));
+
+    let one_arg = find_callee(&callgraph, "ffi_example::rust_helper");
+    assert_eq!(one_arg["arg_count"].as_u64(), Some(1));
+
+    let no_args = find_callee(&callgraph, "InventoryManager::create_example");
+    assert_eq!(no_args["arg_count"].as_u64(), Some(0));
+}
+
+#[test]
+fn fn_trait_calls_are_not_reported_as_mismatches() {
+    let callgraph = callgraph();
+
+    // `strategy(item)` with `F = fn unit_price(&Item)` passes `(strategy, (item,))`
+    let unit_price = callees_of(&callgraph, "strategy_example::value_with_strategy")
+        .into_iter()
+        .find(|call| call["path"].as_str() == Some("strategy_example::unit_price"))
+        .expect("value_with_strategy should call unit_price through Fn");
+    assert_eq!(unit_price["abi"].as_str(), Some("rust-call"), "{unit_price}");
+    assert_eq!(unit_price["declared_arg_count"].as_u64(), Some(1), "{unit_price}");
+    assert!(unit_price.get("arg_count_mismatch").is_none(), "{unit_price}");
+}
+
+#[test]
+fn assoc_fn_callees_report_impl_and_trait() {
+    let callgraph = callgraph();
+
+    let trait_impl = find_callee(&callgraph, "<Electronics as Product>::price");
+    assert_eq!(trait_impl["trait"].as_str(), Some("Product"));
+    assert_eq!(trait_impl["impl_self_ty"].as_str(), Some("Electronics"));
+
+    let inherent = find_callee(&callgraph, "DataStore::<T>::total_value");
+    assert!(inherent["trait"].is_null(), "inherent impls have no trait");
+    assert!(
+        inherent["impl_self_ty"]
+            .as_str()
+            .is_some_and(|self_ty| self_ty.starts_with("DataStore<")),
+        "got {:?}",
+        inherent["impl_self_ty"]
+    );
+
+    let free_fn = find_callee(&callgraph, "panic_example::checked_divide");
+    assert!(
+        free_fn.get("trait").is_none(),
+        "free functions have no impl information"
+    );
+}
+
+#[test]
+fn reified_callee_collapses_with_direct_callee() {
+    let callgraph = callgraph();
+    let callees = callees_of(&callgraph, "track_caller_example::main");
+    assert!(!callees.is_empty(), "track_caller_example::main should have callees");
+
+    // The direct call and the call through the fn pointer reach the same function node
+    let located: Vec<_> = callees
+        .iter()
+        .filter(|call| call["path"].as_str() == Some("track_caller_example::located"))
+        .collect();
+    assert_eq!(located.len(), 1, "got {callees:#?}");
+}
+
+#[test]
+fn impl_trait_return_resolves_to_concrete_next() {
+    let callgraph = callgraph();
+    let callees = callees_of(&callgraph, "impl_trait_example::main");
+    assert!(!callees.is_empty(), "impl_trait_example::main should have callees");
+
+    let next = callees
+        .iter()
+        .find(|call| {
+            call["path"]
+                .as_str()
+                .is_some_and(|path| path.contains("Countdown") && path.ends_with("::next"))
+        })
+        .unwrap_or_else(|| panic!("Countdown::next should be called, got {callees:#?}"));
+    assert_eq!(next["resolution"].as_str(), Some("exact"));
+}
+
+#[test]
+fn instances_differing_only_in_lifetimes_are_visited_once() {
+    let callgraph = callgraph();
+    let next_token_entries = callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .filter(|entry| {
+            entry["caller"]["path"]
+                .as_str()
+                .is_some_and(|path| path.starts_with("lifetime_example::Tokenizer") && path.ends_with("::next_token"))
+        })
+        .count();
+
+    assert_eq!(
+        next_token_entries, 1,
+        "Tokenizer::next_token is seeded and called twice, but should be analyzed as a single instance"
+    );
+}
+
+#[test]
+fn path_hashes_come_with_resolved_flag() {
+    let callgraph = callgraph();
+    let main = callgraph
+        .as_array()
+        .expect("callgraph.json should be an array")
+        .iter()
+        .find(|entry| entry["caller"]["path"].as_str() == Some("main"))
+        .expect("main should be a caller");
+    assert_eq!(main["caller"]["resolved"].as_bool(), Some(true));
+
+    let mut hashes: HashMap<&str, &str> = HashMap::new();
+    let mut unresolved = 0;
+    for function in all_functions(&callgraph) {
+        let path = function["path"].as_str().expect("path should be a string");
+        let hash = function["path_hash"].as_str().expect("path_hash should be a string");
+        assert_eq!(hash.len(), 32, "{path}: {hash}");
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()), "{path}: {hash}");
+        // Resolved and unresolved references to a def share its hash
+        assert_eq!(*hashes.entry(path).or_insert(hash), hash, "{path}");
+        if !function["resolved"].as_bool().expect("resolved should be a bool") {
+            unresolved += 1;
+        }
+    }
+    // Function pointer candidates are unresolved references
+    assert!(unresolved > 0, "expected unresolved callees");
+}
+
+/// Callees of every analyzed instance of `caller`, which should have some
+fn nonempty_callees_of<'a>(callgraph: &'a Value, caller: &str) -> Vec<&'a Value> {
+    let callees = callees_of(callgraph, caller);
+    assert!(!callees.is_empty(), "{caller} should have callees");
+    callees
+}
+
+fn assert_calls_exact(callees: &[&Value], expected: &str) {
+    let call = callees
+        .iter()
+        .find(|call| call["path"].as_str() == Some(expected))
+        .unwrap_or_else(|| panic!("{expected} should be called, got {callees:#?}"));
+    assert_eq!(call["resolution"].as_str(), Some("exact"), "{expected}");
+}
+
+fn assert_no_fn_trait_methods(callees: &[&Value]) {
+    for call in callees {
+        let path = call["path"].as_str().expect("path should be a string");
+        assert!(
+            !path.ends_with("::call") && !path.ends_with("::call_mut") && !path.ends_with("::call_once"),
+            "Fn trait call should resolve to the callable body, got {path}"
+        );
+    }
+}
+
+#[test]
+fn fn_trait_bound_calls_resolve_to_callable_bodies() {
+    let callgraph = callgraph();
+
+    // `Fn::call` with a closure and with a fn item
+    let strategy = nonempty_callees_of(&callgraph, "strategy_example::value_with_strategy");
+    assert_calls_exact(&strategy, "strategy_example::main::{closure#0}");
+    assert_calls_exact(&strategy, "strategy_example::unit_price");
+    assert_no_fn_trait_methods(&strategy);
+
+    // `FnMut::call_mut` with a capturing closure
+    let visit = nonempty_callees_of(&callgraph, "strategy_example::visit_items");
+    assert_calls_exact(&visit, "strategy_example::main::{closure#1}");
+    assert_no_fn_trait_methods(&visit);
+
+    // `FnOnce::call_once` with a `Fn` closure and a fn item
+    let finish = nonempty_callees_of(&callgraph, "strategy_example::finish_with");
+    assert_calls_exact(&finish, "strategy_example::main::{closure#2}");
+    assert_calls_exact(&finish, "strategy_example::round_total");
+    assert_no_fn_trait_methods(&finish);
+}
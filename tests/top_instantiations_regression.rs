@@ -0,0 +1,50 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn top_instantiations_counts_distinct_monomorphizations() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-top-instantiations");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--top-instantiations", "50"],
+    );
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    let top = stats["top_instantiations"]
+        .as_array()
+        .expect("top_instantiations should be an array");
+    let describe = top
+        .iter()
+        .find(|entry| entry["path"].as_str() == Some("generic_example::describe"))
+        .expect("describe should be reported");
+    assert_eq!(describe["instantiations"].as_u64(), Some(3));
+
+    let report = std::fs::read_to_string(output_dir.join("test1-top-instantiations.txt"))
+        .expect("top instantiations report should be written");
+    assert!(
+        report.contains("generic_example::describe -> 3"),
+        "got report:\n{report}"
+    );
+}
+
+#[test]
+fn top_instantiations_limits_the_list() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-top-instantiations-limit");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--top-instantiations", "1"],
+    );
+
+    let stats = read_json(&output_dir.join("test1-callgraph-stats.json"));
+    let top = stats["top_instantiations"]
+        .as_array()
+        .expect("top_instantiations should be an array");
+    assert_eq!(top.len(), 1);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{callee_paths, callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+#[test]
+fn transposed_json_lists_the_callers_of_each_function() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-transpose-json");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output", "--transpose"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    assert_eq!(callee_paths(&callgraph, "stage4"), ["shortcut", "stage3"]);
+    assert_eq!(callee_paths(&callgraph, "stage1"), ["main"]);
+    assert!(callee_paths(&callgraph, "main").is_empty(), "nothing calls main");
+}
+
+#[test]
+fn transposed_dot_edges_point_from_callee_to_caller() {
+    let manifest_path = manifest_path("testdata/test7/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-transpose-dot");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--format", "dot", "--transpose"]);
+
+    let dot = std::fs::read_to_string(output_dir.join("test7-callgraph.dot")).expect("DOT output should be written");
+    assert!(dot.contains("\"stage4\" -> \"stage3\""), "{dot}");
+    assert!(dot.contains("\"stage1\" -> \"main\""), "{dot}");
+    assert!(!dot.contains("\"main\" -> \"stage1\""), "{dot}");
+}
+
+#[test]
+fn transposed_json_keeps_callee_attributes_on_the_original_callee() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-transpose-attributes");
+
+    run_call_cg4rs_with_args(
+        &manifest_path,
+        &output_dir,
+        &["--json-output", "--transpose", "--annotate-panics"],
+    );
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+    let caller_of = |callee: &str| -> Value {
+        callees_of(&callgraph, callee)
+            .into_iter()
+            .find(|call| call["path"].as_str() == Some("panic_example::main"))
+            .unwrap_or_else(|| panic!("{callee} should be listed with its caller panic_example::main"))
+            .clone()
+    };
+
+    // The entries list `main`, but the call attributes describe the function it calls
+    let divide = caller_of("panic_example::checked_divide");
+    assert_eq!(divide["may_panic"].as_bool(), Some(true), "{divide}");
+    assert_eq!(divide["declared_arg_count"].as_u64(), Some(2), "{divide}");
+    assert_eq!(divide["arg_count_mismatch"].as_bool(), Some(false), "{divide}");
+
+    let sum = caller_of("panic_example::wrapping_sum");
+    assert_eq!(sum["may_panic"].as_bool(), Some(false), "{sum}");
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{callees_of, manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+use serde_json::Value;
+
+fn default_callee<'a>(callgraph: &'a Value, caller: &str, self_ty: &str) -> &'a Value {
+    callees_of(callgraph, caller)
+        .into_iter()
+        .find(|callee| {
+            let path = callee["path"].as_str().unwrap_or_default();
+            path.contains(self_ty) && path.ends_with("::default")
+        })
+        .unwrap_or_else(|| panic!("{caller} should call {self_ty}'s Default impl"))
+}
+
+#[test]
+fn ufcs_default_on_a_concrete_generic_type_resolves_to_its_instance() {
+    let manifest_path = manifest_path("testdata/test12/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-ufcs-resolution");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--json-output"]);
+
+    let callgraph = read_json(&output_dir.join("callgraph.json"));
+
+    let map_default = default_callee(&callgraph, "empty_scores", "HashMap");
+    assert_eq!(map_default["resolved"], true, "{map_default}");
+    assert_eq!(map_default["resolution"], "exact", "{map_default}");
+    let name = map_default["name"].as_str().expect("callee name");
+    assert!(name.contains("String") && name.contains("u32"), "{name}");
+
+    let vec_default = default_callee(&callgraph, "empty_list", "Vec");
+    assert_eq!(vec_default["resolved"], true, "{vec_default}");
+    assert_eq!(vec_default["resolution"], "exact", "{vec_default}");
+}
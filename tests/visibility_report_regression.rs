@@ -0,0 +1,39 @@
+mod common;
+
+use common::{manifest_path, read_json, run_call_cg4rs_with_args, unique_output_dir};
+
+#[test]
+fn visibility_report_covers_all_categories() {
+    let manifest_path = manifest_path("testdata/test1/Cargo.toml");
+    let output_dir = unique_output_dir("cg4rs-visibility-report");
+
+    run_call_cg4rs_with_args(&manifest_path, &output_dir, &["--summarize-by-visibility"]);
+
+    let report = read_json(&output_dir.join("test1-visibility-report.json"));
+    assert_eq!(report["crate_name"].as_str(), Some("test1"));
+    let calls = &report["calls"];
+    let count = |key: &str| {
+        calls[key]
+            .as_u64()
+            .unwrap_or_else(|| panic!("report should have {key}"))
+    };
+
+    // visibility_example::main -> inner::public_api / inner::crate_helper
+    assert!(count("cross_module_pub_calls") >= 1);
+    assert!(count("cross_module_pub_crate_calls") >= 1);
+    // inner::public_api -> inner::private_detail
+    assert!(count("same_module_private_calls") >= 1);
+    // println! and friends call into std
+    assert!(count("cross_crate_pub_calls") >= 1);
+
+    let text_path = output_dir.join("test1-visibility-report.txt");
+    let text =
+        std::fs::read_to_string(&text_path).unwrap_or_else(|e| panic!("failed to read {}: {e}", text_path.display()));
+    for key in [
+        "cross_module_pub_calls",
+        "cross_module_pub_crate_calls",
+        "same_module_private_calls",
+    ] {
+        assert!(text.contains(key), "text report should list {key}");
+    }
+}